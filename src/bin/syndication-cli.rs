@@ -0,0 +1,716 @@
+//! Headless command-line interface for syncing, importing and querying NCTS terminologies.
+//! Uses the same database and indexes as the desktop app, so the two cannot run at the same time.
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use syndication_core::queries::{Acceptability, DesignationKind, TerminologyQueries};
+use syndication_core::storage::ContentFamily;
+use syndication_core::sync::{
+    import_latest_job, import_local_file, promote_release, storage_stats, sync_latest, SYNDICATED_TYPES,
+};
+use syndication_core::{
+    DataPaths, ImportProgress, JobRegistry, NctsClient, ProgressReporter, SyncProgress, TerminologySearch,
+    TerminologyStorage, TerminologyType, TokenManager,
+};
+
+#[derive(Parser)]
+#[command(name = "syndication-cli", version, about = "Sync, import and query NCTS terminologies without the GUI")]
+struct Cli {
+    /// Print results as JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect the NCTS syndication feed
+    Feed {
+        #[command(subcommand)]
+        command: FeedCommand,
+    },
+    /// Download the latest version of a terminology (snomed, snomed-full, snomed-delta, amt, valuesets or all)
+    Sync { terminology_type: String },
+    /// Import the latest downloaded version of a terminology (snomed, snomed-full, snomed-delta, amt, valuesets or all)
+    /// Ctrl-C cancels the import; a SNOMED release resumes from its last committed batch when imported again
+    Import { terminology_type: String },
+    /// Import a release from a local RF2 ZIP, AMT CSV or FHIR bundle (air-gapped mode)
+    /// The type and version are read from the file, which is copied into the data directory first
+    ImportFile { path: PathBuf },
+    /// Full-text search across imported terminologies
+    Search {
+        query: String,
+        /// Terminology to search (snomed, amt, valuesets or all)
+        #[arg(long = "type", default_value = "all")]
+        terminology_type: String,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Search SNOMED as it stood on a date (YYYY-MM-DD); needs an imported snomed-full release
+        #[arg(long)]
+        as_of: Option<String>,
+        /// Search an imported release other than the live one (version id, version or version URI)
+        #[arg(long)]
+        release: Option<String>,
+    },
+    /// Look up a code with its synonyms
+    Lookup {
+        code: String,
+        /// Code system (snomed, amt or the URL of an imported FHIR CodeSystem)
+        #[arg(long, default_value = "snomed")]
+        system: String,
+        /// Look the SNOMED concept up as it stood on a date (YYYY-MM-DD); needs an imported snomed-full release
+        #[arg(long)]
+        as_of: Option<String>,
+        /// Look up in an imported release other than the live one (version id, version or version URI)
+        #[arg(long)]
+        release: Option<String>,
+    },
+    /// Translate a code through the SNOMED CT-AU complex and extended map refsets (e.g. to ICD-10-AM)
+    Translate {
+        code: String,
+        /// Source system: snomed maps forward; any other system looks the code up as a map target
+        #[arg(long, default_value = "snomed")]
+        system: String,
+        /// Only use one map refset
+        #[arg(long)]
+        refset: Option<String>,
+        /// Translate with an imported release other than the live one (version id, version or version URI)
+        #[arg(long)]
+        release: Option<String>,
+    },
+    /// Expand a ValueSet by canonical URL
+    Expand {
+        url: String,
+        /// Maximum number of concepts to print
+        #[arg(long)]
+        count: Option<usize>,
+        /// Expand from an imported ValueSets release other than the live one
+        #[arg(long)]
+        release: Option<String>,
+    },
+    /// Make an imported release the live one that queries use by default
+    Promote {
+        /// Terminology of the release (snomed, snomed-full, amt or valuesets)
+        terminology_type: String,
+        /// Release to promote (version id, version or version URI)
+        release: String,
+    },
+    /// Show the latest local version of each terminology
+    Status,
+    /// Show record counts, database size and search index size of each imported release
+    Stats,
+    /// List import jobs, newest first, with how each one ended
+    Jobs,
+}
+
+#[derive(Subcommand)]
+enum FeedCommand {
+    /// List the versions published in the feed
+    List {
+        /// Only list one terminology type (snomed, snomed-full, snomed-delta, amt, valuesets or loinc)
+        #[arg(long = "type")]
+        terminology_type: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    let outcome = match data_paths() {
+        Ok(paths) => run(cli, &paths).await,
+        Err(e) => Err(e),
+    };
+    match outcome {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run one command against the data directory, returning false when it completed but reported a failure
+async fn run(cli: Cli, paths: &DataPaths) -> Result<bool> {
+    let json = cli.json;
+
+    match cli.command {
+        Command::Feed {
+            command: FeedCommand::List { terminology_type },
+        } => {
+            let client = ncts_client()?;
+            let types = match &terminology_type {
+                Some(t) => vec![t.as_str()],
+                None => SYNDICATED_TYPES.to_vec(),
+            };
+
+            let mut entries = Vec::new();
+            for t in types {
+                let term_type: TerminologyType = t.parse()?;
+                let feed = client
+                    .fetch_feed(term_type)
+                    .await
+                    .with_context(|| format!("Failed to fetch {} feed", t))?;
+                entries.extend(feed.into_iter().map(|entry| (t, entry)));
+            }
+
+            if json {
+                let entries: Vec<_> = entries.iter().map(|(_, entry)| entry).collect();
+                print_json(&entries)?;
+            } else {
+                for (t, entry) in &entries {
+                    println!(
+                        "{:<10} {:<20} {:<12} {}",
+                        t,
+                        entry.content_item_version.as_deref().or(entry.version.as_deref()).unwrap_or("-"),
+                        entry.effective_date.as_deref().unwrap_or("-"),
+                        entry.title
+                    );
+                }
+            }
+            Ok(true)
+        }
+        Command::Sync { terminology_type } => {
+            let client = ncts_client()?;
+            let storage = open_storage(paths)?;
+            let progress = ConsoleProgress { quiet: json };
+
+            let mut results = Vec::new();
+            for t in expand_types(&terminology_type) {
+                results.push(sync_latest(&client, &storage, t, Some(&progress)).await?);
+            }
+
+            if json {
+                print_json(&results)?;
+            } else {
+                for result in &results {
+                    let status = if result.success { "ok" } else { "FAILED" };
+                    println!(
+                        "{:<10} {:<6} {} {}",
+                        result.terminology_type,
+                        status,
+                        result.latest_version.as_deref().unwrap_or("-"),
+                        result.error.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            Ok(results.iter().all(|r| r.success))
+        }
+        Command::Import { terminology_type } => {
+            let storage = open_storage(paths)?;
+            let searcher = open_searcher(paths)?;
+            let progress = ConsoleProgress { quiet: json };
+            let jobs = Arc::new(JobRegistry::new(&storage)?);
+            let cancel_on_interrupt = cancel_on_interrupt(&jobs);
+
+            let imported: Result<Vec<String>> = async {
+                let mut messages = Vec::new();
+                for t in expand_types(&terminology_type) {
+                    let message = import_latest_job(&storage, &searcher, &jobs, t, Some(&progress), |job_id| {
+                        if !json {
+                            eprintln!("Import job {} started for {}", job_id, t);
+                        }
+                    })
+                    .await?;
+                    if !json {
+                        println!("{}", message);
+                    }
+                    messages.push(message);
+                }
+                Ok(messages)
+            }
+            .await;
+            cancel_on_interrupt.abort();
+            let messages = imported?;
+
+            if json {
+                print_json(&messages)?;
+            }
+            Ok(true)
+        }
+        Command::ImportFile { path } => {
+            let storage = open_storage(paths)?;
+            let searcher = open_searcher(paths)?;
+            let progress = ConsoleProgress { quiet: json };
+            let jobs = Arc::new(JobRegistry::new(&storage)?);
+            let cancel_on_interrupt = cancel_on_interrupt(&jobs);
+
+            let imported = import_local_file(&storage, &searcher, &jobs, &path, Some(&progress), |job_id| {
+                if !json {
+                    eprintln!("Import job {} started for {}", job_id, path.display());
+                }
+            })
+            .await;
+            cancel_on_interrupt.abort();
+            let message = imported?;
+
+            if json {
+                print_json(&message)?;
+            } else {
+                println!("{}", message);
+            }
+            Ok(true)
+        }
+        Command::Search {
+            query,
+            terminology_type,
+            limit,
+            as_of,
+            release,
+        } => {
+            let storage = open_storage(paths)?;
+            let searcher = open_searcher(paths)?;
+            let family = match &release {
+                Some(_) => ContentFamily::of(&terminology_type)
+                    .ok_or_else(|| anyhow!("--release needs a single --type (snomed, amt or valuesets)"))?,
+                None => ContentFamily::Snomed,
+            };
+            let storage = storage.select_release(family, release.as_deref())?;
+            let searcher = searcher.for_view(&storage)?;
+
+            let results = match (terminology_type.as_str(), as_of) {
+                ("snomed", Some(as_of)) => {
+                    TerminologyQueries::search_snomed_as_of(&searcher, &storage, &query, limit, &as_of)?
+                }
+                (other, Some(_)) => {
+                    return Err(anyhow!("--as-of is only supported with --type snomed, not {}", other))
+                }
+                (terminology_type, None) => match terminology_type {
+                    "all" => TerminologyQueries::search_all(&searcher, &storage, &query, limit)?,
+                    "snomed" => TerminologyQueries::search_snomed(&searcher, &storage, &query, limit)?,
+                    "amt" => TerminologyQueries::search_amt(&searcher, &query, limit, None)?,
+                    "valuesets" => TerminologyQueries::search_valuesets(&searcher, &query, limit)?,
+                    other => return Err(anyhow!("Unknown terminology type: {}", other)),
+                },
+            };
+
+            if json {
+                print_json(&results)?;
+            } else {
+                for result in &results {
+                    match &result.matched_term {
+                        Some(matched) => println!(
+                            "{:<10} {:<20} {} (matched \"{}\")",
+                            result.terminology_type, result.code, result.display, matched
+                        ),
+                        None => println!("{:<10} {:<20} {}", result.terminology_type, result.code, result.display),
+                    }
+                }
+            }
+            Ok(true)
+        }
+        Command::Lookup {
+            code,
+            system,
+            as_of,
+            release,
+        } => {
+            let storage = open_storage(paths)?;
+            let family = if system.contains("amt") {
+                ContentFamily::Amt
+            } else if system.contains("snomed") {
+                ContentFamily::Snomed
+            } else {
+                ContentFamily::ValueSets
+            };
+            let storage = storage.select_release(family, release.as_deref())?;
+            let result = if system.contains("snomed") {
+                match as_of {
+                    Some(as_of) => TerminologyQueries::lookup_snomed_code_as_of(&storage, &code, &as_of)?,
+                    None => TerminologyQueries::lookup_snomed_code(&storage, &code)?,
+                }
+            } else if as_of.is_some() {
+                return Err(anyhow!("--as-of is only supported for SNOMED lookups"));
+            } else if system.contains("amt") {
+                TerminologyQueries::lookup_amt_code(&storage, &code)?
+            } else if storage.get_codesystem(&system)?.is_some() {
+                TerminologyQueries::lookup_codesystem_code(&storage, &system, &code)?
+            } else {
+                return Err(anyhow!("Unsupported system: {}", system));
+            };
+
+            let Some(result) = result else {
+                eprintln!("Code {} not found in {}", code, system);
+                return Ok(false);
+            };
+
+            if json {
+                print_json(&result)?;
+            } else {
+                println!("{} | {} |{}", result.code, result.display, if result.active { "" } else { " (inactive)" });
+                println!("System: {}", result.system);
+                for designation in &result.designations {
+                    let label = match (designation.kind, designation.acceptability) {
+                        (DesignationKind::Fsn, _) => "FSN",
+                        (_, Some(Acceptability::Preferred)) => "preferred",
+                        (_, Some(Acceptability::Acceptable)) => "acceptable",
+                        (_, None) => "",
+                    };
+                    println!("  {:<11}{}", label, designation.value);
+                }
+                for property in &result.properties {
+                    println!("  {} = {}", property.code, property.value);
+                }
+                if let Some(inactivation) = &result.inactivation {
+                    if let Some(reason_code) = &inactivation.reason_code {
+                        let reason = inactivation.reason.as_deref().unwrap_or("?");
+                        println!("Inactivation reason: {} | {} |", reason_code, reason);
+                    }
+                    for association in &inactivation.associations {
+                        println!(
+                            "  {} {} | {} |",
+                            association.association,
+                            association.code,
+                            association.display.as_deref().unwrap_or("?")
+                        );
+                    }
+                }
+            }
+            Ok(true)
+        }
+        Command::Translate { code, system, refset, release } => {
+            let storage = open_storage(paths)?;
+            let storage = storage.select_release(ContentFamily::Snomed, release.as_deref())?;
+            let translation = TerminologyQueries::translate_code(&storage, &system, &code, refset.as_deref())?;
+            if translation.matches.is_empty() {
+                eprintln!("No map rows for {} in {}", code, system);
+                return Ok(false);
+            }
+
+            if json {
+                print_json(&translation)?;
+            } else {
+                for m in &translation.matches {
+                    println!(
+                        "{} | {} | group {} priority {}: {} {}",
+                        m.refset_id,
+                        m.refset_display.as_deref().unwrap_or("?"),
+                        m.map_group,
+                        m.map_priority,
+                        m.code.as_deref().unwrap_or("(no target)"),
+                        m.display.as_deref().unwrap_or("")
+                    );
+                    if let Some(rule) = &m.map_rule {
+                        println!("  rule: {}", rule);
+                    }
+                    if let Some(advice) = &m.map_advice {
+                        println!("  advice: {}", advice);
+                    }
+                    if let Some(correlation) = m.correlation.as_ref().or(m.correlation_id.as_ref()) {
+                        println!("  correlation: {}", correlation);
+                    }
+                }
+            }
+            Ok(true)
+        }
+        Command::Expand { url, count, release } => {
+            let storage = open_storage(paths)?;
+            let storage = storage.select_release(ContentFamily::ValueSets, release.as_deref())?;
+            let Some(mut expansion) = TerminologyQueries::expand_valueset(&storage, &url)? else {
+                eprintln!("ValueSet not found: {}", url);
+                return Ok(false);
+            };
+            if let Some(count) = count {
+                expansion.concepts.truncate(count);
+            }
+
+            if json {
+                print_json(&expansion)?;
+            } else {
+                println!(
+                    "{} ({} concepts)",
+                    expansion.title.as_deref().unwrap_or(&expansion.url),
+                    expansion.total
+                );
+                for concept in &expansion.concepts {
+                    println!(
+                        "{:<20} {} [{}]",
+                        concept.code,
+                        concept.display.as_deref().unwrap_or(""),
+                        concept.system
+                    );
+                }
+            }
+            Ok(true)
+        }
+        Command::Promote {
+            terminology_type,
+            release,
+        } => {
+            let family = ContentFamily::of(&terminology_type)
+                .ok_or_else(|| anyhow!("Unknown terminology type: {}", terminology_type))?;
+            let storage = open_storage(paths)?;
+            let searcher = open_searcher(paths)?;
+
+            let selected = storage.select_release(family, Some(&release))?;
+            let message = match selected.release() {
+                Some(version_id) => promote_release(&storage, &searcher, version_id)?,
+                None => format!("{} release {} is already live", terminology_type, release),
+            };
+
+            if json {
+                print_json(&message)?;
+            } else {
+                println!("{}", message);
+            }
+            Ok(true)
+        }
+        Command::Status => {
+            let storage = open_storage(paths)?;
+            let versions = storage.get_all_latest()?;
+
+            if json {
+                print_json(&versions)?;
+            } else if versions.is_empty() {
+                println!("No terminologies synced yet");
+            } else {
+                for v in &versions {
+                    let state = if v.imported && storage.is_side_by_side(v)? {
+                        "imported (not live)"
+                    } else if v.imported {
+                        "imported"
+                    } else if v.file_path.is_some() {
+                        "downloaded"
+                    } else {
+                        "recorded"
+                    };
+                    println!(
+                        "{:<10} {:<20} {:<12} {}",
+                        v.terminology_type,
+                        v.content_item_version.as_deref().unwrap_or(&v.version),
+                        v.effective_date.as_deref().unwrap_or("-"),
+                        state
+                    );
+                }
+            }
+            Ok(true)
+        }
+        Command::Jobs => {
+            let storage = open_storage(paths)?;
+            let jobs = JobRegistry::new(&storage)?.list(&storage)?;
+
+            if json {
+                print_json(&jobs)?;
+            } else if jobs.is_empty() {
+                println!("No import jobs yet");
+            } else {
+                for job in &jobs {
+                    let status = serde_json::to_value(job.status)?;
+                    println!(
+                        "{:<6} {:<12} {:<20} {:<12} {:<20} {}",
+                        job.id,
+                        job.terminology_type,
+                        job.version,
+                        status.as_str().unwrap_or("-"),
+                        job.started_at.format("%Y-%m-%d %H:%M:%S"),
+                        job.message.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            Ok(true)
+        }
+        Command::Stats => {
+            let storage = open_storage(paths)?;
+            let searcher = open_searcher(paths)?;
+            let releases = storage_stats(&storage, &searcher)?;
+
+            if json {
+                print_json(&releases)?;
+            } else if releases.is_empty() {
+                println!("No terminologies imported yet");
+            } else {
+                let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+                for r in &releases {
+                    println!(
+                        "{:<12} {:<20} {:<5} {:>10} records {:>9.1} MB database {:>9.1} MB index",
+                        r.terminology_type,
+                        r.version,
+                        if r.live { "live" } else { "" },
+                        r.database_records,
+                        mb(r.database_bytes),
+                        mb(r.index_bytes)
+                    );
+                    let counts = [
+                        ("concepts", r.snomed_concepts),
+                        ("descriptions", r.snomed_descriptions),
+                        ("relationships", r.snomed_relationships),
+                        ("refset members", r.refset_members),
+                        ("AMT codes", r.amt_codes),
+                        ("ValueSets", r.valuesets),
+                        ("ValueSet members", r.valueset_members),
+                        ("CodeSystems", r.codesystems),
+                        ("CodeSystem concepts", r.codesystem_concepts),
+                        ("ConceptMaps", r.concept_maps),
+                    ];
+                    for (label, count) in counts.iter().filter(|(_, count)| *count > 0) {
+                        println!("  {:<20} {:>10}", label, count);
+                    }
+                }
+                let size = std::fs::metadata(storage.db_path()).map(|m| m.len()).unwrap_or(0);
+                println!("Database file: {:.1} MB (storage schema v{})", mb(size), storage.schema_version()?);
+            }
+            Ok(true)
+        }
+    }
+}
+
+/// Prints completed phases to stderr so stdout stays clean for results
+struct ConsoleProgress {
+    quiet: bool,
+}
+
+impl ProgressReporter for ConsoleProgress {
+    fn import_progress(&self, progress: &ImportProgress) {
+        if !self.quiet && progress.phase_status == "completed" {
+            eprintln!("[{}] {}", progress.phase, progress.message);
+        }
+    }
+
+    fn sync_progress(&self, progress: &SyncProgress) {
+        if !self.quiet && progress.phase == "Downloaded" {
+            eprintln!("[{}] {}", progress.phase, progress.message);
+        }
+    }
+}
+
+/// Expand `all` into each syndicated terminology type
+fn expand_types(terminology_type: &str) -> Vec<&str> {
+    if terminology_type == "all" {
+        SYNDICATED_TYPES.to_vec()
+    } else {
+        vec![terminology_type]
+    }
+}
+
+/// Imports run on the calling task, so Ctrl-C is watched on another and stops them at their next batch
+fn cancel_on_interrupt(jobs: &Arc<JobRegistry>) -> tokio::task::JoinHandle<()> {
+    let jobs = Arc::clone(jobs);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() && jobs.cancel_all() > 0 {
+            eprintln!("Cancelling import at the next batch...");
+        }
+    })
+}
+
+fn data_paths() -> Result<DataPaths> {
+    DataPaths::from_project_dirs().ok_or_else(|| anyhow!("Failed to get project directories"))
+}
+
+fn open_storage(paths: &DataPaths) -> Result<TerminologyStorage> {
+    TerminologyStorage::new(paths.db_path.clone(), paths.terminology_data_dir.clone()).with_context(|| {
+        format!(
+            "Failed to open database at {:?} (close the desktop app if it is running)",
+            paths.db_path
+        )
+    })
+}
+
+fn open_searcher(paths: &DataPaths) -> Result<TerminologySearch> {
+    TerminologySearch::new(&paths.index_dir).context("Failed to open search indexes")
+}
+
+fn ncts_client() -> Result<NctsClient> {
+    let token_manager = TokenManager::from_env()
+        .context("Failed to create token manager - ensure NCTS_CLIENT_ID and NCTS_CLIENT_SECRET are set")?;
+    NctsClient::new(token_manager)
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from([&["syndication-cli"], args].concat()).expect("Failed to parse arguments")
+    }
+
+    fn test_paths(dir: &tempfile::TempDir) -> DataPaths {
+        DataPaths {
+            db_path: dir.path().join("syndication.redb"),
+            terminology_data_dir: dir.path().join("terminology"),
+            index_dir: dir.path().join("indexes"),
+        }
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        assert!(!parse(&["status"]).json);
+        assert!(parse(&["--json", "status"]).json);
+        assert!(parse(&["jobs", "--json"]).json, "--json is accepted after the subcommand too");
+
+        let cli = parse(&["search", "asthma", "--type", "snomed", "--as-of", "2024-01-31", "--release", "20250131"]);
+        let Command::Search {
+            query,
+            terminology_type,
+            limit,
+            as_of,
+            release,
+        } = cli.command
+        else {
+            panic!("Expected a search command");
+        };
+        assert_eq!((query.as_str(), terminology_type.as_str(), limit), ("asthma", "snomed", 20));
+        assert_eq!(as_of.as_deref(), Some("2024-01-31"));
+        assert_eq!(release.as_deref(), Some("20250131"));
+
+        let Command::Lookup { system, as_of, release, .. } =
+            parse(&["lookup", "73211009", "--as-of", "2020-01-01"]).command
+        else {
+            panic!("Expected a lookup command");
+        };
+        assert_eq!((system.as_str(), as_of.as_deref(), release), ("snomed", Some("2020-01-01"), None));
+
+        let Command::Expand { release, count, .. } =
+            parse(&["expand", "http://example.org/vs", "--release", "3", "--count", "5"]).command
+        else {
+            panic!("Expected an expand command");
+        };
+        assert_eq!((release.as_deref(), count), (Some("3"), Some(5)));
+
+        assert!(Cli::try_parse_from(["syndication-cli", "promote", "snomed"]).is_err(), "The release is required");
+        assert!(Cli::try_parse_from(["syndication-cli", "search", "asthma", "--as-of"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_commands() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let paths = test_paths(&dir);
+
+        assert!(run(parse(&["--json", "status"]), &paths).await.unwrap());
+        assert!(run(parse(&["jobs"]), &paths).await.unwrap());
+
+        let bundle = dir.path().join("valuesets.json");
+        std::fs::write(
+            &bundle,
+            r#"{ "resourceType": "Bundle", "timestamp": "2025-03-01T10:00:00Z", "entry": [ { "resource": {
+                "resourceType": "ValueSet", "url": "http://example.org/vs/asthma", "title": "Asthma",
+                "status": "active", "expansion": { "contains": [ { "system": "http://snomed.info/sct", "code": "195967001" } ] }
+            } } ] }"#,
+        )
+        .unwrap();
+        assert!(run(parse(&["import-file", &bundle.to_string_lossy()]), &paths).await.unwrap());
+        assert!(paths.terminology_data_dir.exists(), "The file is copied into the data directory");
+
+        assert!(run(parse(&["expand", "http://example.org/vs/asthma"]), &paths).await.unwrap());
+        assert!(run(parse(&["search", "Asthma", "--type", "valuesets"]), &paths).await.unwrap());
+        assert!(run(parse(&["--json", "stats"]), &paths).await.unwrap());
+        assert!(run(parse(&["promote", "valuesets", "2025-03-01T10:00:00Z"]), &paths).await.unwrap());
+
+        // Completed but not found, as opposed to failed
+        assert!(!run(parse(&["expand", "http://example.org/vs/missing"]), &paths).await.unwrap());
+        assert!(run(parse(&["promote", "valuesets", "1999-01-01"]), &paths).await.is_err());
+        assert!(run(parse(&["search", "Asthma", "--release", "1"]), &paths).await.is_err(), "--release needs a --type");
+    }
+}
@@ -1,28 +1,20 @@
-use crate::import::TerminologyImporter;
-use crate::ncts::{FeedEntry, NctsClient, TerminologyType};
-use crate::queries::TerminologyQueries;
-use crate::search::TerminologySearch;
-use crate::storage::{TerminologyStorage, TerminologyVersion};
+use syndication_core::import::TerminologyImporter;
+use syndication_core::jobs::JobRegistry;
+use syndication_core::ncts::{FeedEntry, NctsClient, TerminologyType};
+use syndication_core::progress::{ImportProgress, ProgressReporter, SyncProgress};
+use syndication_core::queries::TerminologyQueries;
+use syndication_core::search::TerminologySearch;
+use syndication_core::storage::{ContentFamily, ImportJob, ReleaseStats, TerminologyStorage, TerminologyVersion};
+use syndication_core::sync::{
+    import_latest_job, import_local_file as import_local_release, promote_release, storage_stats, sync_latest,
+    SyncResult, SYNDICATED_TYPES,
+};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 use tauri::{Emitter, State};
 use tokio::sync::Mutex;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SyncResult {
-    pub terminology_type: String,
-    pub success: bool,
-    pub latest_version: Option<String>,
-    pub error: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SyncProgress {
-    pub phase: String,
-    pub message: String,
-    pub percentage: f32,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StorageStats {
     pub snomed_concepts: i64,
@@ -30,12 +22,37 @@ pub struct StorageStats {
     pub amt_codes: i64,
     pub valuesets: i64,
     pub database_size_mb: f64,
+    /// Storage schema version of the database
+    pub schema_version: u64,
+    /// Total size of the search indexes, live and side-by-side
+    pub index_size_mb: f64,
+    /// Counts and sizes per imported release
+    pub releases: Vec<ReleaseStats>,
 }
 
+/// Storage and search indexes are shared without a lock: redb readers see the last committed
+/// transaction and Tantivy searches the last commit, so queries keep answering during an import
 pub struct AppState {
     pub ncts_client: NctsClient,
-    pub storage: Arc<Mutex<TerminologyStorage>>,
-    pub searcher: Arc<Mutex<TerminologySearch>>,
+    pub storage: Arc<TerminologyStorage>,
+    pub searcher: Arc<TerminologySearch>,
+    /// Held by commands that change content (sync, import, promote, delete) so they run one at a time
+    pub write_lock: Mutex<()>,
+    /// Running import jobs, cancelled and listed without taking `write_lock`
+    pub jobs: JobRegistry,
+}
+
+/// Forwards core import and download progress to the frontend as Tauri events
+pub struct TauriProgress(pub tauri::AppHandle);
+
+impl ProgressReporter for TauriProgress {
+    fn import_progress(&self, progress: &ImportProgress) {
+        let _ = self.0.emit("import-progress", progress);
+    }
+
+    fn sync_progress(&self, progress: &SyncProgress) {
+        let _ = self.0.emit("sync-progress", progress);
+    }
 }
 
 /// Fetch the latest version information for a terminology type
@@ -76,149 +93,13 @@ pub async fn sync_terminology(
     state: State<'_, AppState>,
 ) -> Result<SyncResult, String> {
     println!("🔵 sync_terminology called for: {}", terminology_type);
-    let term_type = parse_terminology_type(&terminology_type)?;
-
-    // Fetch the latest version from NCTS
-    let latest_entry = match state.ncts_client.fetch_latest(term_type.clone()).await {
-        Ok(Some(entry)) => entry,
-        Ok(None) => {
-            return Ok(SyncResult {
-                terminology_type: terminology_type.clone(),
-                success: false,
-                latest_version: None,
-                error: Some("No versions found".to_string()),
-            });
-        }
-        Err(e) => {
-            return Ok(SyncResult {
-                terminology_type: terminology_type.clone(),
-                success: false,
-                latest_version: None,
-                error: Some(format!("Failed to fetch: {}", e)),
-            });
-        }
-    };
-
-    let storage = state.storage.lock().await;
-
-    // Use content_item_version if available, otherwise fall back to title
-    let version = latest_entry.content_item_version.as_ref()
-        .or(latest_entry.version.as_ref())
-        .unwrap_or(&latest_entry.title)
-        .clone();
-
-    // Check if we already have this version
-    let existing = storage
-        .get_latest(&terminology_type)
-        .map_err(|e| format!("Storage error: {}", e))?;
-
-    if let Some(existing) = existing {
-        // Compare using content_item_version if available, otherwise use version
-        let existing_version = existing.content_item_version.as_ref()
-            .unwrap_or(&existing.version);
-
-        // Only skip download if version matches AND file actually exists on disk
-        if existing_version == &version {
-            if let Some(ref file_path) = existing.file_path {
-                let path = std::path::Path::new(file_path);
-                if path.exists() {
-                    return Ok(SyncResult {
-                        terminology_type: terminology_type.clone(),
-                        success: true,
-                        latest_version: Some(version.clone()),
-                        error: Some("Already up to date".to_string()),
-                    });
-                } else {
-                    println!("⚠ Version exists in database but file is missing, re-downloading...");
-                }
-            } else {
-                println!("⚠ Version exists in database but no file_path recorded, downloading...");
-            }
-        }
-    }
-
-    // Record the new version with NCTS metadata
-    let version_id = storage
-        .record_version(
-            &terminology_type,
-            &version,
-            latest_entry.effective_date.as_deref(),
-            latest_entry.download_url.as_deref().unwrap_or(""),
-            latest_entry.content_item_identifier.as_deref(),
-            latest_entry.content_item_version.as_deref(),
-            latest_entry.sha256_hash.as_deref(),
-            latest_entry.sct_base_version.as_deref(),
-        )
-        .map_err(|e| format!("Failed to record version: {}", e))?;
-
-    // Download the file if a download URL is available
-    if let Some(download_url) = &latest_entry.download_url {
-        let file_path = storage.generate_file_path(&terminology_type, &version);
-
-        // Emit download start event
-        let _ = app_handle.emit("sync-progress", SyncProgress {
-            phase: "Downloading".to_string(),
-            message: format!("Downloading {} from NCTS...", terminology_type),
-            percentage: 0.0,
-        });
-
-        // Download the file
-        match state
-            .ncts_client
-            .download_terminology(download_url, &file_path, Some(app_handle.clone()))
-            .await
-        {
-            Ok(_) => {
-                // Validate SHA-256 hash if provided (CP 94)
-                if let Some(expected_hash) = &latest_entry.sha256_hash {
-                    println!("Validating downloaded file hash...");
-                    match NctsClient::validate_file_hash(&file_path, expected_hash).await {
-                        Ok(_) => {
-                            println!("✓ File integrity validated");
-                        }
-                        Err(e) => {
-                            // Hash validation failed - delete the file and return error
-                            let _ = tokio::fs::remove_file(&file_path).await;
-                            return Ok(SyncResult {
-                                terminology_type: terminology_type.clone(),
-                                success: false,
-                                latest_version: Some(version.clone()),
-                                error: Some(format!("Hash validation failed: {}", e)),
-                            });
-                        }
-                    }
-                } else {
-                    println!("⚠ Warning: No SHA-256 hash provided in feed, skipping validation");
-                }
-
-                // Mark as downloaded
-                let file_path_str = file_path.to_str().unwrap().to_string();
-                storage
-                    .mark_downloaded(version_id, &file_path_str)
-                    .map_err(|e| format!("Failed to mark downloaded: {}", e))?;
-            }
-            Err(e) => {
-                return Ok(SyncResult {
-                    terminology_type: terminology_type.clone(),
-                    success: false,
-                    latest_version: Some(version.clone()),
-                    error: Some(format!("Download failed: {}", e)),
-                });
-            }
-        }
-    }
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
+    let progress = TauriProgress(app_handle);
 
-    // Mark as latest
-    storage
-        .mark_as_latest(version_id, &terminology_type)
-        .map_err(|e| format!("Failed to mark as latest: {}", e))?;
-
-    Ok(SyncResult {
-        terminology_type: terminology_type.clone(),
-        success: true,
-        latest_version: Some(version.clone()),
-        error: None,
-    })
+    sync_latest(&state.ncts_client, storage, &terminology_type, Some(&progress))
+        .await
+        .map_err(|e| format!("{:#}", e))
 }
 
 /// Sync all terminology types (excludes LOINC - not available via syndication)
@@ -227,11 +108,9 @@ pub async fn sync_all_terminologies(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
 ) -> Result<Vec<SyncResult>, String> {
-    let terminology_types = vec!["snomed", "valuesets", "amt"]; // LOINC excluded - proprietary binary only
-
     let mut results = Vec::new();
 
-    for term_type in terminology_types {
+    for term_type in SYNDICATED_TYPES {
         let result = sync_terminology(term_type.to_string(), app_handle.clone(), state.clone()).await?;
         results.push(result);
     }
@@ -245,7 +124,7 @@ pub async fn get_local_latest(
     terminology_type: String,
     state: State<'_, AppState>,
 ) -> Result<Option<TerminologyVersion>, String> {
-    let storage = state.storage.lock().await;
+    let storage = state.storage.as_ref();
 
     storage
         .get_latest(&terminology_type)
@@ -258,7 +137,7 @@ pub async fn get_local_versions(
     terminology_type: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<TerminologyVersion>, String> {
-    let storage = state.storage.lock().await;
+    let storage = state.storage.as_ref();
 
     storage
         .get_all_versions(&terminology_type)
@@ -270,7 +149,7 @@ pub async fn get_local_versions(
 pub async fn get_all_local_latest(
     state: State<'_, AppState>,
 ) -> Result<Vec<TerminologyVersion>, String> {
-    let storage = state.storage.lock().await;
+    let storage = state.storage.as_ref();
 
     storage
         .get_all_latest()
@@ -278,76 +157,61 @@ pub async fn get_all_local_latest(
 }
 
 /// Import terminology content into database
+/// Runs as a job: its id is emitted as `import-job-started` so the import can be cancelled with `cancel_job`
 #[tauri::command]
 pub async fn import_terminology(
     terminology_type: String,
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let storage = state.storage.lock().await;
-    let mut searcher = state.searcher.lock().await;
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
 
-    // Get the latest version for this terminology
-    let version = storage
-        .get_latest(&terminology_type)
-        .map_err(|e| format!("Failed to get latest version: {}", e))?;
-
-    let version = version.ok_or_else(|| {
-        format!(
-            "No downloaded version found for {}. Please sync first.",
-            terminology_type
-        )
-    })?;
-
-    if version.imported {
-        return Ok(format!(
-            "{} version {} already imported",
-            terminology_type, version.version
-        ));
-    }
+    let progress = TauriProgress(app_handle.clone());
 
-    let file_path = version
-        .file_path
-        .ok_or_else(|| format!("No file path found for {}", terminology_type))?;
+    import_latest_job(storage, searcher, &state.jobs, &terminology_type, Some(&progress), |job_id| {
+        let _ = app_handle.emit("import-job-started", job_id);
+    })
+    .await
+    .map_err(|e| format!("{:#}", e))
+}
 
-    // Create importer with app handle for progress events
-    let importer = TerminologyImporter::new(&storage, version.id)
-        .with_app_handle(app_handle);
+/// Import a release from a local file (RF2 ZIP, AMT CSV or FHIR bundle) on a machine without NCTS access
+/// The type and version are read from the file, which is copied into the data directory and imported as a job
+#[tauri::command]
+pub async fn import_local_file(
+    path: String,
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
 
-    // Import based on terminology type (passing searcher for index building)
-    match terminology_type.as_str() {
-        "snomed" => {
-            importer
-                .import_snomed(std::path::Path::new(&file_path), &mut searcher)
-                .await
-                .map_err(|e| format!("SNOMED import failed: {}", e))?;
-        }
-        "amt" => {
-            importer
-                .import_amt(std::path::Path::new(&file_path), &mut searcher)
-                .await
-                .map_err(|e| format!("AMT import failed: {}", e))?;
-        }
-        "valuesets" => {
-            importer
-                .import_valuesets(std::path::Path::new(&file_path), &mut searcher)
-                .await
-                .map_err(|e| format!("ValueSets import failed: {}", e))?;
-        }
-        _ => {
-            return Err(format!("Unknown terminology type: {}", terminology_type));
-        }
-    }
+    let progress = TauriProgress(app_handle.clone());
 
-    // Mark as imported
-    storage
-        .mark_imported(version.id)
-        .map_err(|e| format!("Failed to mark as imported: {}", e))?;
+    import_local_release(storage, searcher, &state.jobs, Path::new(&path), Some(&progress), |job_id| {
+        let _ = app_handle.emit("import-job-started", job_id);
+    })
+    .await
+    .map_err(|e| format!("{:#}", e))
+}
 
-    Ok(format!(
-        "Successfully imported {} version {}",
-        terminology_type, version.version
-    ))
+/// Cancel a running import job; returns false if no job with that id is running
+/// A cancelled SNOMED import resumes from its last committed batch when imported again
+#[tauri::command]
+pub async fn cancel_job(job_id: u64, state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.jobs.cancel(job_id))
+}
+
+/// List import jobs, newest first, with how each one ended
+#[tauri::command]
+pub async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<ImportJob>, String> {
+    state
+        .jobs
+        .list(state.storage.as_ref())
+        .map_err(|e| format!("Storage error: {}", e))
 }
 
 /// Search for codes across terminologies
@@ -356,14 +220,40 @@ pub async fn search_terminology(
     query: String,
     terminology_types: Vec<String>,
     limit: Option<i32>,
+    as_of: Option<String>,
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<crate::search::SearchResult>, String> {
-    let searcher = state.searcher.lock().await;
+) -> Result<Vec<syndication_core::search::SearchResult>, String> {
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
     let limit = limit.unwrap_or(20) as usize;
 
+    // A version selector names one release, so it needs exactly one terminology to search
+    let family = match (&version, terminology_types.as_slice()) {
+        (None, _) => ContentFamily::Snomed,
+        (Some(_), [term_type]) => ContentFamily::of(term_type)
+            .ok_or_else(|| format!("Unknown terminology type: {}", term_type))?,
+        (Some(_), _) => return Err("Select a single terminology type to search a specific version".to_string()),
+    };
+    let storage = storage
+        .select_release(family, version.as_deref())
+        .map_err(|e| e.to_string())?;
+    let searcher = searcher
+        .for_view(&storage)
+        .map_err(|e| format!("Failed to open release search index: {}", e))?;
+
+    // Point-in-time search only covers SNOMED, which is the only terminology with history
+    if let Some(as_of) = as_of {
+        if terminology_types.iter().any(|t| t != "snomed" && t != "all") {
+            return Err("Point-in-time search is only supported for SNOMED CT-AU".to_string());
+        }
+        return TerminologyQueries::search_snomed_as_of(&searcher, &storage, &query, limit, &as_of)
+            .map_err(|e| format!("SNOMED search failed: {}", e));
+    }
+
     if terminology_types.is_empty() || terminology_types.contains(&"all".to_string()) {
         // Search all terminologies
-        TerminologyQueries::search_all(&searcher, &query, limit)
+        TerminologyQueries::search_all(&searcher, &storage, &query, limit)
             .map_err(|e| format!("Search failed: {}", e))
     } else {
         let mut results = Vec::new();
@@ -372,7 +262,7 @@ pub async fn search_terminology(
             match term_type.as_str() {
                 "snomed" => {
                     let snomed_results =
-                        TerminologyQueries::search_snomed(&searcher, &query, limit)
+                        TerminologyQueries::search_snomed(&searcher, &storage, &query, limit)
                             .map_err(|e| format!("SNOMED search failed: {}", e))?;
                     results.extend(snomed_results);
                 }
@@ -396,17 +286,44 @@ pub async fn search_terminology(
     }
 }
 
+/// Evaluate a SNOMED ECL expression constraint against the local store
+#[tauri::command]
+pub async fn evaluate_ecl(
+    expression: String,
+    limit: Option<i32>,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<syndication_core::queries::EclResult, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(100) as usize;
+
+    TerminologyQueries::evaluate_ecl(&storage, &expression, limit)
+        .map_err(|e| format!("ECL evaluation failed: {}", e))
+}
+
 /// Search AMT codes for patient use (MP PT and TPUU TP PT columns only)
 /// Returns Medicinal Product (MP) and Trade Product Unit of Use (TPUU TP) terms for patient-facing searches
 #[tauri::command]
 pub async fn search_amt_patient(
     query: String,
     limit: Option<i32>,
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<crate::search::SearchResult>, String> {
-    let searcher = state.searcher.lock().await;
+) -> Result<Vec<syndication_core::search::SearchResult>, String> {
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
     let limit = limit.unwrap_or(20) as usize;
 
+    let storage = storage
+        .select_release(ContentFamily::Amt, version.as_deref())
+        .map_err(|e| e.to_string())?;
+    let searcher = searcher
+        .for_view(&storage)
+        .map_err(|e| format!("Failed to open release search index: {}", e))?;
+
     // Filter to MP (Medicinal Product) and TPUU TP (Trade Product Unit of Use - TP) only
     // Indexes the MP PT and TPUU TP PT columns from AMT CSV
     let code_types = vec!["MP".to_string(), "TPUU TP".to_string()];
@@ -422,11 +339,20 @@ pub async fn search_amt_patient(
 pub async fn search_amt_doctor(
     query: String,
     limit: Option<i32>,
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<crate::search::SearchResult>, String> {
-    let searcher = state.searcher.lock().await;
+) -> Result<Vec<syndication_core::search::SearchResult>, String> {
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
     let limit = limit.unwrap_or(20) as usize;
 
+    let storage = storage
+        .select_release(ContentFamily::Amt, version.as_deref())
+        .map_err(|e| e.to_string())?;
+    let searcher = searcher
+        .for_view(&storage)
+        .map_err(|e| format!("Failed to open release search index: {}", e))?;
+
     // Filter to doctor-relevant types: MP, MPUU, TPUU TP, TPUU
     let code_types = vec![
         "MP".to_string(),
@@ -449,7 +375,7 @@ pub struct AmtCodeTypeStats {
 pub async fn get_amt_code_type_stats(
     state: State<'_, AppState>,
 ) -> Result<Vec<AmtCodeTypeStats>, String> {
-    let storage = state.storage.lock().await;
+    let storage = state.storage.as_ref();
 
     // Get all AMT codes
     let all_codes = storage
@@ -487,7 +413,7 @@ pub async fn debug_amt_codes(
     drug_name: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<AmtCodeVariant>, String> {
-    let storage = state.storage.lock().await;
+    let storage = state.storage.as_ref();
 
     // Get all AMT codes
     let all_codes = storage
@@ -521,16 +447,17 @@ pub async fn debug_amt_codes(
 pub async fn rebuild_amt_index(
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let storage = state.storage.lock().await;
-    let mut searcher = state.searcher.lock().await;
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
 
     println!("Rebuilding AMT Tantivy index from redb storage...");
 
     // Use the importer's build_amt_index method which includes filtering
     // Version ID doesn't matter for rebuild (we're just reading from storage)
-    let importer = crate::import::TerminologyImporter::new(&storage, 0);
+    let importer = TerminologyImporter::new(storage, 0);
 
-    importer.build_amt_index(&mut searcher)
+    importer.build_amt_index(searcher)
         .map_err(|e| format!("Failed to rebuild AMT index: {}", e))?;
 
     // Get code counts by type for reporting
@@ -563,8 +490,8 @@ pub async fn rebuild_amt_index(
 pub async fn diagnose_amt_index(
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let storage = state.storage.lock().await;
-    let searcher = state.searcher.lock().await;
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
 
     // Check redb storage
     let all_codes = storage
@@ -622,28 +549,184 @@ pub async fn diagnose_amt_index(
 pub async fn lookup_code(
     code: String,
     system: String,
+    as_of: Option<String>,
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Option<crate::queries::CodeLookupResult>, String> {
-    let storage = state.storage.lock().await;
+) -> Result<Option<syndication_core::queries::CodeLookupResult>, String> {
+    let storage = state.storage.as_ref();
+    let family = if system.contains("amt") {
+        ContentFamily::Amt
+    } else if system.contains("snomed") {
+        ContentFamily::Snomed
+    } else {
+        ContentFamily::ValueSets
+    };
+    let storage = storage
+        .select_release(family, version.as_deref())
+        .map_err(|e| e.to_string())?;
 
     if system.contains("snomed") {
-        TerminologyQueries::lookup_snomed_code(&storage, &code)
-            .map_err(|e| format!("Lookup failed: {}", e))
+        match as_of {
+            Some(as_of) => TerminologyQueries::lookup_snomed_code_as_of(&storage, &code, &as_of),
+            None => TerminologyQueries::lookup_snomed_code(&storage, &code),
+        }
+        .map_err(|e| format!("Lookup failed: {}", e))
+    } else if as_of.is_some() {
+        Err("Point-in-time lookup is only supported for SNOMED CT-AU".to_string())
     } else if system.contains("amt") {
         TerminologyQueries::lookup_amt_code(&storage, &code)
             .map_err(|e| format!("Lookup failed: {}", e))
     } else {
-        Err(format!("Unsupported system: {}", system))
+        match storage.get_codesystem(&system) {
+            Ok(Some(_)) => TerminologyQueries::lookup_codesystem_code(&storage, &system, &code)
+                .map_err(|e| format!("Lookup failed: {}", e)),
+            Ok(None) => Err(format!("Unsupported system: {}", system)),
+            Err(e) => Err(format!("Lookup failed: {}", e)),
+        }
     }
 }
 
+/// Get the direct supertypes and subtypes of a SNOMED concept
+#[tauri::command]
+pub async fn get_snomed_hierarchy(
+    code: String,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<syndication_core::queries::SnomedHierarchy>, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::snomed_hierarchy(&storage, &code)
+        .map_err(|e| format!("Hierarchy lookup failed: {}", e))
+}
+
+/// Test subsumption between two SNOMED concepts (FHIR $subsumes semantics)
+#[tauri::command]
+pub async fn subsumes(
+    code_a: String,
+    code_b: String,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<syndication_core::queries::SubsumptionResult, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::subsumes(&storage, &code_a, &code_b)
+        .map_err(|e| format!("Subsumption test failed: {}", e))
+}
+
+/// List the imported SNOMED reference sets
+#[tauri::command]
+pub async fn list_refsets(
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<syndication_core::queries::RefsetSummary>, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::list_refsets(&storage)
+        .map_err(|e| format!("Failed to list reference sets: {}", e))
+}
+
+/// Page through the active members of a SNOMED reference set
+#[tauri::command]
+pub async fn get_refset_members(
+    refset_id: String,
+    offset: Option<usize>,
+    count: Option<usize>,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<syndication_core::queries::RefsetMembers>, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::refset_members(&storage, &refset_id, offset.unwrap_or(0), count.unwrap_or(100))
+        .map_err(|e| format!("Failed to get reference set members: {}", e))
+}
+
+/// Test whether a concept is an active member of a SNOMED reference set
+#[tauri::command]
+pub async fn is_refset_member(
+    refset_id: String,
+    code: String,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::is_refset_member(&storage, &refset_id, &code)
+        .map_err(|e| format!("Membership test failed: {}", e))
+}
+
+/// Translate a code through the SNOMED CT-AU complex and extended map refsets (e.g. to ICD-10-AM)
+/// A SNOMED code maps forward to its targets; a code from any other system maps back to SNOMED
+#[tauri::command]
+pub async fn translate_code(
+    system: String,
+    code: String,
+    refset_id: Option<String>,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<syndication_core::queries::Translation, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::Snomed, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::translate_code(&storage, &system, &code, refset_id.as_deref())
+        .map_err(|e| format!("Translation failed: {}", e))
+}
+
+/// Translate a code with the imported FHIR ConceptMaps (FHIR $translate semantics)
+/// With `reverse`, the code is looked up as a map target and translated back to its sources
+#[tauri::command]
+pub async fn translate(
+    system: String,
+    code: String,
+    url: Option<String>,
+    target_system: Option<String>,
+    reverse: Option<bool>,
+    version: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<syndication_core::queries::ConceptMapTranslation, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::ValueSets, version.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    TerminologyQueries::translate(
+        &storage,
+        &system,
+        &code,
+        url.as_deref(),
+        target_system.as_deref(),
+        reverse.unwrap_or(false),
+    )
+    .map_err(|e| format!("Translation failed: {}", e))
+}
+
 /// Expand a ValueSet by URL
 #[tauri::command]
 pub async fn expand_valueset(
     valueset_url: String,
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Option<crate::queries::ValueSetExpansion>, String> {
-    let storage = state.storage.lock().await;
+) -> Result<Option<syndication_core::queries::ValueSetExpansion>, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::ValueSets, version.as_deref())
+        .map_err(|e| e.to_string())?;
 
     TerminologyQueries::expand_valueset(&storage, &valueset_url)
         .map_err(|e| format!("ValueSet expansion failed: {}", e))
@@ -655,9 +738,13 @@ pub async fn validate_code(
     code: String,
     system: String,
     valueset_url: String,
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<crate::queries::ValidationResult, String> {
-    let storage = state.storage.lock().await;
+) -> Result<syndication_core::queries::ValidationResult, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::ValueSets, version.as_deref())
+        .map_err(|e| e.to_string())?;
 
     TerminologyQueries::validate_code(&storage, &code, &system, &valueset_url)
         .map_err(|e| format!("Code validation failed: {}", e))
@@ -666,21 +753,61 @@ pub async fn validate_code(
 /// List all available ValueSets
 #[tauri::command]
 pub async fn list_valuesets(
+    version: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<crate::queries::ValueSetListItem>, String> {
-    let storage = state.storage.lock().await;
+) -> Result<Vec<syndication_core::queries::ValueSetListItem>, String> {
+    let storage = state.storage.as_ref();
+    let storage = storage
+        .select_release(ContentFamily::ValueSets, version.as_deref())
+        .map_err(|e| e.to_string())?;
 
     TerminologyQueries::list_valuesets(&storage)
         .map_err(|e| format!("Failed to list ValueSets: {}", e))
 }
 
-/// Get storage statistics (record counts)
-/// TEMPORARILY DISABLED - requires table iteration optimization for redb
-// #[tauri::command]
-// pub async fn get_storage_stats(state: State<'_, AppState>) -> Result<StorageStats, String> {
-//     // TODO: Implement with redb table iteration
-//     Err("Storage stats temporarily disabled during redb migration".to_string())
-// }
+/// Make an imported release the default for queries, replacing the live release of its terminology
+#[tauri::command]
+pub async fn mark_as_latest(
+    version_id: u64,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
+
+    promote_release(storage, searcher, version_id).map_err(|e| format!("{:#}", e))
+}
+
+/// Get storage statistics: live record counts plus counts, database and index sizes per release
+#[tauri::command]
+pub async fn get_storage_stats(state: State<'_, AppState>) -> Result<StorageStats, String> {
+    let storage = state.storage.as_ref();
+    let releases = storage_stats(storage, &state.searcher).map_err(|e| format!("{:#}", e))?;
+
+    let live = releases.iter().filter(|r| r.live);
+    let (mut snomed_concepts, mut snomed_descriptions, mut amt_codes, mut valuesets) = (0, 0, 0, 0);
+    for release in live {
+        snomed_concepts += release.snomed_concepts as i64;
+        snomed_descriptions += release.snomed_descriptions as i64;
+        amt_codes += release.amt_codes as i64;
+        valuesets += release.valuesets as i64;
+    }
+
+    let database_size = tokio::fs::metadata(storage.db_path()).await.map(|m| m.len()).unwrap_or(0);
+    let index_size: u64 = releases.iter().map(|r| r.index_bytes).sum();
+    let schema_version = storage.schema_version().map_err(|e| e.to_string())?;
+
+    Ok(StorageStats {
+        snomed_concepts,
+        snomed_descriptions,
+        amt_codes,
+        valuesets,
+        database_size_mb: database_size as f64 / (1024.0 * 1024.0),
+        schema_version,
+        index_size_mb: index_size as f64 / (1024.0 * 1024.0),
+        releases,
+    })
+}
 
 /// Test NCTS connection
 #[tauri::command]
@@ -733,6 +860,7 @@ pub struct DetailedStorageInfo {
     pub file_path: Option<String>,
     pub database_records: i64,
     pub database_size_estimate_bytes: u64,
+    pub index_size_bytes: u64,
     pub total_size_bytes: u64,
     pub has_file: bool,
     pub has_database_data: bool,
@@ -751,10 +879,11 @@ pub struct StorageBreakdown {
 pub async fn get_detailed_storage_info(
     state: State<'_, AppState>,
 ) -> Result<StorageBreakdown, String> {
-    let storage = state.storage.lock().await;
+    let storage = state.storage.as_ref();
 
     let mut terminologies = Vec::new();
     let mut total_file_size = 0u64;
+    let releases = storage_stats(storage, &state.searcher).map_err(|e| format!("{:#}", e))?;
 
     // Get all terminology versions
     let all_versions = storage
@@ -786,11 +915,14 @@ pub async fn get_detailed_storage_info(
             (0, None, false)
         };
 
-        // Record counting temporarily disabled during redb migration
-        // TODO: Implement efficient table iteration for redb
-        let db_records = 0i64;
-        let has_data = false;
-        let db_size_estimate = 0u64;
+        // Database and index figures come from the family's live release
+        let family = ContentFamily::of(terminology_type).expect("known terminology type");
+        let live = releases
+            .iter()
+            .find(|r| r.live && ContentFamily::of(&r.terminology_type) == Some(family));
+        let db_records = live.map_or(0, |r| r.database_records as i64);
+        let db_size_estimate = live.map_or(0, |r| r.database_bytes);
+        let index_size = live.map_or(0, |r| r.index_bytes);
 
         total_file_size += file_size;
 
@@ -800,9 +932,10 @@ pub async fn get_detailed_storage_info(
             file_path: file_path_str,
             database_records: db_records,
             database_size_estimate_bytes: db_size_estimate,
-            total_size_bytes: file_size + db_size_estimate,
+            index_size_bytes: index_size,
+            total_size_bytes: file_size + db_size_estimate + index_size,
             has_file,
-            has_database_data: has_data,
+            has_database_data: db_records > 0,
         });
     }
 
@@ -827,7 +960,8 @@ pub async fn delete_terminology_file(
     terminology_type: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let storage = state.storage.lock().await;
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
 
     // Get the version to find the file path
     let version = storage
@@ -866,8 +1000,9 @@ pub async fn delete_terminology_data(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let storage = state.storage.lock().await;
-    let mut searcher = state.searcher.lock().await;
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
+    let searcher = state.searcher.as_ref();
 
     // Emit progress: Starting
     let _ = app_handle.emit("delete-progress", SyncProgress {
@@ -882,6 +1017,12 @@ pub async fn delete_terminology_data(
         .map_err(|e| format!("Failed to get version: {}", e))?;
 
     if let Some(version) = version {
+        // A release imported beside the live one only owns its own tables and search index
+        let side_by_side = version.imported
+            && storage
+                .is_side_by_side(&version)
+                .map_err(|e| format!("Failed to get version: {}", e))?;
+
         // Small delay so user sees "Starting" phase
         tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
 
@@ -894,10 +1035,39 @@ pub async fn delete_terminology_data(
 
         // Delete records based on terminology type
         let deleted_count = match terminology_type.as_str() {
-            "snomed" => {
-                storage
+            "snomed" | "snomed-full" if side_by_side => storage
+                .delete_snomed_by_version(version.id)
+                .map_err(|e| format!("Failed to delete SNOMED data: {}", e))?,
+            "snomed" | "snomed-full" => {
+                let mut deleted = storage
                     .delete_snomed_by_version(version.id)
-                    .map_err(|e| format!("Failed to delete SNOMED data: {}", e))?
+                    .map_err(|e| format!("Failed to delete SNOMED data: {}", e))?;
+
+                // Snapshot, Full and Delta imports share the live SNOMED tables and rows carry the id of
+                // the release that last wrote them, so remove every other release in them too
+                for release_type in ["snomed", "snomed-full", "snomed-delta"] {
+                    let releases = storage
+                        .get_all_versions(release_type)
+                        .map_err(|e| format!("Failed to get SNOMED versions: {}", e))?;
+                    for release in releases.iter().filter(|r| r.imported && r.id != version.id) {
+                        let own_tables = storage
+                            .is_side_by_side(release)
+                            .map_err(|e| format!("Failed to get SNOMED versions: {}", e))?;
+                        if own_tables {
+                            continue;
+                        }
+                        deleted += storage
+                            .delete_snomed_by_version(release.id)
+                            .map_err(|e| format!("Failed to delete SNOMED data: {}", e))?;
+                        storage
+                            .clear_imported_status(release.id)
+                            .map_err(|e| format!("Failed to update version: {}", e))?;
+                    }
+                }
+                deleted
+            }
+            "snomed-delta" => {
+                return Err("A SNOMED delta cannot be removed on its own; delete the SNOMED data instead".to_string());
             }
             "amt" => {
                 storage
@@ -921,7 +1091,11 @@ pub async fn delete_terminology_data(
 
         // Clear Tantivy indexes
         match terminology_type.as_str() {
-            "snomed" => {
+            _ if side_by_side => {
+                searcher.remove_release(version.id)
+                    .map_err(|e| format!("Failed to remove release index: {}", e))?;
+            }
+            "snomed" | "snomed-full" => {
                 searcher.clear_snomed()
                     .map_err(|e| format!("Failed to clear SNOMED index: {}", e))?;
             }
@@ -935,6 +1109,10 @@ pub async fn delete_terminology_data(
             }
             _ => {}
         }
+        if let Some(family) = ContentFamily::of(&terminology_type).filter(|_| !side_by_side) {
+            searcher.commit(family)
+                .map_err(|e| format!("Failed to commit search indexes: {}", e))?;
+        }
 
         // Emit progress: Updating version
         let _ = app_handle.emit("delete-progress", SyncProgress {
@@ -992,7 +1170,8 @@ pub async fn delete_all_terminology_data(
 pub async fn cleanup_ghost_versions(
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let storage = state.storage.lock().await;
+    let _writing = state.write_lock.lock().await;
+    let storage = state.storage.as_ref();
 
     // Get all ghost version IDs
     let ghost_ids = storage
@@ -1017,11 +1196,5 @@ pub async fn cleanup_ghost_versions(
 
 /// Helper function to parse terminology type string
 fn parse_terminology_type(s: &str) -> Result<TerminologyType, String> {
-    match s.to_lowercase().as_str() {
-        "snomed" => Ok(TerminologyType::Snomed),
-        "loinc" => Ok(TerminologyType::Loinc),
-        "valuesets" => Ok(TerminologyType::ValueSets),
-        "amt" => Ok(TerminologyType::Amt),
-        _ => Err(format!("Unknown terminology type: {}", s)),
-    }
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
 }
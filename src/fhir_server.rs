@@ -0,0 +1,936 @@
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Query, Request, State};
+use axum::http::{header, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, MethodRouter};
+use axum::Router;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use syndication_core::ecl::{EclEvaluator, EclParser};
+use syndication_core::parsers::snomed_rf2::{
+    ACCEPTABLE_ACCEPTABILITY_ID, CONCEPT_INACTIVATION_REFSET_ID, FSN_TYPE_ID, PREFERRED_ACCEPTABILITY_ID,
+    SYNONYM_TYPE_ID,
+};
+use syndication_core::queries::{
+    Acceptability, CodeLookupResult, DesignationKind, TerminologyQueries, ValueSetConceptResult, AMT_SYSTEM,
+    SNOMED_SYSTEM,
+};
+use syndication_core::search::TerminologySearch;
+use syndication_core::storage::{ContentFamily, StorageError, TerminologyStorage};
+
+/// Default localhost port for the FHIR terminology server (override with FHIR_SERVER_PORT)
+pub const DEFAULT_PORT: u16 = 8765;
+
+/// Default page size for ValueSet/$expand when no `count` is given
+const DEFAULT_EXPAND_COUNT: usize = 1000;
+
+/// Cap on search hits considered for a text-filtered implicit SNOMED expansion
+const FILTER_SEARCH_LIMIT: usize = 1000;
+
+/// Shared state for the FHIR endpoint: the same storage and searcher as the Tauri AppState
+#[derive(Clone)]
+pub struct FhirServerState {
+    pub storage: Arc<TerminologyStorage>,
+    pub searcher: Arc<TerminologySearch>,
+}
+
+/// Read the FHIR server port from FHIR_SERVER_PORT, falling back to DEFAULT_PORT
+pub fn port_from_env() -> u16 {
+    std::env::var("FHIR_SERVER_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_PORT)
+}
+
+/// Build the FHIR R4 terminology routes under /fhir
+pub fn router(state: FhirServerState) -> Router {
+    Router::new()
+        .route("/fhir/metadata", get(capability_statement))
+        .route("/fhir/CodeSystem/$lookup", operation(lookup))
+        .route("/fhir/CodeSystem/$subsumes", operation(subsumes))
+        .route("/fhir/ValueSet/$expand", operation(expand))
+        .route("/fhir/ValueSet/$validate-code", operation(validate_code))
+        .route("/fhir/ConceptMap/$translate", operation(translate))
+        .with_state(state)
+}
+
+/// GET and POST routes for an operation, run on the blocking thread pool
+/// The operations read redb and Tantivy and evaluate ECL synchronously, which would stall the async workers
+fn operation(run: fn(&FhirServerState, &OperationParams) -> FhirResult) -> MethodRouter<FhirServerState> {
+    let handler = move |State(state): State<FhirServerState>, params: OperationParams| async move {
+        tokio::task::spawn_blocking(move || run(&state, &params))
+            .await
+            .unwrap_or_else(|e| Err(FhirError::from(e)))
+    };
+    get(handler).post(handler)
+}
+
+/// Serve the FHIR endpoint on 127.0.0.1 until the process exits
+pub async fn serve(state: FhirServerState, port: u16) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+    println!("FHIR terminology server listening on http://127.0.0.1:{}/fhir", port);
+
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+/// Error returned to FHIR clients as an OperationOutcome
+struct FhirError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl FhirError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid", message)
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not-found", message)
+    }
+
+    fn not_supported(status: StatusCode, message: impl Into<String>) -> Self {
+        Self::new(status, "not-supported", message)
+    }
+}
+
+/// Anything else (storage, search or evaluation failures) is a server-side exception
+impl<E: Into<anyhow::Error>> From<E> for FhirError {
+    fn from(err: E) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "exception", err.into().to_string())
+    }
+}
+
+impl IntoResponse for FhirError {
+    fn into_response(self) -> Response {
+        let outcome = fhir_json(json!({
+            "resourceType": "OperationOutcome",
+            "issue": [{
+                "severity": "error",
+                "code": self.code,
+                "diagnostics": self.message,
+            }]
+        }));
+
+        (self.status, outcome).into_response()
+    }
+}
+
+type FhirResult = Result<Response, FhirError>;
+
+fn fhir_json(body: Value) -> Response {
+    ([(header::CONTENT_TYPE, "application/fhir+json")], body.to_string()).into_response()
+}
+
+/// Operation parameters from the query string (GET) and/or a Parameters resource body (POST)
+struct OperationParams(HashMap<String, String>);
+
+impl OperationParams {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(|s| s.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, FhirError> {
+        self.get(name).ok_or_else(|| {
+            FhirError::new(
+                StatusCode::BAD_REQUEST,
+                "required",
+                format!("Missing required parameter '{}'", name),
+            )
+        })
+    }
+
+    fn usize(&self, name: &str) -> Result<Option<usize>, FhirError> {
+        self.get(name)
+            .map(|v| {
+                v.parse().map_err(|_| {
+                    FhirError::invalid(format!("Parameter '{}' must be a non-negative integer", name))
+                })
+            })
+            .transpose()
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for OperationParams {
+    type Rejection = FhirError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let mut params = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+            .map(|q| q.0)
+            .map_err(|e| FhirError::invalid(e.to_string()))?;
+
+        if req.method() == Method::POST {
+            let body = Bytes::from_request(req, state)
+                .await
+                .map_err(|e| FhirError::invalid(e.to_string()))?;
+
+            if !body.is_empty() {
+                let resource: Value = serde_json::from_slice(&body)
+                    .map_err(|e| FhirError::invalid(format!("Invalid JSON body: {}", e)))?;
+                params.extend(parameters_from_resource(&resource));
+            }
+        }
+
+        Ok(OperationParams(params))
+    }
+}
+
+/// Flatten a FHIR Parameters resource into name -> value strings
+/// Codings are split into their system and code (`coding` -> code, `codingA` -> codeA, ...)
+fn parameters_from_resource(resource: &Value) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+
+    for parameter in resource
+        .get("parameter")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let Some(name) = parameter.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let Some((_, value)) = parameter
+            .as_object()
+            .and_then(|p| p.iter().find(|(key, _)| key.starts_with("value")))
+        else {
+            continue;
+        };
+
+        match value {
+            Value::String(s) => {
+                params.insert(name.to_string(), s.clone());
+            }
+            Value::Number(_) | Value::Bool(_) => {
+                params.insert(name.to_string(), value.to_string());
+            }
+            Value::Object(coding) => {
+                let code_name = name.replacen("coding", "code", 1);
+                if let Some(code) = coding.get("code").and_then(|v| v.as_str()) {
+                    params.insert(code_name, code.to_string());
+                }
+                if let Some(system) = coding.get("system").and_then(|v| v.as_str()) {
+                    params.entry("system".to_string()).or_insert_with(|| system.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    params
+}
+
+/// Map an implicit SNOMED ValueSet URL (http://snomed.info/sct?fhir_vs...) to ECL
+/// ECL that does not parse is the client's error, not the server's
+fn implicit_snomed_ecl(valueset_url: &str) -> Option<Result<String, FhirError>> {
+    if !valueset_url.starts_with("http://snomed.info/sct?fhir_vs") {
+        return None;
+    }
+
+    let fhir_vs = url::Url::parse(valueset_url)
+        .ok()
+        .and_then(|u| u.query_pairs().find(|(k, _)| k == "fhir_vs").map(|(_, v)| v.into_owned()))
+        .unwrap_or_default();
+
+    let ecl = if fhir_vs.is_empty() {
+        Ok("*".to_string())
+    } else if let Some(code) = fhir_vs.strip_prefix("isa/") {
+        Ok(format!("<< {}", code))
+    } else if let Some(refset) = fhir_vs.strip_prefix("refset/") {
+        Ok(format!("^ {}", refset))
+    } else if let Some(ecl) = fhir_vs.strip_prefix("ecl/") {
+        Ok(ecl.to_string())
+    } else {
+        Err(FhirError::not_supported(
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported implicit ValueSet: {}", valueset_url),
+        ))
+    };
+
+    Some(ecl.and_then(|ecl| match EclParser::parse(&ecl) {
+        Ok(_) => Ok(ecl),
+        Err(e) => Err(FhirError::invalid(format!("Invalid ECL in {}: {}", valueset_url, e))),
+    }))
+}
+
+async fn capability_statement() -> Response {
+    let operation = |name: &str, definition: &str| json!({ "name": name, "definition": definition });
+
+    fhir_json(json!({
+        "resourceType": "CapabilityStatement",
+        "status": "active",
+        "kind": "instance",
+        "fhirVersion": "4.0.1",
+        "format": ["json"],
+        "rest": [{
+            "mode": "server",
+            "resource": [
+                {
+                    "type": "CodeSystem",
+                    "operation": [
+                        operation("lookup", "http://hl7.org/fhir/OperationDefinition/CodeSystem-lookup"),
+                        operation("subsumes", "http://hl7.org/fhir/OperationDefinition/CodeSystem-subsumes"),
+                    ]
+                },
+                {
+                    "type": "ValueSet",
+                    "operation": [
+                        operation("expand", "http://hl7.org/fhir/OperationDefinition/ValueSet-expand"),
+                        operation("validate-code", "http://hl7.org/fhir/OperationDefinition/ValueSet-validate-code"),
+                    ]
+                },
+                {
+                    "type": "ConceptMap",
+                    "operation": [
+                        operation("translate", "http://hl7.org/fhir/OperationDefinition/ConceptMap-translate"),
+                    ]
+                }
+            ]
+        }]
+    }))
+}
+
+/// CodeSystem/$lookup?system=&code=[&version=][&date=]
+fn lookup(state: &FhirServerState, params: &OperationParams) -> FhirResult {
+    let system = params.required("system")?;
+    let code = params.required("code")?;
+
+    let storage = state.storage.as_ref();
+    let family = match system {
+        SNOMED_SYSTEM => ContentFamily::Snomed,
+        AMT_SYSTEM => ContentFamily::Amt,
+        _ => ContentFamily::ValueSets,
+    };
+    let storage = select_release(storage, family, params.get("version"))?;
+
+    // `date` asks for the concept as it stood on that day, answered from the SNOMED RF2 Full history
+    if let Some(date) = params.get("date") {
+        if system != SNOMED_SYSTEM {
+            return Err(FhirError::not_supported(
+                StatusCode::BAD_REQUEST,
+                format!("$lookup with a date is only supported for {}", SNOMED_SYSTEM),
+            ));
+        }
+        let as_of = TerminologyQueries::parse_as_of(date.get(..10).unwrap_or(date))
+            .map_err(|e| FhirError::invalid(e.to_string()))?;
+        if !storage.has_snomed_full_release()? {
+            return Err(FhirError::not_supported(
+                StatusCode::BAD_REQUEST,
+                "$lookup with a date needs an imported SNOMED CT-AU RF2 Full release",
+            ));
+        }
+        let result = TerminologyQueries::lookup_snomed_code_as_of(&storage, code, &as_of)?
+            .ok_or_else(|| FhirError::not_found(format!("Code {} not found in {} on {}", code, system, date)))?;
+        return Ok(lookup_parameters("SNOMED CT", result));
+    }
+
+    let result = match system {
+        // AMT concepts are SCTIDs, so fall back to AMT when SNOMED has no match
+        SNOMED_SYSTEM => match TerminologyQueries::lookup_snomed_code(&storage, code)? {
+            Some(result) => Some(result),
+            None => TerminologyQueries::lookup_amt_code(&storage, code)?,
+        },
+        AMT_SYSTEM => TerminologyQueries::lookup_amt_code(&storage, code)?,
+        // Any other system is answered from an imported FHIR CodeSystem
+        _ => {
+            let codesystem = storage
+                .get_codesystem(system)?
+                .ok_or_else(|| FhirError::not_found(format!("CodeSystem not available: {}", system)))?;
+            let result = TerminologyQueries::lookup_codesystem_code(&storage, system, code)?
+                .ok_or_else(|| FhirError::not_found(format!("Code {} not found in {}", code, system)))?;
+            let name = codesystem.title.or(codesystem.name).unwrap_or(codesystem.url);
+            return Ok(lookup_parameters(&name, result));
+        }
+    };
+    let result =
+        result.ok_or_else(|| FhirError::not_found(format!("Code {} not found in {}", code, system)))?;
+
+    let name = if system == SNOMED_SYSTEM { "SNOMED CT" } else { "AMT" };
+    Ok(lookup_parameters(name, result))
+}
+
+/// Storage view for the `version` parameter: an imported release's local id, version or version URI
+/// Without it the live release answers
+fn select_release(
+    storage: &TerminologyStorage,
+    family: ContentFamily,
+    version: Option<&str>,
+) -> Result<TerminologyStorage, FhirError> {
+    match storage.select_release(family, version) {
+        Ok(storage) => Ok(storage),
+        Err(StorageError::NotFound(message)) => Err(FhirError::not_found(message)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Release selector from `system-version` (`system|version`), when it names the given system
+fn system_version<'a>(params: &'a OperationParams, system: &str) -> Option<&'a str> {
+    params.get("system-version")?.strip_prefix(system)?.strip_prefix('|')
+}
+
+/// Build the $lookup Parameters response for a code
+fn lookup_parameters(name: &str, result: CodeLookupResult) -> Response {
+    let mut parameter = vec![
+        json!({ "name": "name", "valueString": name }),
+        json!({ "name": "display", "valueString": result.display }),
+        json!({
+            "name": "property",
+            "part": [
+                { "name": "code", "valueCode": "inactive" },
+                { "name": "value", "valueBoolean": !result.active },
+            ]
+        }),
+    ];
+    // Inactivation reason and historical associations, as properties coded by their refset id
+    if let Some(inactivation) = &result.inactivation {
+        if let Some(reason_code) = &inactivation.reason_code {
+            let mut coding = json!({ "system": SNOMED_SYSTEM, "code": reason_code });
+            if let Some(reason) = &inactivation.reason {
+                coding["display"] = json!(reason);
+            }
+            parameter.push(json!({
+                "name": "property",
+                "part": [
+                    { "name": "code", "valueCode": CONCEPT_INACTIVATION_REFSET_ID },
+                    { "name": "value", "valueCoding": coding },
+                ]
+            }));
+        }
+        parameter.extend(inactivation.associations.iter().map(|association| {
+            json!({
+                "name": "property",
+                "part": [
+                    { "name": "code", "valueCode": association.refset_id },
+                    { "name": "value", "valueCode": association.code },
+                    { "name": "description", "valueString": association.association },
+                ]
+            })
+        }));
+    }
+    // CodeSystem properties, including parent and child, typed as they were in the CodeSystem
+    parameter.extend(result.properties.iter().map(|property| {
+        let value = match property.value_type.as_str() {
+            "boolean" => json!({ "name": "value", "valueBoolean": property.value == "true" }),
+            "integer" | "decimal" => match property.value.parse::<serde_json::Number>() {
+                Ok(number) => json!({ "name": "value", format!("value{}", capitalise(&property.value_type)): number }),
+                Err(_) => json!({ "name": "value", "valueString": property.value }),
+            },
+            "coding" | "code" => json!({ "name": "value", "valueCode": property.value }),
+            other => json!({ "name": "value", format!("value{}", capitalise(other)): property.value }),
+        };
+        json!({ "name": "property", "part": [{ "name": "code", "valueCode": property.code }, value] })
+    }));
+    parameter.extend(result.designations.iter().map(|designation| {
+        let mut part = vec![json!({ "name": "value", "valueString": designation.value })];
+        if let Some(language) = &designation.language {
+            part.insert(0, json!({ "name": "language", "valueCode": language }));
+        }
+        if result.system == SNOMED_SYSTEM {
+            let use_code = match designation.kind {
+                DesignationKind::Fsn => FSN_TYPE_ID,
+                DesignationKind::Synonym => SYNONYM_TYPE_ID,
+            };
+            part.insert(
+                part.len() - 1,
+                json!({ "name": "use", "valueCoding": { "system": SNOMED_SYSTEM, "code": use_code } }),
+            );
+            // en-AU acceptability, as an R5-style additionalUse
+            if let Some(acceptability) = designation.acceptability {
+                let (code, display) = match acceptability {
+                    Acceptability::Preferred => (PREFERRED_ACCEPTABILITY_ID, "Preferred"),
+                    Acceptability::Acceptable => (ACCEPTABLE_ACCEPTABILITY_ID, "Acceptable"),
+                };
+                part.push(json!({
+                    "name": "additionalUse",
+                    "valueCoding": { "system": SNOMED_SYSTEM, "code": code, "display": display }
+                }));
+            }
+        }
+        json!({ "name": "designation", "part": part })
+    }));
+
+    fhir_json(json!({ "resourceType": "Parameters", "parameter": parameter }))
+}
+
+/// Upper-case the first letter of a FHIR type name, e.g. dateTime -> DateTime for valueDateTime
+fn capitalise(type_name: &str) -> String {
+    let mut chars = type_name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// CodeSystem/$subsumes?system=&codeA=&codeB=[&version=]
+fn subsumes(state: &FhirServerState, params: &OperationParams) -> FhirResult {
+    let code_a = params.required("codeA")?;
+    let code_b = params.required("codeB")?;
+    let system = params.get("system").unwrap_or(SNOMED_SYSTEM);
+    if system != SNOMED_SYSTEM {
+        return Err(FhirError::not_supported(
+            StatusCode::BAD_REQUEST,
+            format!("Subsumption testing is only supported for {}", SNOMED_SYSTEM),
+        ));
+    }
+
+    let storage = state.storage.as_ref();
+    let storage = select_release(storage, ContentFamily::Snomed, params.get("version"))?;
+    for code in [code_a, code_b] {
+        if storage.get_snomed_concept(code)?.is_none() {
+            return Err(FhirError::not_found(format!("Code {} not found in {}", code, SNOMED_SYSTEM)));
+        }
+    }
+
+    let result = TerminologyQueries::subsumes(&storage, code_a, code_b)?;
+    Ok(fhir_json(json!({
+        "resourceType": "Parameters",
+        "parameter": [{ "name": "outcome", "valueCode": result.outcome }]
+    })))
+}
+
+/// ValueSet/$expand?url=&filter=&offset=&count=[&valueSetVersion=][&system-version=]
+/// Supports stored ValueSets and implicit SNOMED ValueSets (fhir_vs, isa/, refset/, ecl/)
+/// `valueSetVersion` picks the release of a stored ValueSet, `system-version` the SNOMED release of an implicit one
+fn expand(state: &FhirServerState, params: &OperationParams) -> FhirResult {
+    let url = params.required("url")?;
+    let offset = params.usize("offset")?.unwrap_or(0);
+    let count = params.usize("count")?.unwrap_or(DEFAULT_EXPAND_COUNT);
+    let filter = params.get("filter").map(|f| f.to_lowercase());
+
+    // Each branch yields the ValueSet header, the full match count and the requested page
+    let (mut valueset, total, page) = match implicit_snomed_ecl(url).transpose()? {
+        Some(ecl) => {
+            let storage = state.storage.as_ref();
+            let storage = select_release(storage, ContentFamily::Snomed, system_version(params, SNOMED_SYSTEM))?;
+            let header = json!({ "resourceType": "ValueSet", "url": url, "status": "active" });
+            match &filter {
+                Some(filter) => {
+                    let searcher = state.searcher.for_view(&storage)?;
+                    let concepts = expand_implicit_with_filter(&storage, &searcher, &ecl, filter)?;
+                    let (total, page) = page_of(concepts, offset, count);
+                    (header, total, page)
+                }
+                None => {
+                    // evaluate_ecl already orders and truncates, so only the offset is left to skip
+                    let result = TerminologyQueries::evaluate_ecl(&storage, &ecl, offset + count)?;
+                    (header, result.total, result.concepts.into_iter().skip(offset).collect())
+                }
+            }
+        }
+        None => {
+            let storage = state.storage.as_ref();
+            let storage = select_release(storage, ContentFamily::ValueSets, params.get("valueSetVersion"))?;
+            let expansion = TerminologyQueries::expand_valueset(&storage, url)?
+                .ok_or_else(|| FhirError::not_found(format!("ValueSet not found: {}", url)))?;
+
+            let mut concepts = expansion.concepts;
+            if let Some(filter) = &filter {
+                concepts.retain(|c| {
+                    c.display.as_deref().is_some_and(|d| d.to_lowercase().contains(filter.as_str()))
+                });
+            }
+
+            let header = json!({
+                "resourceType": "ValueSet",
+                "url": expansion.url,
+                "version": expansion.version,
+                "title": expansion.title,
+                "status": "active",
+            });
+            let (total, page) = page_of(concepts, offset, count);
+            (header, total, page)
+        }
+    };
+
+    let contains: Vec<Value> = page
+        .into_iter()
+        .map(|c| {
+            let mut entry = json!({ "system": c.system, "code": c.code });
+            if let Some(display) = c.display {
+                entry["display"] = json!(display);
+            }
+            entry
+        })
+        .collect();
+
+    valueset["expansion"] = json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "total": total,
+        "offset": offset,
+        "contains": contains,
+    });
+
+    Ok(fhir_json(valueset))
+}
+
+/// Split a full expansion into its total and one page
+fn page_of(
+    concepts: Vec<ValueSetConceptResult>,
+    offset: usize,
+    count: usize,
+) -> (usize, Vec<ValueSetConceptResult>) {
+    let total = concepts.len();
+    (total, concepts.into_iter().skip(offset).take(count).collect())
+}
+
+/// Text-filtered expansion of an implicit SNOMED ValueSet using the Tantivy index
+fn expand_implicit_with_filter(
+    storage: &TerminologyStorage,
+    searcher: &TerminologySearch,
+    ecl: &str,
+    filter: &str,
+) -> Result<Vec<ValueSetConceptResult>> {
+    let hits = TerminologyQueries::search_snomed(searcher, storage, filter, FILTER_SEARCH_LIMIT)?;
+
+    let members = if ecl == "*" {
+        None
+    } else {
+        let parsed = EclParser::parse(ecl)?;
+        Some(EclEvaluator::new(storage).evaluate(&parsed)?)
+    };
+
+    let mut seen = HashSet::new();
+    Ok(hits
+        .into_iter()
+        .filter(|hit| members.as_ref().is_none_or(|m| m.contains(&hit.code)))
+        .filter(|hit| seen.insert(hit.code.clone()))
+        .map(|hit| ValueSetConceptResult {
+            system: SNOMED_SYSTEM.to_string(),
+            code: hit.code,
+            display: Some(hit.display),
+        })
+        .collect())
+}
+
+/// ValueSet/$validate-code?url=&system=&code=[&valueSetVersion=][&system-version=]
+/// The release is picked as for $expand
+fn validate_code(state: &FhirServerState, params: &OperationParams) -> FhirResult {
+    let url = params.required("url")?;
+    let system = params.required("system")?;
+    let code = params.required("code")?;
+
+    let storage = state.storage.as_ref();
+    let (valid, message) = match implicit_snomed_ecl(url).transpose()? {
+        Some(ecl) => {
+            let storage = select_release(storage, ContentFamily::Snomed, system_version(params, SNOMED_SYSTEM))?;
+            let parsed = EclParser::parse(&ecl)?;
+            let members = EclEvaluator::new(&storage).evaluate(&parsed)?;
+            let valid = system == SNOMED_SYSTEM && members.contains(code);
+            let message = if valid {
+                format!("Code {} is valid in ValueSet {}", code, url)
+            } else {
+                format!("Code {} not found in ValueSet {}", code, url)
+            };
+            (valid, message)
+        }
+        None => {
+            let storage = select_release(storage, ContentFamily::ValueSets, params.get("valueSetVersion"))?;
+            if storage.get_valueset(url)?.is_none() {
+                return Err(FhirError::not_found(format!("ValueSet not found: {}", url)));
+            }
+            let result = TerminologyQueries::validate_code(&storage, code, system, url)?;
+            (result.valid, result.message.unwrap_or_default())
+        }
+    };
+
+    Ok(fhir_json(json!({
+        "resourceType": "Parameters",
+        "parameter": [
+            { "name": "result", "valueBoolean": valid },
+            { "name": "message", "valueString": message },
+        ]
+    })))
+}
+
+/// ConceptMap/$translate?system=&code=[&url=][&targetsystem=][&reverse=][&version=]
+/// Without `url` every imported ConceptMap is searched; `version` picks the release holding the ConceptMaps
+fn translate(state: &FhirServerState, params: &OperationParams) -> FhirResult {
+    let system = params.required("system")?;
+    let code = params.required("code")?;
+    let url = params.get("url");
+    let reverse = match params.get("reverse") {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(other) => {
+            return Err(FhirError::invalid(format!("Parameter 'reverse' must be true or false, not {}", other)));
+        }
+    };
+
+    let storage = state.storage.as_ref();
+    let storage = select_release(storage, ContentFamily::ValueSets, params.get("version"))?;
+    if let Some(url) = url {
+        if storage.get_concept_map(url)?.is_none() {
+            return Err(FhirError::not_found(format!("ConceptMap not found: {}", url)));
+        }
+    }
+    let translation =
+        TerminologyQueries::translate(&storage, system, code, url, params.get("targetsystem"), reverse)?;
+
+    let mut parameter = vec![json!({ "name": "result", "valueBoolean": translation.result })];
+    if let Some(message) = &translation.message {
+        parameter.push(json!({ "name": "message", "valueString": message }));
+    }
+    parameter.extend(translation.matches.iter().map(|m| {
+        let mut part = vec![json!({ "name": "equivalence", "valueCode": m.equivalence })];
+        if let Some(code) = &m.code {
+            let mut coding = json!({ "code": code });
+            if let Some(system) = &m.system {
+                coding["system"] = json!(system);
+            }
+            if let Some(display) = &m.display {
+                coding["display"] = json!(display);
+            }
+            part.push(json!({ "name": "concept", "valueCoding": coding }));
+        }
+        part.push(json!({ "name": "source", "valueUri": m.source }));
+        json!({ "name": "match", "part": part })
+    }));
+
+    Ok(fhir_json(json!({ "resourceType": "Parameters", "parameter": parameter })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syndication_core::storage::{ValueSet, ValueSetConcept};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_parameters_from_resource() {
+        let resource = json!({
+            "resourceType": "Parameters",
+            "parameter": [
+                { "name": "url", "valueUri": "http://example.org/ValueSet/test" },
+                { "name": "count", "valueInteger": 10 },
+                { "name": "codingA", "valueCoding": { "system": "http://snomed.info/sct", "code": "73211009" } },
+                { "name": "ignored" }
+            ]
+        });
+
+        let params = parameters_from_resource(&resource);
+        assert_eq!(params.get("url").map(|s| s.as_str()), Some("http://example.org/ValueSet/test"));
+        assert_eq!(params.get("count").map(|s| s.as_str()), Some("10"));
+        assert_eq!(params.get("codeA").map(|s| s.as_str()), Some("73211009"));
+        assert_eq!(params.get("system").map(|s| s.as_str()), Some("http://snomed.info/sct"));
+        assert!(!params.contains_key("ignored"));
+    }
+
+    #[test]
+    fn test_implicit_snomed_ecl() {
+        let ecl = |url: &str| implicit_snomed_ecl(url).map(|r| r.ok());
+
+        assert_eq!(ecl("http://snomed.info/sct?fhir_vs"), Some(Some("*".to_string())));
+        assert_eq!(ecl("http://snomed.info/sct?fhir_vs=isa/73211009"), Some(Some("<< 73211009".to_string())));
+        assert_eq!(
+            ecl("http://snomed.info/sct?fhir_vs=ecl/%3C%3C%2073211009"),
+            Some(Some("<< 73211009".to_string()))
+        );
+        assert_eq!(ecl("http://snomed.info/sct?fhir_vs=bogus"), Some(None));
+        assert_eq!(ecl("http://snomed.info/sct?fhir_vs=ecl/%3C%3C%20("), Some(None));
+        assert_eq!(ecl("http://snomed.info/sct?fhir_vs=isa/diabetes"), Some(None));
+        assert_eq!(ecl("http://example.org/ValueSet/test"), None);
+    }
+
+    fn test_state() -> (tempfile::TempDir, FhirServerState) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let storage = TerminologyStorage::new(dir.path().join("test.redb"), dir.path().join("data"))
+            .expect("Failed to create storage");
+        let searcher = TerminologySearch::new(&dir.path().join("index")).expect("Failed to create indexes");
+        let state = FhirServerState {
+            storage: Arc::new(storage),
+            searcher: Arc::new(searcher),
+        };
+        (dir, state)
+    }
+
+    async fn get(state: &FhirServerState, path: &str, params: &[(&str, &str)]) -> (StatusCode, Value) {
+        let query = url::form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+        let request = Request::builder()
+            .uri(format!("{}?{}", path, query))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn issue_code(outcome: &Value) -> &str {
+        outcome["issue"][0]["code"].as_str().unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_operation_errors() {
+        let (_dir, state) = test_state();
+
+        let (status, outcome) = get(&state, "/fhir/CodeSystem/$lookup", &[("system", SNOMED_SYSTEM)]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::BAD_REQUEST, "required"));
+
+        let (status, outcome) =
+            get(&state, "/fhir/CodeSystem/$lookup", &[("system", SNOMED_SYSTEM), ("code", "73211009")]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::NOT_FOUND, "not-found"));
+
+        let missing = "http://example.org/vs/missing";
+        let (status, outcome) = get(&state, "/fhir/ValueSet/$expand", &[("url", missing)]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::NOT_FOUND, "not-found"));
+
+        let (status, outcome) = get(&state, "/fhir/ValueSet/$expand", &[("url", missing), ("count", "-1")]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::BAD_REQUEST, "invalid"));
+
+        let malformed = "http://snomed.info/sct?fhir_vs=ecl/%3C%3C%20(";
+        let (status, outcome) = get(&state, "/fhir/ValueSet/$expand", &[("url", malformed)]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::BAD_REQUEST, "invalid"));
+        let (status, outcome) =
+            get(&state, "/fhir/ValueSet/$expand", &[("url", malformed), ("filter", "diabetes")]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::BAD_REQUEST, "invalid"));
+        let (status, outcome) = get(
+            &state,
+            "/fhir/ValueSet/$validate-code",
+            &[("url", malformed), ("system", SNOMED_SYSTEM), ("code", "73211009")],
+        )
+        .await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::BAD_REQUEST, "invalid"));
+
+        let (status, outcome) = get(
+            &state,
+            "/fhir/ConceptMap/$translate",
+            &[("system", SNOMED_SYSTEM), ("code", "73211009"), ("url", "http://example.org/cm/missing")],
+        )
+        .await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::NOT_FOUND, "not-found"));
+    }
+
+    #[tokio::test]
+    async fn test_expand_paging() {
+        let (_dir, state) = test_state();
+        let url = "http://example.org/vs/test";
+        state
+            .storage
+            .insert_valueset(&ValueSet {
+                url: url.to_string(),
+                version: Some("1.0.0".to_string()),
+                name: None,
+                title: Some("Test".to_string()),
+                status: Some("active".to_string()),
+                description: None,
+                publisher: None,
+                version_id: 0,
+            })
+            .unwrap();
+        for (code, display) in [("1001", "Asthma"), ("1002", "Bronchitis"), ("1003", "Croup")] {
+            state
+                .storage
+                .insert_valueset_concept(&ValueSetConcept {
+                    valueset_url: url.to_string(),
+                    system: SNOMED_SYSTEM.to_string(),
+                    code: code.to_string(),
+                    display: Some(display.to_string()),
+                })
+                .unwrap();
+        }
+
+        let (status, valueset) =
+            get(&state, "/fhir/ValueSet/$expand", &[("url", url), ("offset", "1"), ("count", "1")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(valueset["version"], "1.0.0");
+        assert_eq!(valueset["expansion"]["total"], 3);
+        assert_eq!(valueset["expansion"]["offset"], 1);
+        let contains = valueset["expansion"]["contains"].as_array().unwrap();
+        assert_eq!(contains.len(), 1);
+        assert_eq!(contains[0]["code"], "1002");
+
+        let (_, valueset) = get(&state, "/fhir/ValueSet/$expand", &[("url", url), ("filter", "CROUP")]).await;
+        assert_eq!(valueset["expansion"]["total"], 1);
+        assert_eq!(valueset["expansion"]["contains"][0]["display"], "Croup");
+
+        let (status, parameters) = get(
+            &state,
+            "/fhir/ValueSet/$validate-code",
+            &[("url", url), ("system", SNOMED_SYSTEM), ("code", "1003")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(parameters["parameter"][0]["valueBoolean"], true);
+
+        // An implicit ValueSet over an empty SNOMED release expands to nothing rather than failing
+        let implicit = "http://snomed.info/sct?fhir_vs=isa/73211009";
+        let (status, valueset) = get(&state, "/fhir/ValueSet/$expand", &[("url", implicit)]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(valueset["expansion"]["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_release_selection() {
+        let (_dir, state) = test_state();
+        let storage = state.storage.as_ref();
+        let v1 = storage.record_version("valuesets", "2025-03-01", None, "", None, None, None, None).unwrap();
+        storage.mark_imported(v1).unwrap();
+        storage.set_live_release(ContentFamily::ValueSets, Some(v1)).unwrap();
+
+        let v2 = storage.record_version("valuesets", "2025-04-01", None, "", None, None, None, None).unwrap();
+        let side = storage.release_view(&storage.get_version(v2).unwrap().unwrap()).unwrap();
+        let url = "http://example.org/vs/side";
+        side.insert_valueset(&ValueSet {
+            url: url.to_string(),
+            version: None,
+            name: None,
+            title: Some("Side".to_string()),
+            status: Some("active".to_string()),
+            description: None,
+            publisher: None,
+            version_id: v2,
+        })
+        .unwrap();
+        side.insert_valueset_concept(&ValueSetConcept {
+            valueset_url: url.to_string(),
+            system: SNOMED_SYSTEM.to_string(),
+            code: "1001".to_string(),
+            display: Some("Asthma".to_string()),
+        })
+        .unwrap();
+        storage.mark_imported(v2).unwrap();
+
+        let (status, _) = get(&state, "/fhir/ValueSet/$expand", &[("url", url)]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "The live release does not have it");
+        let (status, valueset) =
+            get(&state, "/fhir/ValueSet/$expand", &[("url", url), ("valueSetVersion", "2025-04-01")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(valueset["expansion"]["contains"][0]["code"], "1001");
+
+        let v2_id = v2.to_string();
+        let (status, parameters) = get(
+            &state,
+            "/fhir/ValueSet/$validate-code",
+            &[("url", url), ("system", SNOMED_SYSTEM), ("code", "1001"), ("valueSetVersion", &v2_id)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(parameters["parameter"][0]["valueBoolean"], true);
+
+        let (status, outcome) =
+            get(&state, "/fhir/ValueSet/$expand", &[("url", url), ("valueSetVersion", "2025-05-01")]).await;
+        assert_eq!((status, issue_code(&outcome)), (StatusCode::NOT_FOUND, "not-found"));
+        let (status, _) = get(
+            &state,
+            "/fhir/ConceptMap/$translate",
+            &[("system", SNOMED_SYSTEM), ("code", "1001"), ("version", "2025-05-01")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let implicit = "http://snomed.info/sct?fhir_vs=isa/73211009";
+        let system_version = format!("{}|20250131", SNOMED_SYSTEM);
+        let (status, _) =
+            get(&state, "/fhir/ValueSet/$expand", &[("url", implicit), ("system-version", &system_version)]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "No such SNOMED CT release is imported");
+    }
+}
@@ -0,0 +1,142 @@
+pub mod commands;
+pub mod fhir_server;
+
+use commands::{
+    cancel_job, cleanup_ghost_versions, debug_amt_codes, diagnose_amt_index, delete_all_terminology_data,
+    delete_terminology_data, delete_terminology_file, evaluate_ecl, expand_valueset,
+    fetch_all_versions, fetch_latest_version, get_all_local_latest, get_amt_code_type_stats,
+    get_detailed_storage_info, get_local_latest, get_local_versions, get_refset_members, get_snomed_hierarchy,
+    get_storage_stats, import_local_file, import_terminology, is_refset_member, list_jobs, list_refsets, list_valuesets, lookup_code,
+    mark_as_latest, rebuild_amt_index, search_amt_doctor, search_amt_patient, search_terminology, subsumes,
+    sync_all_terminologies, sync_terminology, test_connection, translate, translate_code, validate_code,
+    AppState,
+};
+use fhir_server::FhirServerState;
+use std::sync::Arc;
+use syndication_core::storage::StorageError;
+use syndication_core::{
+    DataPaths, JobRegistry, NctsClient, TerminologySearch, TerminologyStorage, TokenManager,
+};
+use tauri::Manager;
+use tokio::sync::Mutex;
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Load environment variables from .env file
+    dotenvy::dotenv().ok(); // Ignore error if .env doesn't exist
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            // Get the app data directory
+            let DataPaths {
+                db_path,
+                terminology_data_dir,
+                index_dir,
+            } = DataPaths::from_project_dirs().expect("Failed to get project directories");
+
+            println!("Database path: {:?}", db_path);
+            println!("Data directory: {:?}", terminology_data_dir);
+            println!("Index directory: {:?}", index_dir);
+
+            // Initialize storage (redb)
+            let storage = match TerminologyStorage::new(db_path, terminology_data_dir) {
+                Ok(storage) => storage,
+                // A newer build has upgraded the database; this one must not touch it
+                Err(e @ StorageError::SchemaTooNew { .. }) => exit_on_startup_error("Cannot open the database", e),
+                Err(e) => exit_on_startup_error("Failed to initialize storage", e),
+            };
+
+            // Initialize Tantivy search indexes
+            let searcher = TerminologySearch::new(&index_dir)
+                .expect("Failed to initialize search indexes");
+
+            // Initialize token manager from environment variables
+            let token_manager = TokenManager::from_env()
+                .expect("Failed to create token manager - ensure NCTS_CLIENT_ID and NCTS_CLIENT_SECRET are set");
+
+            // Initialize NCTS client with authentication
+            let ncts_client = NctsClient::new(token_manager)
+                .expect("Failed to create NCTS client");
+
+            // Jobs a previous run left running are recorded as interrupted
+            let jobs = JobRegistry::new(&storage)
+                .unwrap_or_else(|e| exit_on_startup_error("Failed to load import jobs", e));
+
+            let storage = Arc::new(storage);
+            let searcher = Arc::new(searcher);
+
+            // Start the local FHIR terminology server over the same storage and indexes
+            let fhir_state = FhirServerState {
+                storage: storage.clone(),
+                searcher: searcher.clone(),
+            };
+            let fhir_port = fhir_server::port_from_env();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = fhir_server::serve(fhir_state, fhir_port).await {
+                    eprintln!("Warning: FHIR terminology server failed to start: {}", e);
+                }
+            });
+
+            // Create app state
+            let state = AppState {
+                ncts_client,
+                storage,
+                searcher,
+                write_lock: Mutex::new(()),
+                jobs,
+            };
+
+            app.manage(state);
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            fetch_latest_version,
+            fetch_all_versions,
+            sync_terminology,
+            sync_all_terminologies,
+            get_local_latest,
+            get_local_versions,
+            get_all_local_latest,
+            import_terminology,
+            import_local_file,
+            cancel_job,
+            list_jobs,
+            mark_as_latest,
+            search_terminology,
+            evaluate_ecl,
+            search_amt_patient,
+            search_amt_doctor,
+            get_amt_code_type_stats,
+            debug_amt_codes,
+            rebuild_amt_index,
+            diagnose_amt_index,
+            lookup_code,
+            get_snomed_hierarchy,
+            subsumes,
+            list_refsets,
+            get_refset_members,
+            is_refset_member,
+            translate_code,
+            translate,
+            expand_valueset,
+            validate_code,
+            list_valuesets,
+            get_storage_stats,
+            get_detailed_storage_info,
+            delete_terminology_file,
+            delete_terminology_data,
+            delete_all_terminology_data,
+            test_connection,
+            cleanup_ghost_versions,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+/// Report an error that leaves the app unable to start, and exit without a panic
+fn exit_on_startup_error(context: &str, error: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}: {}", context, error);
+    std::process::exit(1);
+}
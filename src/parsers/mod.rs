@@ -1,8 +0,0 @@
-pub mod snomed_rf2;
-pub mod amt_csv;
-pub mod valueset_r4;
-
-// Re-export commonly used items
-pub use snomed_rf2::{SnomedConcept, SnomedDescription, SnomedRf2Parser};
-pub use amt_csv::{AmtCode, AmtCsvParser};
-pub use valueset_r4::{ValueSetEntry, ValueSetR4Parser};
@@ -0,0 +1,854 @@
+//! SNOMED CT Expression Constraint Language (ECL) support
+//!
+//! Covers the brief syntax most value-set authors use day to day:
+//! hierarchy operators (`<`, `<<`, `<!`, `<<!`, `>`, `>>`, `>!`, `>>!`), reference set
+//! membership (`^`), the wildcard (`*`), compound constraints (AND / OR / MINUS, with `,`
+//! as AND) and attribute refinements with `=`, `!=`, attribute groups and cardinality.
+//! Reverse flags, dotted attributes and concrete values are rejected with a parse error.
+
+use crate::storage::{SnomedRelationship, TerminologyStorage};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+
+/// Hierarchy constraint operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum ConstraintOperator {
+    DescendantOf,       // <
+    DescendantOrSelfOf, // <<
+    ChildOf,            // <!
+    ChildOrSelfOf,      // <<!
+    AncestorOf,         // >
+    AncestorOrSelfOf,   // >>
+    ParentOf,           // >!
+    ParentOrSelfOf,     // >>!
+}
+
+/// The focus of a sub-expression: a concept, the wildcard, or a bracketed expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum EclFocus {
+    Concept(String),
+    Wildcard,
+    Nested(Box<EclExpression>),
+}
+
+/// Parsed ECL expression constraint
+#[derive(Debug, Clone, PartialEq)]
+pub enum EclExpression {
+    Sub {
+        operator: Option<ConstraintOperator>,
+        member_of: bool,
+        focus: EclFocus,
+    },
+    And(Box<EclExpression>, Box<EclExpression>),
+    Or(Box<EclExpression>, Box<EclExpression>),
+    Minus(Box<EclExpression>, Box<EclExpression>),
+    Refined {
+        focus: Box<EclExpression>,
+        refinement: EclRefinement,
+    },
+}
+
+/// Attribute cardinality `[min..max]`, where a missing max means `*`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cardinality {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Cardinality {
+    fn contains(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+/// Default cardinality when none is written is `[1..*]`
+const AT_LEAST_ONE: Cardinality = Cardinality { min: 1, max: None };
+
+/// Parsed refinement (the part after `:`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum EclRefinement {
+    Attribute {
+        cardinality: Option<Cardinality>,
+        name: Box<EclExpression>,
+        negated: bool,
+        value: Box<EclExpression>,
+    },
+    Group {
+        cardinality: Option<Cardinality>,
+        refinement: Box<EclRefinement>,
+    },
+    And(Box<EclRefinement>, Box<EclRefinement>),
+    Or(Box<EclRefinement>, Box<EclRefinement>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    ConceptId(String),
+    Term,
+    Operator(ConstraintOperator),
+    MemberOf,
+    Wildcard,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Colon,
+    Comma,
+    Equals,
+    NotEquals,
+    DotDot,
+    And,
+    Or,
+    Minus,
+}
+
+/// Binary operator kinds used to reject un-bracketed mixing (e.g. `A AND B OR C`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryKind {
+    And,
+    Or,
+    Minus,
+}
+
+pub struct EclParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl EclParser {
+    /// Parse an ECL expression string
+    pub fn parse(input: &str) -> Result<EclExpression> {
+        let tokens = Self::tokenize(input)?;
+        if tokens.is_empty() {
+            anyhow::bail!("Empty ECL expression");
+        }
+
+        let mut parser = Self { tokens, pos: 0 };
+        let expression = parser.parse_expression()?;
+
+        if let Some((token, offset)) = parser.tokens.get(parser.pos) {
+            anyhow::bail!("Unexpected {:?} at position {}", token, offset);
+        }
+
+        Ok(expression)
+    }
+
+    /// Split the input into tokens, dropping whitespace, comments and |term| text
+    fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let start = i;
+
+            if c.is_whitespace() {
+                i += 1;
+                continue;
+            }
+
+            // Block comments: /* ... */
+            if c == '/' && chars.get(i + 1) == Some(&'*') {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    anyhow::bail!("Unterminated comment at position {}", start);
+                }
+                i += 2;
+                continue;
+            }
+
+            let token = match c {
+                '0'..='9' => {
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    tokens.push((Token::ConceptId(chars[start..i].iter().collect()), start));
+                    continue;
+                }
+                'a'..='z' | 'A'..='Z' => {
+                    while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let word: String = chars[start..i].iter().collect();
+                    let token = match word.to_ascii_uppercase().as_str() {
+                        "AND" => Token::And,
+                        "OR" => Token::Or,
+                        "MINUS" => Token::Minus,
+                        _ => anyhow::bail!("Unsupported keyword '{}' at position {}", word, start),
+                    };
+                    tokens.push((token, start));
+                    continue;
+                }
+                '|' => {
+                    i += 1;
+                    while i < chars.len() && chars[i] != '|' {
+                        i += 1;
+                    }
+                    if i >= chars.len() {
+                        anyhow::bail!("Unterminated |term| at position {}", start);
+                    }
+                    i += 1;
+                    tokens.push((Token::Term, start));
+                    continue;
+                }
+                '<' | '>' => {
+                    let doubled = chars.get(i + 1) == Some(&c);
+                    let width = if doubled { 2 } else { 1 };
+                    let bang = chars.get(i + width) == Some(&'!');
+                    i += width + usize::from(bang);
+                    let operator = match (c, doubled, bang) {
+                        ('<', false, false) => ConstraintOperator::DescendantOf,
+                        ('<', true, false) => ConstraintOperator::DescendantOrSelfOf,
+                        ('<', false, true) => ConstraintOperator::ChildOf,
+                        ('<', true, true) => ConstraintOperator::ChildOrSelfOf,
+                        ('>', false, false) => ConstraintOperator::AncestorOf,
+                        ('>', true, false) => ConstraintOperator::AncestorOrSelfOf,
+                        ('>', false, true) => ConstraintOperator::ParentOf,
+                        _ => ConstraintOperator::ParentOrSelfOf,
+                    };
+                    tokens.push((Token::Operator(operator), start));
+                    continue;
+                }
+                '!' if chars.get(i + 1) == Some(&'=') => {
+                    i += 1;
+                    Token::NotEquals
+                }
+                '.' if chars.get(i + 1) == Some(&'.') => {
+                    i += 1;
+                    Token::DotDot
+                }
+                '^' => Token::MemberOf,
+                '*' => Token::Wildcard,
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                '{' => Token::LBrace,
+                '}' => Token::RBrace,
+                '[' => Token::LBracket,
+                ']' => Token::RBracket,
+                ':' => Token::Colon,
+                ',' => Token::Comma,
+                '=' => Token::Equals,
+                _ => anyhow::bail!("Unexpected character '{}' at position {}", c, start),
+            };
+
+            i += 1;
+            tokens.push((token, start));
+        }
+
+        Ok(tokens)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.tokens.get(self.pos) {
+            Some((token, _)) if *token == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some((token, offset)) => {
+                anyhow::bail!("Expected {:?} but found {:?} at position {}", expected, token, offset)
+            }
+            None => anyhow::bail!("Expected {:?} but reached end of expression", expected),
+        }
+    }
+
+    /// Map the next token to a binary operator kind, if it is one
+    fn peek_binary(&self, allow_minus: bool) -> Option<BinaryKind> {
+        match self.peek() {
+            Some(Token::And) | Some(Token::Comma) => Some(BinaryKind::And),
+            Some(Token::Or) => Some(BinaryKind::Or),
+            Some(Token::Minus) if allow_minus => Some(BinaryKind::Minus),
+            _ => None,
+        }
+    }
+
+    /// expression = sub [":" refinement] | sub (op sub)+
+    fn parse_expression(&mut self) -> Result<EclExpression> {
+        let mut expression = self.parse_sub()?;
+
+        if self.eat(&Token::Colon) {
+            let refinement = self.parse_refinement(false)?;
+            return Ok(EclExpression::Refined {
+                focus: Box::new(expression),
+                refinement,
+            });
+        }
+
+        let mut kind: Option<BinaryKind> = None;
+        while let Some(next) = self.peek_binary(true) {
+            if kind.is_some_and(|k| k != next || k == BinaryKind::Minus) {
+                anyhow::bail!("Mixed AND/OR/MINUS operators must be separated with brackets");
+            }
+            kind = Some(next);
+            self.pos += 1;
+
+            let right = Box::new(self.parse_sub()?);
+            let left = Box::new(expression);
+            expression = match next {
+                BinaryKind::And => EclExpression::And(left, right),
+                BinaryKind::Or => EclExpression::Or(left, right),
+                BinaryKind::Minus => EclExpression::Minus(left, right),
+            };
+        }
+
+        Ok(expression)
+    }
+
+    /// sub = [operator] ["^"] (conceptId [|term|] | "*" | "(" expression ")")
+    fn parse_sub(&mut self) -> Result<EclExpression> {
+        let operator = match self.peek() {
+            Some(Token::Operator(op)) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        };
+        let member_of = self.eat(&Token::MemberOf);
+
+        let focus = match self.advance() {
+            Some(Token::ConceptId(id)) => {
+                self.eat(&Token::Term);
+                EclFocus::Concept(id)
+            }
+            Some(Token::Wildcard) => EclFocus::Wildcard,
+            Some(Token::LParen) => {
+                let nested = self.parse_expression()?;
+                self.expect(Token::RParen)?;
+                EclFocus::Nested(Box::new(nested))
+            }
+            Some(token) => anyhow::bail!("Expected a concept, '*' or '(' but found {:?}", token),
+            None => anyhow::bail!("Expected a concept, '*' or '(' but reached end of expression"),
+        };
+
+        Ok(EclExpression::Sub {
+            operator,
+            member_of,
+            focus,
+        })
+    }
+
+    /// refinement = subRefinement ((AND | ",") subRefinement)* | subRefinement (OR subRefinement)*
+    fn parse_refinement(&mut self, in_group: bool) -> Result<EclRefinement> {
+        let mut refinement = self.parse_sub_refinement(in_group)?;
+
+        let mut kind: Option<BinaryKind> = None;
+        while let Some(next) = self.peek_binary(false) {
+            if kind.is_some_and(|k| k != next) {
+                anyhow::bail!("Mixed AND/OR refinements must be separated with brackets");
+            }
+            kind = Some(next);
+            self.pos += 1;
+
+            let right = Box::new(self.parse_sub_refinement(in_group)?);
+            let left = Box::new(refinement);
+            refinement = match next {
+                BinaryKind::Or => EclRefinement::Or(left, right),
+                _ => EclRefinement::And(left, right),
+            };
+        }
+
+        Ok(refinement)
+    }
+
+    /// subRefinement = "(" refinement ")" | [cardinality] "{" refinement "}" | attribute
+    fn parse_sub_refinement(&mut self, in_group: bool) -> Result<EclRefinement> {
+        if self.eat(&Token::LParen) {
+            let refinement = self.parse_refinement(in_group)?;
+            self.expect(Token::RParen)?;
+            return Ok(refinement);
+        }
+
+        let cardinality = if self.peek() == Some(&Token::LBracket) {
+            Some(self.parse_cardinality()?)
+        } else {
+            None
+        };
+
+        if self.eat(&Token::LBrace) {
+            if in_group {
+                anyhow::bail!("Attribute groups cannot be nested");
+            }
+            let refinement = self.parse_refinement(true)?;
+            self.expect(Token::RBrace)?;
+            return Ok(EclRefinement::Group {
+                cardinality,
+                refinement: Box::new(refinement),
+            });
+        }
+
+        let name = self.parse_sub()?;
+        let negated = match self.advance() {
+            Some(Token::Equals) => false,
+            Some(Token::NotEquals) => true,
+            Some(token) => anyhow::bail!("Expected '=' or '!=' after attribute name but found {:?}", token),
+            None => anyhow::bail!("Expected '=' or '!=' after attribute name"),
+        };
+        let value = self.parse_sub()?;
+
+        Ok(EclRefinement::Attribute {
+            cardinality,
+            name: Box::new(name),
+            negated,
+            value: Box::new(value),
+        })
+    }
+
+    /// cardinality = "[" min ".." (max | "*") "]"
+    fn parse_cardinality(&mut self) -> Result<Cardinality> {
+        self.expect(Token::LBracket)?;
+
+        let min = match self.advance() {
+            Some(Token::ConceptId(n)) => n.parse().context("Invalid cardinality minimum")?,
+            _ => anyhow::bail!("Expected a number for cardinality minimum"),
+        };
+        self.expect(Token::DotDot)?;
+        let max = match self.advance() {
+            Some(Token::ConceptId(n)) => Some(n.parse().context("Invalid cardinality maximum")?),
+            Some(Token::Wildcard) => None,
+            _ => anyhow::bail!("Expected a number or '*' for cardinality maximum"),
+        };
+        self.expect(Token::RBracket)?;
+
+        if max.is_some_and(|max| max < min) {
+            anyhow::bail!("Cardinality maximum is less than minimum");
+        }
+
+        Ok(Cardinality { min, max })
+    }
+}
+
+/// A concept set where `Any` stands in for the wildcard without materialising it
+enum ConceptSet {
+    Any,
+    Of(HashSet<String>),
+}
+
+impl ConceptSet {
+    fn contains(&self, id: &str) -> bool {
+        match self {
+            ConceptSet::Any => true,
+            ConceptSet::Of(set) => set.contains(id),
+        }
+    }
+}
+
+/// Refinement with attribute names and values already evaluated to concept sets
+enum CompiledRefinement {
+    Attribute {
+        cardinality: Cardinality,
+        names: ConceptSet,
+        negated: bool,
+        values: ConceptSet,
+    },
+    Group {
+        cardinality: Cardinality,
+        refinement: Box<CompiledRefinement>,
+    },
+    And(Box<CompiledRefinement>, Box<CompiledRefinement>),
+    Or(Box<CompiledRefinement>, Box<CompiledRefinement>),
+}
+
+/// Evaluates parsed ECL against the local SNOMED store
+pub struct EclEvaluator<'a> {
+    storage: &'a TerminologyStorage,
+}
+
+impl<'a> EclEvaluator<'a> {
+    pub fn new(storage: &'a TerminologyStorage) -> Self {
+        Self { storage }
+    }
+
+    /// Evaluate an expression to the set of matching concept ids
+    pub fn evaluate(&self, expression: &EclExpression) -> Result<HashSet<String>> {
+        match expression {
+            EclExpression::Sub {
+                operator,
+                member_of,
+                focus,
+            } => self.evaluate_sub(*operator, *member_of, focus),
+            EclExpression::And(left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Ok(left.intersection(&right).cloned().collect())
+            }
+            EclExpression::Or(left, right) => {
+                let mut left = self.evaluate(left)?;
+                left.extend(self.evaluate(right)?);
+                Ok(left)
+            }
+            EclExpression::Minus(left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Ok(left.difference(&right).cloned().collect())
+            }
+            EclExpression::Refined { focus, refinement } => {
+                let candidates = self.evaluate(focus)?;
+                let compiled = self.compile_refinement(refinement)?;
+
+                let mut matches = HashSet::new();
+                for concept_id in candidates {
+                    let relationships: Vec<SnomedRelationship> = self
+                        .storage
+                        .get_snomed_relationships(&concept_id)?
+                        .into_iter()
+                        .filter(|r| r.active)
+                        .collect();
+                    let relationships: Vec<&SnomedRelationship> = relationships.iter().collect();
+
+                    if Self::refinement_matches(&compiled, &relationships) {
+                        matches.insert(concept_id);
+                    }
+                }
+
+                Ok(matches)
+            }
+        }
+    }
+
+    fn evaluate_sub(
+        &self,
+        operator: Option<ConstraintOperator>,
+        member_of: bool,
+        focus: &EclFocus,
+    ) -> Result<HashSet<String>> {
+        if let EclFocus::Wildcard = focus {
+            if !member_of {
+                return self.evaluate_wildcard(operator);
+            }
+        }
+
+        let mut focus_set = match focus {
+            EclFocus::Concept(id) => {
+                let mut set = HashSet::new();
+                if self.storage.get_snomed_concept(id)?.is_some() {
+                    set.insert(id.clone());
+                }
+                set
+            }
+            EclFocus::Wildcard => self.all_concepts()?,
+            EclFocus::Nested(expression) => self.evaluate(expression)?,
+        };
+
+        if member_of {
+            focus_set = self.refset_members(&focus_set)?;
+        }
+
+        let Some(operator) = operator else {
+            return Ok(focus_set);
+        };
+
+        let mut result = HashSet::new();
+        for concept_id in &focus_set {
+            match operator {
+                ConstraintOperator::DescendantOf => {
+                    result.extend(self.storage.get_snomed_descendants(concept_id)?);
+                }
+                ConstraintOperator::DescendantOrSelfOf => {
+                    result.extend(self.storage.get_snomed_descendants(concept_id)?);
+                    result.insert(concept_id.clone());
+                }
+                ConstraintOperator::ChildOf => {
+                    result.extend(self.storage.get_snomed_children(concept_id)?);
+                }
+                ConstraintOperator::ChildOrSelfOf => {
+                    result.extend(self.storage.get_snomed_children(concept_id)?);
+                    result.insert(concept_id.clone());
+                }
+                ConstraintOperator::AncestorOf => {
+                    result.extend(self.storage.get_snomed_ancestors(concept_id)?);
+                }
+                ConstraintOperator::AncestorOrSelfOf => {
+                    result.extend(self.storage.get_snomed_ancestors(concept_id)?);
+                    result.insert(concept_id.clone());
+                }
+                ConstraintOperator::ParentOf => {
+                    result.extend(self.storage.get_snomed_parents(concept_id)?);
+                }
+                ConstraintOperator::ParentOrSelfOf => {
+                    result.extend(self.storage.get_snomed_parents(concept_id)?);
+                    result.insert(concept_id.clone());
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// `*` with a hierarchy operator: strict descendants are every concept with a parent,
+    /// strict ancestors are every concept with a child, and the "or self" forms are everything
+    fn evaluate_wildcard(&self, operator: Option<ConstraintOperator>) -> Result<HashSet<String>> {
+        match operator {
+            Some(ConstraintOperator::DescendantOf) | Some(ConstraintOperator::ChildOf) => {
+                let (_, children) = self.storage.get_snomed_isa_participants()?;
+                Ok(children)
+            }
+            Some(ConstraintOperator::AncestorOf) | Some(ConstraintOperator::ParentOf) => {
+                let (parents, _) = self.storage.get_snomed_isa_participants()?;
+                Ok(parents)
+            }
+            _ => self.all_concepts(),
+        }
+    }
+
+    fn all_concepts(&self) -> Result<HashSet<String>> {
+        Ok(self.storage.get_all_snomed_concept_ids()?.into_iter().collect())
+    }
+
+    /// Active members of whichever of the given concepts are imported reference sets
+    fn refset_members(&self, refset_ids: &HashSet<String>) -> Result<HashSet<String>> {
+        let mut members = HashSet::new();
+        for refset in self.storage.get_snomed_refsets()? {
+            if refset_ids.contains(&refset.refset_id) {
+                members.extend(self.storage.get_refset_member_ids(&refset.refset_id)?);
+            }
+        }
+        Ok(members)
+    }
+
+    /// Evaluate every attribute name and value in a refinement once, up front
+    fn compile_refinement(&self, refinement: &EclRefinement) -> Result<CompiledRefinement> {
+        Ok(match refinement {
+            EclRefinement::Attribute {
+                cardinality,
+                name,
+                negated,
+                value,
+            } => CompiledRefinement::Attribute {
+                cardinality: cardinality.unwrap_or(AT_LEAST_ONE),
+                names: self.compile_concept_set(name)?,
+                negated: *negated,
+                values: self.compile_concept_set(value)?,
+            },
+            EclRefinement::Group {
+                cardinality,
+                refinement,
+            } => CompiledRefinement::Group {
+                cardinality: cardinality.unwrap_or(AT_LEAST_ONE),
+                refinement: Box::new(self.compile_refinement(refinement)?),
+            },
+            EclRefinement::And(left, right) => CompiledRefinement::And(
+                Box::new(self.compile_refinement(left)?),
+                Box::new(self.compile_refinement(right)?),
+            ),
+            EclRefinement::Or(left, right) => CompiledRefinement::Or(
+                Box::new(self.compile_refinement(left)?),
+                Box::new(self.compile_refinement(right)?),
+            ),
+        })
+    }
+
+    fn compile_concept_set(&self, expression: &EclExpression) -> Result<ConceptSet> {
+        match expression {
+            EclExpression::Sub {
+                operator: None,
+                member_of: false,
+                focus: EclFocus::Wildcard,
+            } => Ok(ConceptSet::Any),
+            _ => Ok(ConceptSet::Of(self.evaluate(expression)?)),
+        }
+    }
+
+    /// Test a compiled refinement against one concept's active relationships
+    fn refinement_matches(refinement: &CompiledRefinement, relationships: &[&SnomedRelationship]) -> bool {
+        match refinement {
+            CompiledRefinement::Attribute {
+                cardinality,
+                names,
+                negated,
+                values,
+            } => {
+                let count = relationships
+                    .iter()
+                    .filter(|r| names.contains(&r.type_id) && values.contains(&r.destination_id) != *negated)
+                    .count();
+                cardinality.contains(count)
+            }
+            CompiledRefinement::Group {
+                cardinality,
+                refinement,
+            } => {
+                // Group 0 holds ungrouped relationships; each of those counts as its own group
+                let mut groups: HashMap<i32, Vec<&SnomedRelationship>> = HashMap::new();
+                let mut singletons = Vec::new();
+                for relationship in relationships {
+                    if relationship.relationship_group == 0 {
+                        singletons.push(vec![*relationship]);
+                    } else {
+                        groups.entry(relationship.relationship_group).or_default().push(relationship);
+                    }
+                }
+
+                let count = groups
+                    .values()
+                    .chain(singletons.iter())
+                    .filter(|group| Self::refinement_matches(refinement, group))
+                    .count();
+                cardinality.contains(count)
+            }
+            CompiledRefinement::And(left, right) => {
+                Self::refinement_matches(left, relationships) && Self::refinement_matches(right, relationships)
+            }
+            CompiledRefinement::Or(left, right) => {
+                Self::refinement_matches(left, relationships) || Self::refinement_matches(right, relationships)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn concept(id: &str) -> EclExpression {
+        EclExpression::Sub {
+            operator: None,
+            member_of: false,
+            focus: EclFocus::Concept(id.to_string()),
+        }
+    }
+
+    fn with_operator(operator: ConstraintOperator, id: &str) -> EclExpression {
+        EclExpression::Sub {
+            operator: Some(operator),
+            member_of: false,
+            focus: EclFocus::Concept(id.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_constraint_operators() {
+        let cases = [
+            ("< 404684003", ConstraintOperator::DescendantOf),
+            ("<< 404684003", ConstraintOperator::DescendantOrSelfOf),
+            ("<! 404684003", ConstraintOperator::ChildOf),
+            ("<<! 404684003", ConstraintOperator::ChildOrSelfOf),
+            ("> 404684003", ConstraintOperator::AncestorOf),
+            (">> 404684003", ConstraintOperator::AncestorOrSelfOf),
+            (">! 404684003", ConstraintOperator::ParentOf),
+            (">>! 404684003", ConstraintOperator::ParentOrSelfOf),
+        ];
+
+        for (input, operator) in cases {
+            assert_eq!(EclParser::parse(input).unwrap(), with_operator(operator, "404684003"), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_terms_and_comments_are_ignored() {
+        let parsed = EclParser::parse("<< 73211009 |Diabetes mellitus (disorder)| /* any diabetes */").unwrap();
+        assert_eq!(parsed, with_operator(ConstraintOperator::DescendantOrSelfOf, "73211009"));
+    }
+
+    #[test]
+    fn test_parse_compound_expressions() {
+        let parsed = EclParser::parse("<< 73211009 MINUS << 46635009").unwrap();
+        assert_eq!(
+            parsed,
+            EclExpression::Minus(
+                Box::new(with_operator(ConstraintOperator::DescendantOrSelfOf, "73211009")),
+                Box::new(with_operator(ConstraintOperator::DescendantOrSelfOf, "46635009")),
+            )
+        );
+
+        let parsed = EclParser::parse("1 or 2 OR 3").unwrap();
+        assert_eq!(
+            parsed,
+            EclExpression::Or(
+                Box::new(EclExpression::Or(Box::new(concept("1")), Box::new(concept("2")))),
+                Box::new(concept("3")),
+            )
+        );
+
+        assert!(EclParser::parse("1 AND 2 OR 3").is_err(), "Mixed operators need brackets");
+        assert!(EclParser::parse("(1 AND 2) OR 3").is_ok());
+        assert!(EclParser::parse("1 MINUS 2 MINUS 3").is_err());
+    }
+
+    #[test]
+    fn test_parse_member_of_and_wildcard() {
+        let parsed = EclParser::parse("^ 32570071000036102").unwrap();
+        assert_eq!(
+            parsed,
+            EclExpression::Sub {
+                operator: None,
+                member_of: true,
+                focus: EclFocus::Concept("32570071000036102".to_string()),
+            }
+        );
+
+        let parsed = EclParser::parse("*").unwrap();
+        assert_eq!(
+            parsed,
+            EclExpression::Sub {
+                operator: None,
+                member_of: false,
+                focus: EclFocus::Wildcard,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_refinements() {
+        let parsed = EclParser::parse(
+            "< 404684003 |Clinical finding| : [1..*] { 363698007 |Finding site| = << 39057004, 116676008 != * }",
+        )
+        .unwrap();
+
+        let EclExpression::Refined { focus, refinement } = parsed else {
+            panic!("Expected a refined expression");
+        };
+        assert_eq!(*focus, with_operator(ConstraintOperator::DescendantOf, "404684003"));
+
+        let EclRefinement::Group { cardinality, refinement } = refinement else {
+            panic!("Expected an attribute group");
+        };
+        assert_eq!(cardinality, Some(Cardinality { min: 1, max: None }));
+        assert!(matches!(*refinement, EclRefinement::And(_, _)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(EclParser::parse("").is_err());
+        assert!(EclParser::parse("<< ").is_err());
+        assert!(EclParser::parse("(<< 123").is_err());
+        assert!(EclParser::parse("123 : 456").is_err(), "Attribute needs a comparison");
+        assert!(EclParser::parse("123 : { { 1 = 2 } }").is_err(), "Groups cannot nest");
+        assert!(EclParser::parse("123 : [2..1] 1 = 2").is_err());
+        assert!(EclParser::parse("123 : R 1 = 2").is_err(), "Reverse flag is not supported");
+    }
+
+    #[test]
+    fn test_cardinality_contains() {
+        let card = Cardinality { min: 0, max: Some(0) };
+        assert!(card.contains(0));
+        assert!(!card.contains(1));
+        assert!(AT_LEAST_ONE.contains(5));
+        assert!(!AT_LEAST_ONE.contains(0));
+    }
+}
@@ -0,0 +1,2971 @@
+use crate::jobs::JobControl;
+use crate::parsers::{AmtCsvParser, BundleResource, Rf2Archive, SnomedRf2Parser, ValueSetR4Parser};
+use crate::progress::{ImportProgress, ProgressReporter};
+use crate::search::TerminologySearch;
+use crate::parsers::snomed_rf2::{EN_AU_LANGUAGE_REFSET_ID, IS_A_TYPE_ID, PREFERRED_ACCEPTABILITY_ID, SYNONYM_TYPE_ID};
+use crate::storage::{
+    AmtCode, CodeSystem, CodeSystemConcept, ConceptMap, ConceptMapGroupInfo, ConceptMapRow, ContentFamily, ImportCheckpoint,
+    LanguageRefsetMember, RefsetMember,
+    SnomedConcept, SnomedDescription, SnomedMapEntry, SnomedRefset, SnomedRelationship, TerminologyStorage, ValueSet,
+    ValueSetConcept,
+};
+use anyhow::{Context, Result};
+use redb::{ReadableTable, TableDefinition};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+
+// Redb table definitions for batch operations
+const SNOMED_CONCEPTS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_concepts");
+const SNOMED_DESCRIPTIONS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_descriptions");
+// SNOMED_CONCEPT_DESCRIPTIONS indexes descriptions as (concept_id, description_id)
+const SNOMED_CONCEPT_DESCRIPTIONS: TableDefinition<(&str, &str), ()> =
+    TableDefinition::new("snomed_concept_descriptions");
+// SNOMED_RELATIONSHIPS uses composite key (source_id, relationship_id) so a concept's outgoing relationships are a range scan
+const SNOMED_RELATIONSHIPS: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("snomed_relationships");
+// SNOMED_ISA_CHILDREN indexes active is-a relationships as (parent_id, child_id) -> version_id
+const SNOMED_ISA_CHILDREN: TableDefinition<(&str, &str), u64> = TableDefinition::new("snomed_isa_children");
+// SNOMED_CLOSURE is the transitive closure of is-a as (ancestor_id, descendant_id) -> version_id
+const SNOMED_CLOSURE: TableDefinition<(&str, &str), u64> = TableDefinition::new("snomed_closure");
+// SNOMED_CONCEPT_HISTORY / SNOMED_DESCRIPTION_HISTORY keep every dated row for point-in-time queries
+const SNOMED_CONCEPT_HISTORY: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("snomed_concept_history");
+const SNOMED_DESCRIPTION_HISTORY: TableDefinition<(&str, &str, &str), &[u8]> =
+    TableDefinition::new("snomed_description_history");
+// SNOMED_LANGUAGE_REFSET is keyed (description_id, refset_id); SNOMED_PREFERRED_TERMS maps concept -> en-AU preferred synonym
+const SNOMED_LANGUAGE_REFSET: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("snomed_language_refset");
+const SNOMED_PREFERRED_TERMS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_preferred_terms");
+// SNOMED_REFSET_MEMBERS is keyed (refset_id, referenced_component_id, member_id); SNOMED_REFSETS summarises each refset
+const SNOMED_REFSET_MEMBERS: TableDefinition<(&str, &str, &str), &[u8]> =
+    TableDefinition::new("snomed_refset_members");
+const SNOMED_REFSETS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_refsets");
+// SNOMED_MAP_ENTRIES is keyed (source_code, refset_id, member_id); SNOMED_MAP_TARGETS is its (map_target, refset_id, member_id) index
+const SNOMED_MAP_ENTRIES: TableDefinition<(&str, &str, &str), &[u8]> = TableDefinition::new("snomed_map_entries");
+const SNOMED_MAP_TARGETS: TableDefinition<(&str, &str, &str), &str> = TableDefinition::new("snomed_map_targets");
+// AMT_CODES uses composite key (SCTID, code_type) because same SCTID can appear in multiple product types
+const AMT_CODES: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("amt_codes");
+const VALUESETS: TableDefinition<&str, &[u8]> = TableDefinition::new("valuesets");
+const VALUESET_CONCEPTS: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("valueset_concepts");
+// VALUESET_MEMBERS indexes expansion membership as (valueset_url, system, code)
+const VALUESET_MEMBERS: TableDefinition<(&str, &str, &str), ()> = TableDefinition::new("valueset_members");
+const VALUESET_COMPOSE: TableDefinition<&str, &[u8]> = TableDefinition::new("valueset_compose");
+const CODESYSTEMS: TableDefinition<&str, &[u8]> = TableDefinition::new("codesystems");
+const CODESYSTEM_CONCEPTS: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("codesystem_concepts");
+// CONCEPT_MAP_ELEMENTS / CONCEPT_MAP_TARGETS hold each mapping keyed by its source and by its target
+const CONCEPT_MAPS: TableDefinition<&str, &[u8]> = TableDefinition::new("concept_maps");
+const CONCEPT_MAP_ELEMENTS: TableDefinition<(&str, &str, &str, u64), &[u8]> =
+    TableDefinition::new("concept_map_elements");
+const CONCEPT_MAP_TARGETS: TableDefinition<(&str, &str, &str, u64), &[u8]> =
+    TableDefinition::new("concept_map_targets");
+// IMPORT_CHECKPOINTS: version id -> bincode ImportCheckpoint, written with each SNOMED batch of a job
+const IMPORT_CHECKPOINTS: TableDefinition<u64, &[u8]> = TableDefinition::new("import_checkpoints");
+
+/// Rows per write transaction for RF2 files
+const BATCH_SIZE: usize = 1000;
+
+/// Parsed batches that can wait for their redb write before the parser blocks
+const PIPELINE_BATCHES: usize = 8;
+
+/// Import terminology content into the database
+pub struct TerminologyImporter<'a> {
+    storage: &'a TerminologyStorage,
+    version_id: u64,
+    progress: Option<&'a dyn ProgressReporter>,
+    job: Option<&'a JobControl>,
+    /// Work committed so far when running as a job (see `ImportCheckpoint`), saved with each batch
+    checkpoint: Mutex<Option<ImportCheckpoint>>,
+}
+
+impl<'a> TerminologyImporter<'a> {
+    pub fn new(storage: &'a TerminologyStorage, version_id: u64) -> Self {
+        Self {
+            storage,
+            version_id,
+            progress: None,
+            job: None,
+            checkpoint: Mutex::new(None),
+        }
+    }
+
+    /// Report progress for each import phase to the given reporter
+    pub fn with_progress(mut self, reporter: &'a dyn ProgressReporter) -> Self {
+        self.progress = Some(reporter);
+        self
+    }
+
+    fn emit_progress(&self, progress: ImportProgress) {
+        if let Some(reporter) = self.progress {
+            reporter.import_progress(&progress);
+        }
+    }
+
+    /// Run as an import job: stop between batches once the job is cancelled, and keep a checkpoint
+    /// of a SNOMED release's committed batches so an interrupted import resumes from it
+    pub fn with_job(mut self, job: &'a JobControl) -> Self {
+        self.job = Some(job);
+        self
+    }
+
+    /// Fail with `jobs::Cancelled` once the job has been cancelled
+    fn check_cancelled(&self) -> Result<()> {
+        match self.job {
+            Some(job) => job.check(),
+            None => Ok(()),
+        }
+    }
+
+    fn checkpoint(&self) -> std::sync::MutexGuard<'_, Option<ImportCheckpoint>> {
+        self.checkpoint.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Pick up the checkpoint an interrupted run of the job left in storage
+    fn resume_checkpoint(&self) -> Result<()> {
+        if self.job.is_none() {
+            return Ok(());
+        }
+        let checkpoint = self.storage.get_import_checkpoint(self.version_id)?.unwrap_or_default();
+        if checkpoint != ImportCheckpoint::default() {
+            println!(
+                "Resuming import: {:?} done, {} rows of {}",
+                checkpoint.completed,
+                checkpoint.rows,
+                checkpoint.phase.as_deref().unwrap_or("the next phase")
+            );
+        }
+        *self.checkpoint() = Some(checkpoint);
+        Ok(())
+    }
+
+    /// Rows of a phase committed by an earlier run, or None when the whole phase is done
+    fn resume_from(&self, phase: &str) -> Option<u64> {
+        match &*self.checkpoint() {
+            Some(checkpoint) if checkpoint.completed.iter().any(|p| p == phase) => None,
+            Some(checkpoint) if checkpoint.phase.as_deref() == Some(phase) => Some(checkpoint.rows),
+            _ => Some(0),
+        }
+    }
+
+    /// Save the checkpoint in a batch's write transaction, so it never counts rows that were not committed
+    fn save_checkpoint(&self, write_txn: &redb::WriteTransaction) -> Result<()> {
+        if let Some(checkpoint) = &*self.checkpoint() {
+            let bytes = bincode::serialize(checkpoint)?;
+            write_txn.open_table(IMPORT_CHECKPOINTS)?.insert(self.version_id, bytes.as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Record that a phase is complete
+    fn complete_phase(&self, phase: &str) -> Result<()> {
+        if let Some(checkpoint) = self.checkpoint().as_mut() {
+            checkpoint.completed.push(phase.to_string());
+            checkpoint.phase = None;
+            checkpoint.rows = 0;
+        }
+        let write_txn = self.storage.database().begin_write()?;
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Parse an RF2 file and insert its rows in batches, reporting progress as "Importing <Rows>"
+    /// Parsing runs on its own thread and hands batches to this one, which writes them to redb while the
+    /// next batch is parsed; the rows are counted for progress on a third thread in the meantime
+    /// The rows an earlier run committed are parsed but not inserted again, and a completed phase is skipped
+    fn import_rows<T: Send>(
+        &self,
+        rows: &str,
+        count: impl FnOnce() -> Result<usize> + Send,
+        parse: impl FnOnce(&mut dyn FnMut(T) -> Result<()>) -> Result<usize> + Send,
+        mut insert: impl FnMut(Vec<T>) -> Result<()>,
+    ) -> Result<usize> {
+        let Some(skip) = self.resume_from(rows) else {
+            println!("All {} were imported before the import was interrupted", rows);
+            return count();
+        };
+        let title: Vec<String> = rows.split(' ').map(|word| word[..1].to_uppercase() + &word[1..]).collect();
+        let phase = format!("Importing {}", title.join(" "));
+
+        self.emit_progress(ImportProgress {
+            phase: phase.clone(),
+            phase_status: "in_progress".to_string(),
+            current: skip as usize,
+            total: None,
+            percentage: 0.0,
+            message: format!("Importing SNOMED {}...", rows),
+        });
+
+        // Zero until the count finishes
+        let total = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::sync_channel::<(Vec<T>, u64)>(PIPELINE_BATCHES);
+
+        let count = std::thread::scope(|scope| -> Result<usize> {
+            let counter = scope.spawn(|| -> Result<()> {
+                total.store(count()?, Ordering::Relaxed);
+                Ok(())
+            });
+            let parser = scope.spawn(move || -> Result<usize> {
+                let mut batch = Vec::with_capacity(BATCH_SIZE);
+                let mut seen = 0u64;
+                let count = parse(&mut |row| {
+                    seen += 1;
+                    if seen <= skip {
+                        return Ok(());
+                    }
+                    batch.push(row);
+
+                    // Batch insert every 1000 records
+                    if batch.len() >= BATCH_SIZE {
+                        let full = std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE));
+                        sender.send((full, seen)).map_err(|_| anyhow::anyhow!("Import of {} stopped", rows))?;
+                    }
+                    Ok(())
+                })?;
+
+                // Insert remaining rows
+                if !batch.is_empty() {
+                    sender.send((batch, seen)).map_err(|_| anyhow::anyhow!("Import of {} stopped", rows))?;
+                }
+                Ok(count)
+            });
+
+            let written = receiver.iter().try_for_each(|(batch, seen)| {
+                self.check_cancelled()?;
+                if let Some(checkpoint) = self.checkpoint().as_mut() {
+                    checkpoint.phase = Some(rows.to_string());
+                    checkpoint.rows = seen;
+                }
+                insert(batch)?;
+
+                // Emit progress every batch
+                let total = total.load(Ordering::Relaxed);
+                self.emit_progress(ImportProgress {
+                    phase: phase.clone(),
+                    phase_status: "in_progress".to_string(),
+                    current: seen as usize,
+                    total: (total > 0).then_some(total),
+                    percentage: if total > 0 { (seen as f32 / total as f32 * 100.0).min(100.0) } else { 0.0 },
+                    message: format!("Imported {} {}...", seen, rows),
+                });
+                Ok::<_, anyhow::Error>(())
+            });
+            // A failed write closes the pipeline, so the parser stops at its next batch
+            drop(receiver);
+
+            let parsed = parser.join().unwrap_or_else(|e| std::panic::resume_unwind(e));
+            let counted = counter.join().unwrap_or_else(|e| std::panic::resume_unwind(e));
+            written?;
+            let count = parsed?;
+            counted?;
+            Ok(count)
+        })?;
+        self.complete_phase(rows)?;
+
+        self.emit_progress(ImportProgress {
+            phase,
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: format!("Imported {} {}", count, rows),
+        });
+
+        Ok(count)
+    }
+
+    /// Import SNOMED CT-AU SNAPSHOT from ZIP file (Concepts, Descriptions and Relationships)
+    pub async fn import_snomed(&self, zip_path: &Path, searcher: &TerminologySearch) -> Result<()> {
+        self.import_snomed_release(zip_path, false, searcher).await
+    }
+
+    /// Import SNOMED CT-AU FULL from ZIP file, keeping every effectiveTime row for point-in-time queries
+    /// The current tables end up holding the latest row per component, exactly as a snapshot import would
+    pub async fn import_snomed_full(&self, zip_path: &Path, searcher: &TerminologySearch) -> Result<()> {
+        self.import_snomed_release(zip_path, true, searcher).await
+    }
+
+    async fn import_snomed_release(&self, zip_path: &Path, full: bool, searcher: &TerminologySearch) -> Result<()> {
+        println!("Importing SNOMED CT-AU {} from: {:?}", if full { "FULL" } else { "SNAPSHOT" }, zip_path);
+
+        // Once a Full release has been imported, later releases extend its history too
+        let keep_history = full || self.storage.has_snomed_full_release()?;
+        self.resume_checkpoint()?;
+
+        self.emit_progress(ImportProgress {
+            phase: "Locating Files".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: None,
+            percentage: 0.0,
+            message: "Locating RF2 files in the SNOMED ZIP archive...".to_string(),
+        });
+
+        // RF2 files are streamed out of the archive as they are parsed, so nothing is extracted to disk
+        let mut archive = Rf2Archive::open(zip_path)?;
+
+        // Find RF2 SNAPSHOT or FULL files
+        let release = if full { "Full" } else { "Snapshot" };
+        let concept_file = archive.find_file(&format!("sct2_Concept_{}", release))?;
+        let description_file = archive.find_file(&format!("sct2_Description_{}-en", release))?;
+        let relationship_file = archive.find_file(&format!("sct2_Relationship_{}", release))?;
+        // Language refsets are optional: without them lookups fall back to the FSN
+        let language_files = archive.find_files(&format!("der2_cRefset_Language{}-en", release));
+        let refset_files = archive.find_refset_files(release);
+
+        println!("Found concept file: {:?}", concept_file);
+        println!("Found description file: {:?}", description_file);
+        println!("Found relationship file: {:?}", relationship_file);
+        println!("Found {} language refset file(s)", language_files.len());
+        println!("Found {} other refset file(s)", refset_files.len());
+
+        // Mark file location as complete
+        self.emit_progress(ImportProgress {
+            phase: "Locating Files".to_string(),
+            phase_status: "completed".to_string(),
+            current: 0,
+            total: None,
+            percentage: 100.0,
+            message: "Files located".to_string(),
+        });
+
+        // Rows are counted for progress on a second handle to the archive while the first is parsed
+        let count_rows = |file: &str| Rf2Archive::open(zip_path)?.count_rows(file);
+
+        // Import concepts with batch inserts
+        println!("Importing concepts...");
+        let concept_count = self.import_rows(
+            "concepts",
+            || count_rows(&concept_file),
+            |callback| SnomedRf2Parser::parse_concepts(archive.reader(&concept_file)?, callback),
+            |batch| self.insert_concept_batch(batch, keep_history),
+        )?;
+        println!("Imported {} concepts", concept_count);
+
+        // Import descriptions with batch inserts, indexing them for search in the same pass
+        // A resumed import has committed descriptions that are not parsed again, so it indexes them from redb
+        println!("Importing descriptions...");
+        let index_in_pass = self.resume_from("descriptions") == Some(0);
+        let mut import_descriptions = |index: bool| {
+            // Concepts that had a description row replaced or superseded by another row of the file
+            let mut revised = HashSet::new();
+            let count = self.import_rows(
+                "descriptions",
+                || count_rows(&description_file),
+                |callback| SnomedRf2Parser::parse_descriptions(archive.reader(&description_file)?, callback),
+                |batch| {
+                    if index {
+                        for desc in &batch {
+                            searcher.index_snomed_description(&desc.concept_id, &desc.term, &desc.type_id, desc.active)?;
+                        }
+                    }
+                    revised.extend(self.insert_description_batch(batch, keep_history)?);
+                    Ok(())
+                },
+            )?;
+            Ok::<_, anyhow::Error>((count, revised))
+        };
+        let description_count = if index_in_pass {
+            searcher.rebuild(ContentFamily::Snomed, || {
+                searcher.clear_snomed()?;
+                let (count, revised) = import_descriptions(true)?;
+                // A Full file lists every state of a description, so those concepts are indexed from their final rows
+                self.index_snomed_concepts(&revised, searcher)?;
+                Ok(count)
+            })?
+        } else {
+            import_descriptions(false)?.0
+        };
+        println!("Imported {} descriptions", description_count);
+
+        // Import language refsets, then derive each concept's en-AU preferred term
+        if self.resume_from("language refsets").is_some() {
+            let language_count = self.import_language_refsets(&mut archive, zip_path, &language_files)?;
+            self.check_cancelled()?;
+            let preferred_count = self.rebuild_preferred_terms()?;
+            self.complete_phase("language refsets")?;
+            println!("Imported {} language refset members, {} preferred terms", language_count, preferred_count);
+        }
+
+        // Import simple, ordered and other reference sets for membership queries
+        if self.resume_from("reference sets").is_some() {
+            let (refset_count, refset_kinds) = self.import_refsets(&mut archive, zip_path, &refset_files)?;
+            self.check_cancelled()?;
+            let refsets = self.rebuild_refset_index(refset_kinds)?;
+            let map_count = self.rebuild_map_index()?;
+            self.complete_phase("reference sets")?;
+            println!(
+                "Imported {} refset members into {} refsets ({} map rows)",
+                refset_count, refsets, map_count
+            );
+        }
+
+        // Import relationships with batch inserts (also builds the is-a hierarchy index)
+        println!("Importing relationships...");
+        let relationship_count = self.import_rows(
+            "relationships",
+            || count_rows(&relationship_file),
+            |callback| SnomedRf2Parser::parse_relationships(archive.reader(&relationship_file)?, callback),
+            |batch| {
+                if full {
+                    self.insert_relationship_full_batch(batch)
+                } else {
+                    self.insert_relationship_batch(batch)
+                }
+            },
+        )?;
+        println!("Imported {} relationships", relationship_count);
+
+        if self.resume_from("closure").is_some() {
+            self.check_cancelled()?;
+
+            // A Full file has superseded rows, so the is-a index is derived from the final relationship state
+            if full {
+                self.rebuild_isa_index()?;
+            }
+
+            // Precompute the is-a transitive closure for constant-time subsumption tests
+            println!("Building is-a transitive closure...");
+            let closure_count = self.build_snomed_closure()?;
+            self.complete_phase("closure")?;
+            println!("Stored {} transitive closure entries", closure_count);
+        }
+
+        // Build Tantivy index from imported data, unless the descriptions were indexed as they were imported
+        if !index_in_pass {
+            self.emit_progress(ImportProgress {
+                phase: "Building Search Index".to_string(),
+                phase_status: "in_progress".to_string(),
+                current: 0,
+                total: Some(description_count),
+                percentage: 0.0,
+                message: "Building SNOMED search index...".to_string(),
+            });
+
+            println!("Building Tantivy index for SNOMED...");
+            self.build_snomed_index(searcher)?;
+        }
+
+        self.emit_progress(ImportProgress {
+            phase: "Building Search Index".to_string(),
+            phase_status: "completed".to_string(),
+            current: description_count,
+            total: Some(description_count),
+            percentage: 100.0,
+            message: "Search index built".to_string(),
+        });
+
+        self.emit_progress(ImportProgress {
+            phase: "Complete".to_string(),
+            phase_status: "completed".to_string(),
+            current: concept_count + description_count,
+            total: Some(concept_count + description_count),
+            percentage: 100.0,
+            message: format!(
+                "Import complete! {} concepts, {} descriptions indexed, {} relationships",
+                concept_count, description_count, relationship_count
+            ),
+        });
+
+        Ok(())
+    }
+
+    /// Apply a SNOMED CT-AU RF2 DELTA on top of the imported base release
+    /// Delta rows replace the stored concepts, descriptions and relationships; the is-a closure is
+    /// rebuilt only if the hierarchy changed, and only the affected concepts are re-indexed
+    /// As a job it checkpoints like a full import, and a resumed delta rebuilds the closure and index
+    pub async fn import_snomed_delta(
+        &self,
+        zip_path: &Path,
+        base_version: Option<&str>,
+        searcher: &TerminologySearch,
+    ) -> Result<()> {
+        println!("Applying SNOMED CT-AU delta from: {:?}", zip_path);
+
+        self.check_snomed_base_version(base_version)?;
+        let keep_history = self.storage.has_snomed_full_release()?;
+        self.resume_checkpoint()?;
+        // The changes an earlier run committed are not parsed again, so a resumed delta re-indexes everything
+        let resumed = self.checkpoint().as_ref().is_some_and(|c| *c != ImportCheckpoint::default());
+
+        // Staged deltas are applied to a copy of the live release (see `TerminologyStorage::stage_release`)
+        if self.storage.release().is_some() && self.resume_from("live release").is_some() {
+            self.check_cancelled()?;
+            self.emit_progress(ImportProgress {
+                phase: "Copying Live Release".to_string(),
+                phase_status: "in_progress".to_string(),
+                current: 0,
+                total: None,
+                percentage: 0.0,
+                message: "Copying the live SNOMED CT release to apply the delta to...".to_string(),
+            });
+            let copied = self.storage.copy_live_tables()?;
+            self.complete_phase("live release")?;
+            self.emit_progress(ImportProgress {
+                phase: "Copying Live Release".to_string(),
+                phase_status: "completed".to_string(),
+                current: copied,
+                total: Some(copied),
+                percentage: 100.0,
+                message: format!("Copied {} rows", copied),
+            });
+        }
+
+        self.emit_progress(ImportProgress {
+            phase: "Locating Files".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: None,
+            percentage: 0.0,
+            message: "Locating RF2 files in the SNOMED delta archive...".to_string(),
+        });
+
+        let mut archive = Rf2Archive::open(zip_path)?;
+        let concept_file = archive.find_file("sct2_Concept_Delta")?;
+        let description_file = archive.find_file("sct2_Description_Delta-en")?;
+        let relationship_file = archive.find_file("sct2_Relationship_Delta")?;
+        let language_files = archive.find_files("der2_cRefset_LanguageDelta-en");
+        let refset_files = archive.find_refset_files("Delta");
+
+        self.emit_progress(ImportProgress {
+            phase: "Locating Files".to_string(),
+            phase_status: "completed".to_string(),
+            current: 0,
+            total: None,
+            percentage: 100.0,
+            message: "Files located".to_string(),
+        });
+
+        let count_rows = |file: &str| Rf2Archive::open(zip_path)?.count_rows(file);
+
+        // Concepts whose search documents need rebuilding
+        let mut changed_concepts: HashSet<String> = HashSet::new();
+
+        let concept_count = self.import_rows(
+            "concepts",
+            || count_rows(&concept_file),
+            |callback| SnomedRf2Parser::parse_concepts(archive.reader(&concept_file)?, callback),
+            |batch| {
+                changed_concepts.extend(batch.iter().map(|concept| concept.id.clone()));
+                self.insert_concept_batch(batch, keep_history)
+            },
+        )?;
+        println!("Applied {} concept changes", concept_count);
+
+        let description_count = self.import_rows(
+            "descriptions",
+            || count_rows(&description_file),
+            |callback| SnomedRf2Parser::parse_descriptions(archive.reader(&description_file)?, callback),
+            |batch| {
+                changed_concepts.extend(batch.iter().map(|description| description.concept_id.clone()));
+                self.insert_description_batch(batch, keep_history)?;
+                Ok(())
+            },
+        )?;
+        println!("Applied {} description changes", description_count);
+
+        // Preferred terms depend on both descriptions and language refset members
+        if self.resume_from("language refsets").is_some() {
+            let language_count = self.import_language_refsets(&mut archive, zip_path, &language_files)?;
+            self.check_cancelled()?;
+            if description_count > 0 || language_count > 0 {
+                self.rebuild_preferred_terms()?;
+            }
+            self.complete_phase("language refsets")?;
+            println!("Applied {} language refset changes", language_count);
+        }
+
+        if self.resume_from("reference sets").is_some() {
+            let (refset_count, refset_kinds) = self.import_refsets(&mut archive, zip_path, &refset_files)?;
+            self.check_cancelled()?;
+            if refset_count > 0 {
+                self.rebuild_refset_index(refset_kinds)?;
+                self.rebuild_map_index()?;
+            }
+            self.complete_phase("reference sets")?;
+            println!("Applied {} refset member changes", refset_count);
+        }
+
+        // A resumed delta cannot tell whether the relationships it skipped changed the hierarchy
+        let mut hierarchy_changed = resumed;
+        let relationship_count = self.import_rows(
+            "relationships",
+            || count_rows(&relationship_file),
+            |callback| SnomedRf2Parser::parse_relationships(archive.reader(&relationship_file)?, callback),
+            |batch| {
+                hierarchy_changed |= self.apply_relationship_delta_batch(batch)?;
+                Ok(())
+            },
+        )?;
+        println!("Applied {} relationship changes", relationship_count);
+
+        if hierarchy_changed && self.resume_from("closure").is_some() {
+            self.check_cancelled()?;
+            println!("Rebuilding is-a transitive closure...");
+            let closure_count = self.build_snomed_closure()?;
+            self.complete_phase("closure")?;
+            println!("Stored {} transitive closure entries", closure_count);
+        }
+
+        if resumed {
+            self.emit_progress(ImportProgress {
+                phase: "Updating Search Index".to_string(),
+                phase_status: "in_progress".to_string(),
+                current: 0,
+                total: None,
+                percentage: 0.0,
+                message: "Re-indexing the resumed release...".to_string(),
+            });
+            self.build_snomed_index(searcher)?;
+            self.emit_progress(ImportProgress {
+                phase: "Updating Search Index".to_string(),
+                phase_status: "completed".to_string(),
+                current: 0,
+                total: None,
+                percentage: 100.0,
+                message: "Search index rebuilt".to_string(),
+            });
+        } else {
+            self.emit_progress(ImportProgress {
+                phase: "Updating Search Index".to_string(),
+                phase_status: "in_progress".to_string(),
+                current: 0,
+                total: Some(changed_concepts.len()),
+                percentage: 0.0,
+                message: format!("Re-indexing {} changed concepts...", changed_concepts.len()),
+            });
+
+            let reindexed = self.reindex_snomed_concepts(&changed_concepts, searcher)?;
+            println!("Re-indexed {} descriptions for {} concepts", reindexed, changed_concepts.len());
+
+            self.emit_progress(ImportProgress {
+                phase: "Updating Search Index".to_string(),
+                phase_status: "completed".to_string(),
+                current: changed_concepts.len(),
+                total: Some(changed_concepts.len()),
+                percentage: 100.0,
+                message: format!("Re-indexed {} descriptions", reindexed),
+            });
+        }
+
+        self.emit_progress(ImportProgress {
+            phase: "Complete".to_string(),
+            phase_status: "completed".to_string(),
+            current: concept_count + description_count + relationship_count,
+            total: Some(concept_count + description_count + relationship_count),
+            percentage: 100.0,
+            message: format!(
+                "Delta applied! {} concepts, {} descriptions, {} relationships changed",
+                concept_count, description_count, relationship_count
+            ),
+        });
+
+        Ok(())
+    }
+
+    /// Ensure a delta's sctBaseVersion is the SNOMED CT release currently held in storage
+    fn check_snomed_base_version(&self, base_version: Option<&str>) -> Result<()> {
+        let base_version = base_version
+            .context("Delta release does not declare an sctBaseVersion")?;
+
+        let current = self
+            .storage
+            .get_imported_snomed_release()?
+            .with_context(|| format!("No SNOMED CT release imported yet; import base version {} first", base_version))?;
+        let current_version = current.content_item_version.as_deref().unwrap_or(&current.version);
+
+        if current_version != base_version {
+            anyhow::bail!(
+                "Delta requires base version {} but the imported SNOMED CT release is {}",
+                base_version,
+                current_version
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Import AMT from CSV file
+    pub async fn import_amt(&self, csv_path: &Path, searcher: &TerminologySearch) -> Result<()> {
+        println!("Importing AMT from: {:?}", csv_path);
+
+        // Pass 1: Count actual codes (not CSV lines)
+        // AMT CSV is wide-format - each row expands into multiple codes (CTPP, TPP, TPUU, etc.)
+        println!("Counting AMT codes...");
+
+        self.emit_progress(ImportProgress {
+            phase: "Counting AMT".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: None,
+            percentage: 0.0,
+            message: "Counting AMT codes...".to_string(),
+        });
+
+        let amt_total = AmtCsvParser::count_codes(csv_path)
+            .context("Failed to count AMT codes")?;
+        println!("Found {} AMT codes to import", amt_total);
+
+        self.emit_progress(ImportProgress {
+            phase: "Counting AMT".to_string(),
+            phase_status: "completed".to_string(),
+            current: amt_total,
+            total: Some(amt_total),
+            percentage: 100.0,
+            message: format!("Found {} AMT codes", amt_total),
+        });
+
+        // Pass 2: Import with accurate progress
+        self.emit_progress(ImportProgress {
+            phase: "Importing AMT".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: Some(amt_total),
+            percentage: 0.0,
+            message: "Importing AMT codes...".to_string(),
+        });
+
+        // Import AMT codes with batch inserts
+        let mut batch = Vec::new();
+        let mut count_tracker = 0;
+        let count = AmtCsvParser::parse(csv_path, |code| {
+            batch.push(code);
+
+            // Batch insert every 1000 records
+            if batch.len() >= 1000 {
+                let batch_to_insert = std::mem::take(&mut batch);
+                count_tracker += batch_to_insert.len();
+
+                // Emit progress
+                self.emit_progress(ImportProgress {
+                    phase: "Importing AMT".to_string(),
+                    phase_status: "in_progress".to_string(),
+                    current: count_tracker,
+                    total: Some(amt_total),
+                    percentage: (count_tracker as f32 / amt_total as f32 * 100.0).min(100.0),
+                    message: format!("Imported {} AMT codes...", count_tracker),
+                });
+
+                self.check_cancelled()?;
+                self.insert_amt_batch(batch_to_insert)?;
+            }
+
+            Ok(())
+        })?;
+
+        // Insert remaining codes
+        if !batch.is_empty() {
+            self.insert_amt_batch(batch)?;
+        }
+
+        println!("Imported {} AMT codes", count);
+
+        // Mark AMT import as complete
+        self.emit_progress(ImportProgress {
+            phase: "Importing AMT".to_string(),
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: format!("Imported {} AMT codes", count),
+        });
+
+        // Build Tantivy index
+        self.emit_progress(ImportProgress {
+            phase: "Building Search Index".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: Some(count),
+            percentage: 0.0,
+            message: "Building AMT search index...".to_string(),
+        });
+
+        println!("Building Tantivy index for AMT...");
+        self.build_amt_index(searcher)?;
+
+        self.emit_progress(ImportProgress {
+            phase: "Building Search Index".to_string(),
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: "Search index built".to_string(),
+        });
+
+        self.emit_progress(ImportProgress {
+            phase: "Complete".to_string(),
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: format!("Import complete! {} AMT codes imported", count),
+        });
+
+        Ok(())
+    }
+
+    /// Import FHIR ValueSets from JSON bundle
+    pub async fn import_valuesets(&self, json_path: &Path, searcher: &TerminologySearch) -> Result<()> {
+        println!("Importing ValueSets from: {:?}", json_path);
+
+        self.emit_progress(ImportProgress {
+            phase: "Importing ValueSets".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: Some(500), // Typical ValueSet count
+            percentage: 0.0,
+            message: "Importing FHIR ValueSets...".to_string(),
+        });
+
+        let mut count_tracker = 0;
+        let mut valueset_batch = Vec::new();
+        let mut concept_map_batch = Vec::new();
+        let mut concept_map_count = 0;
+        let mut codesystem_count = 0;
+
+        ValueSetR4Parser::parse_bundle(json_path, |resource| {
+            let valueset = match resource {
+                BundleResource::ValueSet(valueset) => valueset,
+                BundleResource::ConceptMap(map) => {
+                    concept_map_count += 1;
+                    concept_map_batch.push(map);
+                    if concept_map_batch.len() >= 10 {
+                        self.insert_concept_map_batch(std::mem::take(&mut concept_map_batch))?;
+                    }
+                    return Ok(());
+                }
+                BundleResource::CodeSystem(codesystem) => {
+                    codesystem_count += 1;
+                    self.insert_codesystem(codesystem)?;
+                    return Ok(());
+                }
+            };
+            count_tracker += 1;
+
+            // Emit progress every 10 valuesets
+            if count_tracker % 10 == 0 {
+                self.emit_progress(ImportProgress {
+                    phase: "Importing ValueSets".to_string(),
+                    phase_status: "in_progress".to_string(),
+                    current: count_tracker,
+                    total: Some(500),
+                    percentage: (count_tracker as f32 / 500.0 * 100.0).min(100.0),
+                    message: format!("Imported {} ValueSets...", count_tracker),
+                });
+            }
+
+            valueset_batch.push(valueset);
+
+            // Batch insert every 50 valuesets
+            if valueset_batch.len() >= 50 {
+                let batch = std::mem::take(&mut valueset_batch);
+                self.check_cancelled()?;
+                self.insert_valueset_batch(batch)?;
+            }
+
+            Ok(())
+        })?;
+
+        // Insert remaining valuesets and concept maps
+        if !valueset_batch.is_empty() {
+            self.insert_valueset_batch(valueset_batch)?;
+        }
+        if !concept_map_batch.is_empty() {
+            self.insert_concept_map_batch(concept_map_batch)?;
+        }
+
+        let count = count_tracker;
+        println!(
+            "Imported {} ValueSets, {} ConceptMaps and {} CodeSystems",
+            count, concept_map_count, codesystem_count
+        );
+
+        // Mark ValueSets import as complete
+        self.emit_progress(ImportProgress {
+            phase: "Importing ValueSets".to_string(),
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: format!("Imported {} ValueSets", count),
+        });
+
+        // Build Tantivy index
+        self.emit_progress(ImportProgress {
+            phase: "Building Search Index".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: Some(count),
+            percentage: 0.0,
+            message: "Building ValueSet search index...".to_string(),
+        });
+
+        println!("Building Tantivy index for ValueSets...");
+        self.build_valueset_index(searcher)?;
+
+        self.emit_progress(ImportProgress {
+            phase: "Building Search Index".to_string(),
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: "Search index built".to_string(),
+        });
+
+        self.emit_progress(ImportProgress {
+            phase: "Complete".to_string(),
+            phase_status: "completed".to_string(),
+            current: count,
+            total: Some(count),
+            percentage: 100.0,
+            message: format!("Import complete! {} ValueSets imported", count),
+        });
+
+        Ok(())
+    }
+
+    /// Batch insert SNOMED concepts into redb
+    /// With keep_history every row is also kept in the history table, and the current row is
+    /// only replaced by one with the same or a later effectiveTime (Full files list every state)
+    fn insert_concept_batch(&self, batch: Vec<crate::parsers::SnomedConcept>, keep_history: bool) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_CONCEPTS))?;
+            let mut history_table = write_txn.open_table(self.storage.table(SNOMED_CONCEPT_HISTORY))?;
+
+            for concept in batch {
+                let storage_concept = SnomedConcept {
+                    id: concept.id.clone(),
+                    effective_time: concept.effective_time,
+                    active: concept.active,
+                    module_id: concept.module_id,
+                    definition_status_id: concept.definition_status_id,
+                    version_id: self.version_id,
+                };
+
+                let bytes = bincode::serialize(&storage_concept)?;
+
+                if keep_history {
+                    history_table.insert(
+                        (concept.id.as_str(), storage_concept.effective_time.as_str()),
+                        bytes.as_slice(),
+                    )?;
+
+                    let superseded = match table.get(concept.id.as_str())? {
+                        Some(existing) => {
+                            let existing: SnomedConcept = bincode::deserialize(existing.value())?;
+                            existing.effective_time > storage_concept.effective_time
+                        }
+                        None => false,
+                    };
+                    if superseded {
+                        continue;
+                    }
+                }
+
+                table.insert(concept.id.as_str(), bytes.as_slice())?;
+            }
+        }
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Batch insert SNOMED descriptions into redb (keep_history as for concepts)
+    /// Returns the concepts whose descriptions already had a row, which only happens with keep_history
+    fn insert_description_batch(
+        &self,
+        batch: Vec<crate::parsers::SnomedDescription>,
+        keep_history: bool,
+    ) -> Result<Vec<String>> {
+        let mut revised = Vec::new();
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_DESCRIPTIONS))?;
+            let mut by_concept = write_txn.open_table(self.storage.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+            let mut history_table = write_txn.open_table(self.storage.table(SNOMED_DESCRIPTION_HISTORY))?;
+
+            for description in batch {
+                let storage_description = SnomedDescription {
+                    id: description.id.clone(),
+                    effective_time: description.effective_time,
+                    active: description.active,
+                    module_id: description.module_id,
+                    concept_id: description.concept_id,
+                    language_code: description.language_code,
+                    type_id: description.type_id,
+                    term: description.term,
+                    case_significance_id: description.case_significance_id,
+                    version_id: self.version_id,
+                };
+
+                let bytes = bincode::serialize(&storage_description)?;
+
+                if keep_history {
+                    history_table.insert(
+                        (
+                            storage_description.concept_id.as_str(),
+                            description.id.as_str(),
+                            storage_description.effective_time.as_str(),
+                        ),
+                        bytes.as_slice(),
+                    )?;
+
+                    let superseded = match table.get(description.id.as_str())? {
+                        Some(existing) => {
+                            let existing: SnomedDescription = bincode::deserialize(existing.value())?;
+                            revised.push(storage_description.concept_id.clone());
+                            existing.effective_time > storage_description.effective_time
+                        }
+                        None => false,
+                    };
+                    if superseded {
+                        continue;
+                    }
+                }
+
+                table.insert(description.id.as_str(), bytes.as_slice())?;
+                by_concept.insert((storage_description.concept_id.as_str(), description.id.as_str()), ())?;
+            }
+        }
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(revised)
+    }
+
+    /// Import reference set members from RF2 files in the archive, in batches like `import_rows`
+    /// Returns the number of rows read and the type of each refset they belong to
+    fn import_refsets(
+        &self,
+        archive: &mut Rf2Archive,
+        zip_path: &Path,
+        files: &[(String, String)],
+    ) -> Result<(usize, HashMap<String, String>)> {
+        // Rows a resumed import skips still record their refset's type
+        let mut kinds = HashMap::new();
+        let imported = self.resume_from("reference set members").is_none();
+        let total = self.import_rows(
+            "reference set members",
+            || {
+                let mut counter = Rf2Archive::open(zip_path)?;
+                files.iter().map(|(file, _)| counter.count_rows(file)).sum()
+            },
+            |callback| {
+                let mut total = 0;
+                for (file, kind) in files {
+                    total += SnomedRf2Parser::parse_refset(archive.reader(file)?, |member| {
+                        if !kinds.contains_key(&member.refset_id) {
+                            kinds.insert(member.refset_id.clone(), kind.clone());
+                        }
+                        callback(member)
+                    })?;
+                }
+                Ok(total)
+            },
+            |batch| self.insert_refset_batch(batch),
+        )?;
+
+        // The rows were all imported before the interruption, so they are read again only for their types
+        if imported {
+            for (file, kind) in files {
+                SnomedRf2Parser::parse_refset(archive.reader(file)?, |member| {
+                    kinds.entry(member.refset_id).or_insert_with(|| kind.clone());
+                    Ok(())
+                })?;
+            }
+        }
+
+        Ok((total, kinds))
+    }
+
+    /// Batch insert reference set members, keeping the latest row per member id
+    fn insert_refset_batch(&self, batch: Vec<crate::parsers::RefsetMember>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_REFSET_MEMBERS))?;
+
+            for member in batch {
+                let storage_member = RefsetMember {
+                    id: member.id,
+                    effective_time: member.effective_time,
+                    active: member.active,
+                    module_id: member.module_id,
+                    refset_id: member.refset_id,
+                    referenced_component_id: member.referenced_component_id,
+                    attributes: member.attributes,
+                    version_id: self.version_id,
+                };
+
+                let key = (
+                    storage_member.refset_id.as_str(),
+                    storage_member.referenced_component_id.as_str(),
+                    storage_member.id.as_str(),
+                );
+                let superseded = match table.get(key)? {
+                    Some(existing) => {
+                        let existing: RefsetMember = bincode::deserialize(existing.value())?;
+                        existing.effective_time > storage_member.effective_time
+                    }
+                    None => false,
+                };
+                if superseded {
+                    continue;
+                }
+
+                let bytes = bincode::serialize(&storage_member)?;
+                table.insert(key, bytes.as_slice())?;
+            }
+        }
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Rebuild the refset summaries from the member table, returning the number of refsets
+    /// Refsets missing from `imported_kinds` keep the type recorded when they were first imported
+    fn rebuild_refset_index(&self, imported_kinds: HashMap<String, String>) -> Result<usize> {
+        let db = self.storage.database();
+
+        let mut kinds: HashMap<String, String> = self
+            .storage
+            .get_snomed_refsets()?
+            .into_iter()
+            .map(|refset| (refset.refset_id, refset.kind))
+            .collect();
+        kinds.extend(imported_kinds);
+
+        let write_txn = db.begin_write()?;
+        write_txn.delete_table(self.storage.table(SNOMED_REFSETS))?;
+        let mut summaries: Vec<SnomedRefset> = Vec::new();
+        {
+            let members = write_txn.open_table(self.storage.table(SNOMED_REFSET_MEMBERS))?;
+            let mut last_counted: Option<(String, String)> = None;
+
+            for item in members.iter()? {
+                let (key, value) = item?;
+                let (refset_id, component_id, _) = key.value();
+                if summaries.last().is_none_or(|s| s.refset_id != refset_id) {
+                    summaries.push(SnomedRefset {
+                        refset_id: refset_id.to_string(),
+                        kind: kinds.get(refset_id).cloned().unwrap_or_default(),
+                        member_count: 0,
+                        version_id: self.version_id,
+                    });
+                }
+
+                let counted = last_counted
+                    .as_ref()
+                    .is_some_and(|(r, c)| r == refset_id && c == component_id);
+                if counted {
+                    continue;
+                }
+                let member: RefsetMember = bincode::deserialize(value.value())?;
+                if member.active {
+                    if let Some(summary) = summaries.last_mut() {
+                        summary.member_count += 1;
+                    }
+                    last_counted = Some((refset_id.to_string(), component_id.to_string()));
+                }
+            }
+
+            let mut refsets = write_txn.open_table(self.storage.table(SNOMED_REFSETS))?;
+            for summary in &summaries {
+                let bytes = bincode::serialize(summary)?;
+                refsets.insert(summary.refset_id.as_str(), bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(summaries.len())
+    }
+
+    /// Rebuild the map tables from the active rows of every complex and extended map refset
+    /// Returns the number of map rows
+    fn rebuild_map_index(&self) -> Result<usize> {
+        let map_refsets: Vec<String> = self
+            .storage
+            .get_snomed_refsets()?
+            .into_iter()
+            .filter(|refset| matches!(refset.kind.as_str(), "ComplexMap" | "ExtendedMap"))
+            .map(|refset| refset.refset_id)
+            .collect();
+
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        write_txn.delete_table(self.storage.table(SNOMED_MAP_ENTRIES))?;
+        write_txn.delete_table(self.storage.table(SNOMED_MAP_TARGETS))?;
+        let mut count = 0;
+        {
+            let members = write_txn.open_table(self.storage.table(SNOMED_REFSET_MEMBERS))?;
+            let mut entries = write_txn.open_table(self.storage.table(SNOMED_MAP_ENTRIES))?;
+            let mut targets = write_txn.open_table(self.storage.table(SNOMED_MAP_TARGETS))?;
+
+            for refset_id in &map_refsets {
+                for item in members.range((refset_id.as_str(), "", "")..)? {
+                    let (key, value) = item?;
+                    if key.value().0 != refset_id {
+                        break;
+                    }
+                    let member: RefsetMember = bincode::deserialize(value.value())?;
+                    let Some(entry) = self.map_entry(member) else {
+                        continue;
+                    };
+
+                    let bytes = bincode::serialize(&entry)?;
+                    let key = (entry.refset_id.as_str(), entry.member_id.as_str());
+                    entries.insert((entry.source_code.as_str(), key.0, key.1), bytes.as_slice())?;
+                    if let Some(target) = &entry.map_target {
+                        targets.insert((target.as_str(), key.0, key.1), entry.source_code.as_str())?;
+                    }
+                    count += 1;
+                }
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(count)
+    }
+
+    /// Read an active complex/extended map row
+    /// Columns after referencedComponentId: mapGroup, mapPriority, mapRule, mapAdvice, mapTarget,
+    /// correlationId and, for extended maps, mapCategoryId
+    fn map_entry(&self, member: RefsetMember) -> Option<SnomedMapEntry> {
+        if !member.active || member.attributes.len() < 6 {
+            return None;
+        }
+
+        let column = |i: usize| member.attributes.get(i).filter(|v| !v.is_empty()).cloned();
+        Some(SnomedMapEntry {
+            map_group: member.attributes[0].parse().ok()?,
+            map_priority: member.attributes[1].parse().ok()?,
+            map_rule: column(2),
+            map_advice: column(3),
+            map_target: column(4),
+            correlation_id: column(5),
+            map_category_id: column(6),
+            member_id: member.id,
+            refset_id: member.refset_id,
+            source_code: member.referenced_component_id,
+            version_id: self.version_id,
+        })
+    }
+
+    /// Import language refset members from RF2 files in the archive, in batches like `import_rows`
+    /// Returns the number of rows read
+    fn import_language_refsets(&self, archive: &mut Rf2Archive, zip_path: &Path, files: &[String]) -> Result<usize> {
+        self.import_rows(
+            "language refset members",
+            || {
+                let mut counter = Rf2Archive::open(zip_path)?;
+                files.iter().map(|file| counter.count_rows(file)).sum()
+            },
+            |callback| {
+                let mut total = 0;
+                for file in files {
+                    total += SnomedRf2Parser::parse_language_refset(archive.reader(file)?, &mut *callback)?;
+                }
+                Ok(total)
+            },
+            |batch| self.insert_language_batch(batch),
+        )
+    }
+
+    /// Batch insert language refset members, keeping the latest row per (description, refset)
+    /// Works for Snapshot, Full and Delta files alike
+    fn insert_language_batch(&self, batch: Vec<crate::parsers::LanguageRefsetMember>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_LANGUAGE_REFSET))?;
+
+            for member in batch {
+                let storage_member = LanguageRefsetMember {
+                    id: member.id,
+                    effective_time: member.effective_time,
+                    active: member.active,
+                    module_id: member.module_id,
+                    refset_id: member.refset_id,
+                    description_id: member.referenced_component_id,
+                    acceptability_id: member.acceptability_id,
+                    version_id: self.version_id,
+                };
+
+                let key = (storage_member.description_id.as_str(), storage_member.refset_id.as_str());
+                let superseded = match table.get(key)? {
+                    Some(existing) => {
+                        let existing: LanguageRefsetMember = bincode::deserialize(existing.value())?;
+                        existing.effective_time > storage_member.effective_time
+                    }
+                    None => false,
+                };
+                if superseded {
+                    continue;
+                }
+
+                let bytes = bincode::serialize(&storage_member)?;
+                table.insert(key, bytes.as_slice())?;
+            }
+        }
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Rebuild the concept -> en-AU preferred term table from the language refset and descriptions
+    /// A concept's preferred term is its active synonym marked preferred in the en-AU refset
+    fn rebuild_preferred_terms(&self) -> Result<usize> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        write_txn.delete_table(self.storage.table(SNOMED_PREFERRED_TERMS))?;
+        let mut count = 0;
+        {
+            let language_table = write_txn.open_table(self.storage.table(SNOMED_LANGUAGE_REFSET))?;
+            let descriptions = write_txn.open_table(self.storage.table(SNOMED_DESCRIPTIONS))?;
+            let mut preferred_terms = write_txn.open_table(self.storage.table(SNOMED_PREFERRED_TERMS))?;
+
+            for item in language_table.iter()? {
+                let (key, value) = item?;
+                let (description_id, refset_id) = key.value();
+                if refset_id != EN_AU_LANGUAGE_REFSET_ID {
+                    continue;
+                }
+                let member: LanguageRefsetMember = bincode::deserialize(value.value())?;
+                if !member.active || member.acceptability_id != PREFERRED_ACCEPTABILITY_ID {
+                    continue;
+                }
+
+                let Some(desc_value) = descriptions.get(description_id)? else {
+                    continue;
+                };
+                let desc: SnomedDescription = bincode::deserialize(desc_value.value())?;
+                if desc.active && desc.type_id == SYNONYM_TYPE_ID {
+                    preferred_terms.insert(desc.concept_id.as_str(), desc_value.value())?;
+                    count += 1;
+                }
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(count)
+    }
+
+    /// Batch insert SNOMED relationships into redb
+    /// Active is-a relationships are also written to the (parent, child) hierarchy index
+    fn insert_relationship_batch(&self, batch: Vec<crate::parsers::SnomedRelationship>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_RELATIONSHIPS))?;
+            let mut isa_table = write_txn.open_table(self.storage.table(SNOMED_ISA_CHILDREN))?;
+
+            for relationship in batch {
+                // Only active rows are indexed: a snapshot can carry an inactive and an active
+                // row for the same (parent, child) pair under different relationship ids
+                if relationship.active && relationship.type_id == IS_A_TYPE_ID {
+                    isa_table.insert(
+                        (relationship.destination_id.as_str(), relationship.source_id.as_str()),
+                        self.version_id,
+                    )?;
+                }
+
+                let storage_relationship = SnomedRelationship {
+                    id: relationship.id,
+                    effective_time: relationship.effective_time,
+                    active: relationship.active,
+                    module_id: relationship.module_id,
+                    source_id: relationship.source_id,
+                    destination_id: relationship.destination_id,
+                    relationship_group: relationship.relationship_group,
+                    type_id: relationship.type_id,
+                    characteristic_type_id: relationship.characteristic_type_id,
+                    modifier_id: relationship.modifier_id,
+                    version_id: self.version_id,
+                };
+
+                let bytes = bincode::serialize(&storage_relationship)?;
+                table.insert(
+                    (storage_relationship.source_id.as_str(), storage_relationship.id.as_str()),
+                    bytes.as_slice(),
+                )?;
+            }
+        }
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Batch insert SNOMED relationships from a Full file, keeping the latest row per relationship
+    /// The is-a index is rebuilt afterwards by rebuild_isa_index
+    fn insert_relationship_full_batch(&self, batch: Vec<crate::parsers::SnomedRelationship>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_RELATIONSHIPS))?;
+
+            for relationship in batch {
+                let superseded = match table.get((relationship.source_id.as_str(), relationship.id.as_str()))? {
+                    Some(existing) => {
+                        let existing: SnomedRelationship = bincode::deserialize(existing.value())?;
+                        existing.effective_time > relationship.effective_time
+                    }
+                    None => false,
+                };
+                if superseded {
+                    continue;
+                }
+
+                let storage_relationship = SnomedRelationship {
+                    id: relationship.id,
+                    effective_time: relationship.effective_time,
+                    active: relationship.active,
+                    module_id: relationship.module_id,
+                    source_id: relationship.source_id,
+                    destination_id: relationship.destination_id,
+                    relationship_group: relationship.relationship_group,
+                    type_id: relationship.type_id,
+                    characteristic_type_id: relationship.characteristic_type_id,
+                    modifier_id: relationship.modifier_id,
+                    version_id: self.version_id,
+                };
+
+                let bytes = bincode::serialize(&storage_relationship)?;
+                table.insert(
+                    (storage_relationship.source_id.as_str(), storage_relationship.id.as_str()),
+                    bytes.as_slice(),
+                )?;
+            }
+        }
+        self.save_checkpoint(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Rebuild the (parent, child) is-a index from the active is-a rows in the relationships table
+    fn rebuild_isa_index(&self) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        write_txn.delete_table(self.storage.table(SNOMED_ISA_CHILDREN))?;
+        {
+            let relationships = write_txn.open_table(self.storage.table(SNOMED_RELATIONSHIPS))?;
+            let mut isa_table = write_txn.open_table(self.storage.table(SNOMED_ISA_CHILDREN))?;
+
+            for item in relationships.iter()? {
+                let (_, value) = item?;
+                let relationship: SnomedRelationship = bincode::deserialize(value.value())?;
+                if relationship.active && relationship.type_id == IS_A_TYPE_ID {
+                    isa_table.insert(
+                        (relationship.destination_id.as_str(), relationship.source_id.as_str()),
+                        relationship.version_id,
+                    )?;
+                }
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Batch apply SNOMED relationship delta rows, returning whether the is-a hierarchy changed
+    /// An inactivated is-a row only removes its (parent, child) entry when no other active is-a
+    /// relationship still links the same pair
+    fn apply_relationship_delta_batch(&self, batch: Vec<crate::parsers::SnomedRelationship>) -> Result<bool> {
+        let mut hierarchy_changed = false;
+
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(SNOMED_RELATIONSHIPS))?;
+            let mut isa_table = write_txn.open_table(self.storage.table(SNOMED_ISA_CHILDREN))?;
+            let mut inactivated = Vec::new();
+
+            for relationship in batch {
+                if relationship.type_id == IS_A_TYPE_ID {
+                    hierarchy_changed = true;
+                    if relationship.active {
+                        isa_table.insert(
+                            (relationship.destination_id.as_str(), relationship.source_id.as_str()),
+                            self.version_id,
+                        )?;
+                    } else {
+                        inactivated.push((relationship.source_id.clone(), relationship.destination_id.clone()));
+                    }
+                }
+
+                let storage_relationship = SnomedRelationship {
+                    id: relationship.id,
+                    effective_time: relationship.effective_time,
+                    active: relationship.active,
+                    module_id: relationship.module_id,
+                    source_id: relationship.source_id,
+                    destination_id: relationship.destination_id,
+                    relationship_group: relationship.relationship_group,
+                    type_id: relationship.type_id,
+                    characteristic_type_id: relationship.characteristic_type_id,
+                    modifier_id: relationship.modifier_id,
+                    version_id: self.version_id,
+                };
+
+                let bytes = bincode::serialize(&storage_relationship)?;
+                table.insert(
+                    (storage_relationship.source_id.as_str(), storage_relationship.id.as_str()),
+                    bytes.as_slice(),
+                )?;
+            }
+
+            for (source_id, destination_id) in inactivated {
+                let mut still_linked = false;
+                for item in table.range((source_id.as_str(), "")..)? {
+                    let (key, value) = item?;
+                    if key.value().0 != source_id {
+                        break;
+                    }
+
+                    let other: SnomedRelationship = bincode::deserialize(value.value())?;
+                    if other.active && other.type_id == IS_A_TYPE_ID && other.destination_id == destination_id {
+                        still_linked = true;
+                        break;
+                    }
+                }
+
+                if !still_linked {
+                    isa_table.remove((destination_id.as_str(), source_id.as_str()))?;
+                }
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(hierarchy_changed)
+    }
+
+    /// Build the transitive closure of the is-a hierarchy from the (parent, child) index
+    /// Ancestor sets are computed once in memory, then written in one transaction that replaces the old closure
+    fn build_snomed_closure(&self) -> Result<usize> {
+        let db = self.storage.database();
+
+        // Load the is-a graph as child -> parents (numeric SCTIDs keep the working set small)
+        let mut parents: HashMap<u64, Vec<u64>> = HashMap::new();
+        {
+            let read_txn = db.begin_read()?;
+            let table = read_txn.open_table(self.storage.table(SNOMED_ISA_CHILDREN))?;
+            for item in table.iter()? {
+                let (key, _) = item?;
+                let (parent, child) = key.value();
+                if let (Ok(parent), Ok(child)) = (parent.parse::<u64>(), child.parse::<u64>()) {
+                    parents.entry(child).or_default().push(parent);
+                }
+            }
+        }
+
+        let concept_total = parents.len();
+
+        self.emit_progress(ImportProgress {
+            phase: "Building Hierarchy".to_string(),
+            phase_status: "in_progress".to_string(),
+            current: 0,
+            total: Some(concept_total),
+            percentage: 0.0,
+            message: "Computing is-a transitive closure...".to_string(),
+        });
+
+        let ancestors = Self::transitive_ancestors(&parents);
+
+        // Replace any previous closure with the freshly computed one in a single commit, so readers
+        // see either the old closure or the new one and a failed rebuild leaves the old one in place
+        let write_txn = db.begin_write()?;
+        write_txn.delete_table(self.storage.table(SNOMED_CLOSURE))?;
+
+        let mut written_concepts = 0;
+        let mut closure_count = 0;
+        let concepts: Vec<&u64> = ancestors.keys().collect();
+
+        let mut table = write_txn.open_table(self.storage.table(SNOMED_CLOSURE))?;
+        for chunk in concepts.chunks(1000) {
+            self.check_cancelled()?;
+            for descendant in chunk {
+                let descendant_id = descendant.to_string();
+                for ancestor in &ancestors[*descendant] {
+                    let ancestor_id = ancestor.to_string();
+                    table.insert((ancestor_id.as_str(), descendant_id.as_str()), self.version_id)?;
+                    closure_count += 1;
+                }
+            }
+
+            written_concepts += chunk.len();
+            self.emit_progress(ImportProgress {
+                phase: "Building Hierarchy".to_string(),
+                phase_status: "in_progress".to_string(),
+                current: written_concepts,
+                total: Some(concept_total),
+                percentage: (written_concepts as f32 / concept_total.max(1) as f32 * 100.0).min(100.0),
+                message: format!("Stored closure for {} concepts...", written_concepts),
+            });
+        }
+        drop(table);
+        write_txn.commit()?;
+
+        self.emit_progress(ImportProgress {
+            phase: "Building Hierarchy".to_string(),
+            phase_status: "completed".to_string(),
+            current: concept_total,
+            total: Some(concept_total),
+            percentage: 100.0,
+            message: format!("Stored {} closure entries", closure_count),
+        });
+
+        Ok(closure_count)
+    }
+
+    /// Compute every concept's full set of proper ancestors from a child -> parents map
+    /// Uses an explicit stack (no recursion) and memoises each concept's ancestor set;
+    /// a cycle in the input is broken by ignoring the edge that closes it
+    fn transitive_ancestors(parents: &HashMap<u64, Vec<u64>>) -> HashMap<u64, Vec<u64>> {
+        let mut ancestors: HashMap<u64, Vec<u64>> = HashMap::with_capacity(parents.len());
+        let mut in_progress: HashSet<u64> = HashSet::new();
+
+        for &start in parents.keys() {
+            if ancestors.contains_key(&start) {
+                continue;
+            }
+
+            // (concept, parents_pushed)
+            let mut stack = vec![(start, false)];
+            while let Some((concept, parents_pushed)) = stack.pop() {
+                if ancestors.contains_key(&concept) {
+                    continue;
+                }
+
+                let direct = parents.get(&concept).map(Vec::as_slice).unwrap_or(&[]);
+
+                if !parents_pushed {
+                    in_progress.insert(concept);
+                    stack.push((concept, true));
+                    for parent in direct {
+                        if !ancestors.contains_key(parent) && !in_progress.contains(parent) {
+                            stack.push((*parent, false));
+                        }
+                    }
+                    continue;
+                }
+
+                let mut all = Vec::new();
+                for parent in direct {
+                    all.push(*parent);
+                    if let Some(parent_ancestors) = ancestors.get(parent) {
+                        all.extend_from_slice(parent_ancestors);
+                    }
+                }
+                all.sort_unstable();
+                all.dedup();
+                all.retain(|a| *a != concept);
+
+                in_progress.remove(&concept);
+                ancestors.insert(concept, all);
+            }
+        }
+
+        ancestors
+    }
+
+    /// Batch insert AMT codes into redb
+    fn insert_amt_batch(&self, batch: Vec<crate::parsers::AmtCode>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.storage.table(AMT_CODES))?;
+
+            for code in batch {
+                let storage_code = AmtCode {
+                    id: code.id.clone(),
+                    preferred_term: code.preferred_term,
+                    code_type: code.code_type.clone(),
+                    parent_code: code.parent_code,
+                    properties: code.properties,
+                    version_id: self.version_id,
+                };
+
+                let bytes = bincode::serialize(&storage_code)?;
+                // Use composite key (SCTID, code_type) to allow same SCTID across multiple product types
+                table.insert((code.id.as_str(), code.code_type.as_str()), bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Batch insert ValueSets and their concepts
+    fn insert_valueset_batch(&self, batch: Vec<crate::parsers::ValueSetEntry>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut vs_table = write_txn.open_table(self.storage.table(VALUESETS))?;
+            let mut concept_table = write_txn.open_table(self.storage.table(VALUESET_CONCEPTS))?;
+            let mut member_table = write_txn.open_table(self.storage.table(VALUESET_MEMBERS))?;
+            let mut compose_table = write_txn.open_table(self.storage.table(VALUESET_COMPOSE))?;
+
+            for valueset in batch {
+                // Insert ValueSet metadata
+                let storage_valueset = ValueSet {
+                    url: valueset.url.clone(),
+                    version: valueset.version,
+                    name: valueset.name,
+                    title: valueset.title,
+                    status: valueset.status,
+                    description: valueset.description,
+                    publisher: valueset.publisher,
+                    version_id: self.version_id,
+                };
+
+                let vs_bytes = bincode::serialize(&storage_valueset)?;
+                vs_table.insert(storage_valueset.url.as_str(), vs_bytes.as_slice())?;
+
+                // Insert expansion concepts if present
+                if let Some(expansion) = valueset.expansion {
+                    for concept in expansion {
+                        let storage_concept = ValueSetConcept {
+                            valueset_url: valueset.url.clone(),
+                            system: concept.system,
+                            code: concept.code.clone(),
+                            display: concept.display,
+                        };
+
+                        let concept_bytes = bincode::serialize(&storage_concept)?;
+                        concept_table.insert(
+                            (valueset.url.as_str(), storage_concept.code.as_str()),
+                            concept_bytes.as_slice(),
+                        )?;
+                        member_table.insert(
+                            (valueset.url.as_str(), storage_concept.system.as_str(), storage_concept.code.as_str()),
+                            (),
+                        )?;
+                    }
+                }
+
+                // Keep intensional compose rules for resolution at expansion time
+                if let Some(compose) = valueset.compose {
+                    let compose_bytes = bincode::serialize(&compose)?;
+                    compose_table.insert(valueset.url.as_str(), compose_bytes.as_slice())?;
+                }
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Insert a CodeSystem with its concepts, deriving each concept's children from the parents
+    /// A re-imported CodeSystem replaces its previous concepts
+    fn insert_codesystem(&self, codesystem: crate::parsers::CodeSystemEntry) -> Result<()> {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for concept in &codesystem.concepts {
+            for parent in &concept.parents {
+                children.entry(parent.clone()).or_default().push(concept.code.clone());
+            }
+        }
+
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut codesystems_table = write_txn.open_table(self.storage.table(CODESYSTEMS))?;
+            let mut concepts_table = write_txn.open_table(self.storage.table(CODESYSTEM_CONCEPTS))?;
+            let url = codesystem.url.as_str();
+
+            let mut stale = Vec::new();
+            for item in concepts_table.range((url, "")..)? {
+                let (key, _) = item?;
+                let (system, code) = key.value();
+                if system != url {
+                    break;
+                }
+                stale.push(code.to_string());
+            }
+            for code in &stale {
+                concepts_table.remove((url, code.as_str()))?;
+            }
+
+            let mut concept_count = 0;
+            for concept in &codesystem.concepts {
+                let storage_concept = CodeSystemConcept {
+                    system: codesystem.url.clone(),
+                    code: concept.code.clone(),
+                    display: concept.display.clone(),
+                    definition: concept.definition.clone(),
+                    parents: concept.parents.clone(),
+                    children: children.remove(&concept.code).unwrap_or_default(),
+                    properties: concept.properties.clone(),
+                    designations: concept.designations.clone(),
+                };
+                let bytes = bincode::serialize(&storage_concept)?;
+                concepts_table.insert((url, concept.code.as_str()), bytes.as_slice())?;
+                concept_count += 1;
+            }
+
+            let storage_codesystem = CodeSystem {
+                url: codesystem.url.clone(),
+                version: codesystem.version,
+                name: codesystem.name,
+                title: codesystem.title,
+                status: codesystem.status,
+                description: codesystem.description,
+                publisher: codesystem.publisher,
+                content: codesystem.content,
+                concept_count,
+                version_id: self.version_id,
+            };
+            let bytes = bincode::serialize(&storage_codesystem)?;
+            codesystems_table.insert(url, bytes.as_slice())?;
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Batch insert ConceptMaps, one row per element target, indexed by source and by target
+    /// A re-imported ConceptMap replaces its previous rows
+    fn insert_concept_map_batch(&self, batch: Vec<crate::parsers::ConceptMapEntry>) -> Result<()> {
+        let db = self.storage.database();
+        let write_txn = db.begin_write()?;
+        {
+            let mut maps_table = write_txn.open_table(self.storage.table(CONCEPT_MAPS))?;
+            let mut elements_table = write_txn.open_table(self.storage.table(CONCEPT_MAP_ELEMENTS))?;
+            let mut targets_table = write_txn.open_table(self.storage.table(CONCEPT_MAP_TARGETS))?;
+
+            let mut replaced = HashSet::new();
+            for map in &batch {
+                if maps_table.get(map.url.as_str())?.is_some() {
+                    replaced.insert(map.url.clone());
+                }
+            }
+            if !replaced.is_empty() {
+                for table in [&mut elements_table, &mut targets_table] {
+                    let mut stale = Vec::new();
+                    for item in table.iter()? {
+                        let (key, _) = item?;
+                        let (system, code, url, row) = key.value();
+                        if replaced.contains(url) {
+                            stale.push((system.to_string(), code.to_string(), url.to_string(), row));
+                        }
+                    }
+                    for (system, code, url, row) in &stale {
+                        table.remove((system.as_str(), code.as_str(), url.as_str(), *row))?;
+                    }
+                }
+            }
+
+            for map in batch {
+                let mut row_id = 0u64;
+                for (group_index, group) in map.groups.iter().enumerate() {
+                    for element in &group.elements {
+                        for target in &element.targets {
+                            let row = ConceptMapRow {
+                                map_url: map.url.clone(),
+                                group: group_index as u32,
+                                source_system: group.source.clone(),
+                                source_code: element.code.clone(),
+                                source_display: element.display.clone(),
+                                target_system: group.target.clone(),
+                                target_code: target.code.clone(),
+                                target_display: target.display.clone(),
+                                equivalence: target.equivalence.clone(),
+                                comment: target.comment.clone(),
+                            };
+                            let bytes = bincode::serialize(&row)?;
+
+                            let source_system = row.source_system.as_deref().unwrap_or("");
+                            elements_table.insert(
+                                (source_system, row.source_code.as_str(), map.url.as_str(), row_id),
+                                bytes.as_slice(),
+                            )?;
+                            if let Some(target_code) = &row.target_code {
+                                let target_system = row.target_system.as_deref().unwrap_or("");
+                                targets_table.insert(
+                                    (target_system, target_code.as_str(), map.url.as_str(), row_id),
+                                    bytes.as_slice(),
+                                )?;
+                            }
+                            row_id += 1;
+                        }
+                    }
+                }
+
+                let storage_map = ConceptMap {
+                    groups: map
+                        .groups
+                        .into_iter()
+                        .map(|group| ConceptMapGroupInfo {
+                            source: group.source,
+                            target: group.target,
+                            unmapped: group.unmapped,
+                        })
+                        .collect(),
+                    url: map.url,
+                    version: map.version,
+                    name: map.name,
+                    title: map.title,
+                    status: map.status,
+                    description: map.description,
+                    publisher: map.publisher,
+                    source: map.source,
+                    target: map.target,
+                    version_id: self.version_id,
+                };
+                let map_bytes = bincode::serialize(&storage_map)?;
+                maps_table.insert(storage_map.url.as_str(), map_bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Build Tantivy index for SNOMED descriptions
+    fn build_snomed_index(&self, searcher: &TerminologySearch) -> Result<()> {
+        println!("Building SNOMED Tantivy index...");
+
+        // Replace the existing documents in one commit (rolled back if indexing fails)
+        let indexed = searcher.rebuild(ContentFamily::Snomed, || {
+            searcher.clear_snomed()?;
+
+            // Read all descriptions from redb and index them
+            let db = self.storage.database();
+            let read_txn = db.begin_read()?;
+            let table = read_txn.open_table(self.storage.table(SNOMED_DESCRIPTIONS))?;
+
+            let mut indexed = 0;
+            for item in table.iter()? {
+                let (_, value) = item?;
+                let desc: SnomedDescription = bincode::deserialize(value.value())?;
+
+                searcher.index_snomed_description(
+                    &desc.concept_id,
+                    &desc.term,
+                    &desc.type_id,
+                    desc.active,
+                )?;
+
+                indexed += 1;
+                if indexed % 10000 == 0 {
+                    self.check_cancelled()?;
+                    println!("Indexed {} SNOMED descriptions...", indexed);
+                }
+            }
+            Ok(indexed)
+        })?;
+
+        println!("SNOMED index built: {} descriptions indexed", indexed);
+
+        Ok(())
+    }
+
+    /// Replace the search documents of the given SNOMED concepts with their current descriptions
+    fn reindex_snomed_concepts(
+        &self,
+        concept_ids: &HashSet<String>,
+        searcher: &TerminologySearch,
+    ) -> Result<usize> {
+        searcher.rebuild(ContentFamily::Snomed, || self.index_snomed_concepts(concept_ids, searcher))
+    }
+
+    /// Replace the search documents of the given SNOMED concepts without committing them
+    fn index_snomed_concepts(&self, concept_ids: &HashSet<String>, searcher: &TerminologySearch) -> Result<usize> {
+        for concept_id in concept_ids {
+            searcher.delete_snomed_concept(concept_id)?;
+        }
+
+        let descriptions = self.storage.get_snomed_descriptions_for(concept_ids)?;
+        for desc in &descriptions {
+            searcher.index_snomed_description(&desc.concept_id, &desc.term, &desc.type_id, desc.active)?;
+        }
+        Ok(descriptions.len())
+    }
+
+    /// Check if a trade product entry should be skipped from indexing
+    /// Returns true if the entry is a TPP TP/TPUU TP/TPP/TPUU that just adds a brand name to the MP term
+    fn should_skip_duplicate_trade_product<'txn>(
+        code: &AmtCode,
+        table: &'txn impl ReadableTable<(&'static str, &'static str), &'static [u8]>,
+    ) -> Result<bool> {
+        // Only filter these four trade product types
+        if code.code_type != "TPP TP"
+            && code.code_type != "TPUU TP"
+            && code.code_type != "TPP"
+            && code.code_type != "TPUU"
+        {
+            return Ok(false);
+        }
+
+        // Must contain parentheses (brand name pattern)
+        if !code.preferred_term.contains('(') || !code.preferred_term.ends_with(')') {
+            return Ok(false); // Keep entries without brand name suffix
+        }
+
+        // Must have a parent to compare against
+        let parent_code_id = match &code.parent_code {
+            Some(id) => id,
+            None => return Ok(false), // No parent, keep it
+        };
+
+        // Define hierarchy (same as in amt_csv.rs)
+        // Maps child_type -> parent_type
+        let hierarchy: std::collections::HashMap<&str, &str> = [
+            ("CTPP", "TPP"),
+            ("TPP", "TPUU"),
+            ("TPUU", "TPP TP"),
+            ("TPP TP", "TPUU TP"),
+            ("TPUU TP", "MPP"),
+            ("MPP", "MPUU"),
+            ("MPUU", "MP"),
+        ].iter().cloned().collect();
+
+        // Walk up the hierarchy to find the root MP entry
+        let mut current_id = parent_code_id.clone();
+        let mut current_type = hierarchy.get(code.code_type.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Unknown code type: {}", code.code_type))?
+            .to_string();
+
+        // Walk up until we reach MP
+        while current_type != "MP" {
+            // Look up current parent
+            let key = (current_id.as_str(), current_type.as_str());
+            let value = match table.get(key)? {
+                Some(v) => v,
+                None => return Ok(false), // Parent not found, keep the entry
+            };
+
+            let parent_code: AmtCode = bincode::deserialize(value.value())?;
+
+            // If we've reached MP, compare terms
+            if current_type == "MP" {
+                break;
+            }
+
+            // Move to next parent
+            current_id = match parent_code.parent_code {
+                Some(id) => id,
+                None => {
+                    // No parent but not MP yet - this shouldn't happen, but keep the entry
+                    return Ok(false);
+                }
+            };
+
+            current_type = match hierarchy.get(current_type.as_str()) {
+                Some(parent_type) => parent_type.to_string(),
+                None => return Ok(false), // Unknown type, keep it
+            };
+        }
+
+        // Now current_id and current_type point to the MP entry
+        // Look it up to get its preferred_term
+        let mp_key = (current_id.as_str(), "MP");
+        let mp_value = match table.get(mp_key)? {
+            Some(v) => v,
+            None => return Ok(false), // MP not found, keep the entry
+        };
+
+        let mp_code: AmtCode = bincode::deserialize(mp_value.value())?;
+
+        // Case-insensitive comparison: check if TP term starts with MP term
+        let tp_term_lower = code.preferred_term.to_lowercase();
+        let mp_term_lower = mp_code.preferred_term.to_lowercase();
+
+        // Skip if the TP term starts with the MP term (indicating it's just MP + brand)
+        Ok(tp_term_lower.starts_with(&mp_term_lower))
+    }
+
+    /// Build Tantivy index for AMT codes (public so the app can rebuild the index on demand)
+    pub fn build_amt_index(&self, searcher: &TerminologySearch) -> Result<()> {
+        println!("Building AMT Tantivy index...");
+
+        // Replace the existing documents in one commit (rolled back if indexing fails)
+        let indexed = searcher.rebuild(ContentFamily::Amt, || {
+            searcher.clear_amt()?;
+
+            // Read all AMT codes from redb and index them
+            let db = self.storage.database();
+            let read_txn = db.begin_read()?;
+            let table = read_txn.open_table(self.storage.table(AMT_CODES))?;
+
+            let mut indexed = 0;
+            let mut filtered = 0;
+            for item in table.iter()? {
+                let (_, value) = item?;
+                let code: AmtCode = bincode::deserialize(value.value())?;
+
+                // Skip duplicate trade product entries (brand name wrappers of MP terms)
+                if Self::should_skip_duplicate_trade_product(&code, &table)? {
+                    filtered += 1;
+                    continue;
+                }
+
+                searcher.index_amt_code(
+                    &code.id,
+                    &code.preferred_term,
+                    &code.code_type,
+                )?;
+
+                indexed += 1;
+                if indexed % 1000 == 0 {
+                    self.check_cancelled()?;
+                    println!("Indexed {} AMT codes...", indexed);
+                }
+            }
+
+            if filtered > 0 {
+                println!("Filtered {} duplicate trade product entries", filtered);
+            }
+            Ok(indexed)
+        })?;
+
+        println!("AMT index built: {} codes indexed", indexed);
+
+        Ok(())
+    }
+
+    /// Rebuild a family's search index from this importer's storage view
+    pub fn build_search_index(&self, family: ContentFamily, searcher: &TerminologySearch) -> Result<()> {
+        match family {
+            ContentFamily::Snomed => self.build_snomed_index(searcher),
+            ContentFamily::Amt => self.build_amt_index(searcher),
+            ContentFamily::ValueSets => self.build_valueset_index(searcher),
+        }
+    }
+
+    /// Build Tantivy index for ValueSets
+    fn build_valueset_index(&self, searcher: &TerminologySearch) -> Result<()> {
+        println!("Building ValueSet Tantivy index...");
+
+        // Replace the existing documents in one commit (rolled back if indexing fails)
+        let indexed = searcher.rebuild(ContentFamily::ValueSets, || {
+            searcher.clear_valuesets()?;
+
+            // Read all ValueSets from redb and index them
+            let db = self.storage.database();
+            let read_txn = db.begin_read()?;
+            let table = read_txn.open_table(self.storage.table(VALUESETS))?;
+
+            let mut indexed = 0;
+            for item in table.iter()? {
+                let (_, value) = item?;
+                let vs: ValueSet = bincode::deserialize(value.value())?;
+
+                searcher.index_valueset(
+                    &vs.url,
+                    vs.title.as_deref(),
+                    vs.name.as_deref(),
+                    vs.description.as_deref(),
+                )?;
+
+                indexed += 1;
+            }
+            Ok(indexed)
+        })?;
+
+        println!("ValueSet index built: {} valuesets indexed", indexed);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_storage;
+
+    #[test]
+    fn test_transitive_ancestors() {
+        // 1 is the root; 2 and 3 are children of 1; 4 has both 2 and 3 as parents; 5 is under 4
+        let mut parents: HashMap<u64, Vec<u64>> = HashMap::new();
+        parents.insert(2, vec![1]);
+        parents.insert(3, vec![1]);
+        parents.insert(4, vec![2, 3]);
+        parents.insert(5, vec![4]);
+        // 6 and 7 form a cycle - must terminate without panicking
+        parents.insert(6, vec![7]);
+        parents.insert(7, vec![6]);
+
+        let ancestors = TerminologyImporter::transitive_ancestors(&parents);
+
+        assert_eq!(ancestors[&2], vec![1]);
+        assert_eq!(ancestors[&4], vec![1, 2, 3]);
+        assert_eq!(ancestors[&5], vec![1, 2, 3, 4]);
+        assert!(ancestors[&1].is_empty(), "Root has no ancestors");
+        assert!(ancestors[&6].contains(&7));
+        assert!(!ancestors[&6].contains(&6), "A concept is never its own ancestor");
+    }
+
+    #[test]
+    fn test_closure_rebuild_is_atomic() {
+        let (_dir, storage) = test_storage();
+        let add_isa = |parent: &str, child: &str| {
+            let write_txn = storage.database().begin_write().unwrap();
+            write_txn.open_table(SNOMED_ISA_CHILDREN).unwrap().insert((parent, child), 1).unwrap();
+            write_txn.commit().unwrap();
+        };
+        add_isa("1", "2");
+        add_isa("2", "3");
+        assert_eq!(TerminologyImporter::new(&storage, 1).build_snomed_closure().unwrap(), 3);
+
+        // The rebuild is cancelled after the old closure was deleted in its transaction
+        add_isa("3", "4");
+        let version_id = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        let jobs = crate::jobs::JobRegistry::new(&storage).unwrap();
+        let job = jobs.start(&storage, &storage.get_version(version_id).unwrap().unwrap()).unwrap();
+        job.cancel();
+        let err = TerminologyImporter::new(&storage, 1).with_job(&job).build_snomed_closure().unwrap_err();
+        assert!(err.is::<crate::jobs::Cancelled>());
+        assert!(storage.snomed_is_ancestor("1", "3").unwrap());
+        assert!(!storage.snomed_is_ancestor("1", "4").unwrap(), "The new closure was never committed");
+
+        assert_eq!(TerminologyImporter::new(&storage, 1).build_snomed_closure().unwrap(), 6);
+        assert!(storage.snomed_is_ancestor("1", "4").unwrap());
+    }
+
+    #[test]
+    fn test_snomed_full_history_point_in_time() {
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let concept = |effective_time: &str, active: bool| crate::parsers::SnomedConcept {
+            id: "22298006".to_string(),
+            effective_time: effective_time.to_string(),
+            active,
+            module_id: "900000000000207008".to_string(),
+            definition_status_id: "900000000000074008".to_string(),
+        };
+        let description = |id: &str, effective_time: &str, active: bool, term: &str| crate::parsers::SnomedDescription {
+            id: id.to_string(),
+            effective_time: effective_time.to_string(),
+            active,
+            module_id: "900000000000207008".to_string(),
+            concept_id: "22298006".to_string(),
+            language_code: "en".to_string(),
+            type_id: "900000000000003001".to_string(),
+            term: term.to_string(),
+            case_significance_id: "900000000000448009".to_string(),
+        };
+
+        // Full files are not ordered by date, so the later row arrives first here
+        importer
+            .insert_concept_batch(vec![concept("20240131", false), concept("20020131", true)], true)
+            .unwrap();
+        importer
+            .insert_description_batch(
+                vec![
+                    description("751689013", "20020131", true, "Old heart attack term"),
+                    description("751689013", "20100131", false, "Old heart attack term"),
+                    description("751690016", "20100131", true, "Myocardial infarction (disorder)"),
+                ],
+                true,
+            )
+            .unwrap();
+
+        assert!(!storage.get_snomed_concept("22298006").unwrap().unwrap().active, "Latest row is current");
+        assert!(storage.get_snomed_concept_as_of("22298006", "20200101").unwrap().unwrap().active);
+        assert!(storage.get_snomed_concept_as_of("22298006", "20010101").unwrap().is_none());
+
+        let as_of_2005 = storage.get_snomed_descriptions_as_of("22298006", "20050101").unwrap();
+        assert_eq!(as_of_2005.len(), 1);
+        assert!(as_of_2005[0].active);
+        let as_of_2015 = storage.get_snomed_descriptions_as_of("22298006", "20150101").unwrap();
+        assert_eq!(as_of_2015.len(), 2);
+        assert!(as_of_2015.iter().any(|d| d.id == "751689013" && !d.active));
+
+        assert_eq!(crate::queries::TerminologyQueries::parse_as_of("2015-01-01").unwrap(), "20150101");
+        assert_eq!(crate::queries::TerminologyQueries::parse_as_of("20150101").unwrap(), "20150101");
+        assert!(crate::queries::TerminologyQueries::parse_as_of("2015-13-01").is_err());
+    }
+
+    #[test]
+    fn test_language_refset_preferred_terms() {
+        use crate::parsers::snomed_rf2::{ACCEPTABLE_ACCEPTABILITY_ID, FSN_TYPE_ID};
+        use crate::queries::{Acceptability, DesignationKind, TerminologyQueries};
+
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let description = |id: &str, type_id: &str, term: &str| crate::parsers::SnomedDescription {
+            id: id.to_string(),
+            effective_time: "20200131".to_string(),
+            active: true,
+            module_id: "32506021000036107".to_string(),
+            concept_id: "22298006".to_string(),
+            language_code: "en".to_string(),
+            type_id: type_id.to_string(),
+            term: term.to_string(),
+            case_significance_id: "900000000000448009".to_string(),
+        };
+        let member = |description_id: &str, effective_time: &str, acceptability_id: &str| {
+            crate::parsers::LanguageRefsetMember {
+                id: format!("member-{}", description_id),
+                effective_time: effective_time.to_string(),
+                active: true,
+                module_id: "32506021000036107".to_string(),
+                refset_id: EN_AU_LANGUAGE_REFSET_ID.to_string(),
+                referenced_component_id: description_id.to_string(),
+                acceptability_id: acceptability_id.to_string(),
+            }
+        };
+
+        importer
+            .insert_concept_batch(
+                vec![crate::parsers::SnomedConcept {
+                    id: "22298006".to_string(),
+                    effective_time: "20200131".to_string(),
+                    active: true,
+                    module_id: "32506021000036107".to_string(),
+                    definition_status_id: "900000000000074008".to_string(),
+                }],
+                false,
+            )
+            .unwrap();
+        importer
+            .insert_description_batch(
+                vec![
+                    description("751689013", FSN_TYPE_ID, "Myocardial infarction (disorder)"),
+                    description("37436014", SYNONYM_TYPE_ID, "Heart attack"),
+                    description("37443015", SYNONYM_TYPE_ID, "Myocardial infarction"),
+                ],
+                false,
+            )
+            .unwrap();
+
+        // Before the language refset is imported the FSN is displayed
+        let result = TerminologyQueries::lookup_snomed_code(&storage, "22298006").unwrap().unwrap();
+        assert_eq!(result.display, "Myocardial infarction (disorder)");
+
+        importer
+            .insert_language_batch(vec![
+                member("751689013", "20200131", PREFERRED_ACCEPTABILITY_ID),
+                member("37436014", "20200131", ACCEPTABLE_ACCEPTABILITY_ID),
+                member("37443015", "20200131", PREFERRED_ACCEPTABILITY_ID),
+            ])
+            .unwrap();
+        assert_eq!(importer.rebuild_preferred_terms().unwrap(), 1);
+
+        let result = TerminologyQueries::lookup_snomed_code(&storage, "22298006").unwrap().unwrap();
+        assert_eq!(result.display, "Myocardial infarction");
+        assert_eq!(result.synonyms[0], "Myocardial infarction");
+        assert_eq!(result.designations[0].kind, DesignationKind::Fsn);
+        let heart_attack = result.designations.iter().find(|d| d.value == "Heart attack").unwrap();
+        assert_eq!(heart_attack.acceptability, Some(Acceptability::Acceptable));
+
+        // An older row does not override the current member; a newer one swaps the preferred term
+        importer
+            .insert_language_batch(vec![
+                member("37436014", "20100131", PREFERRED_ACCEPTABILITY_ID),
+                member("37443015", "20240131", ACCEPTABLE_ACCEPTABILITY_ID),
+            ])
+            .unwrap();
+        assert_eq!(importer.rebuild_preferred_terms().unwrap(), 0);
+        importer
+            .insert_language_batch(vec![member("37436014", "20240131", PREFERRED_ACCEPTABILITY_ID)])
+            .unwrap();
+        importer.rebuild_preferred_terms().unwrap();
+
+        let terms = storage.get_snomed_preferred_terms(["22298006"]).unwrap();
+        assert_eq!(terms.get("22298006").map(String::as_str), Some("Heart attack"));
+    }
+
+    #[test]
+    fn test_refset_membership() {
+        use crate::queries::TerminologyQueries;
+
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let member = |id: &str, effective_time: &str, active: bool, code: &str| crate::parsers::RefsetMember {
+            id: id.to_string(),
+            effective_time: effective_time.to_string(),
+            active,
+            module_id: "32506021000036107".to_string(),
+            refset_id: "32570071000036102".to_string(),
+            referenced_component_id: code.to_string(),
+            attributes: Vec::new(),
+        };
+
+        importer
+            .insert_concept_batch(
+                ["32570071000036102", "22298006", "73211009", "195967001"]
+                    .iter()
+                    .map(|id| crate::parsers::SnomedConcept {
+                        id: id.to_string(),
+                        effective_time: "20200131".to_string(),
+                        active: true,
+                        module_id: "32506021000036107".to_string(),
+                        definition_status_id: "900000000000074008".to_string(),
+                    })
+                    .collect(),
+                false,
+            )
+            .unwrap();
+        importer
+            .insert_refset_batch(vec![
+                member("m1", "20200131", true, "22298006"),
+                member("m2", "20200131", true, "73211009"),
+                member("m3", "20200131", false, "195967001"),
+            ])
+            .unwrap();
+        let kinds = HashMap::from([("32570071000036102".to_string(), "Simple".to_string())]);
+        assert_eq!(importer.rebuild_refset_index(kinds).unwrap(), 1);
+
+        let refsets = TerminologyQueries::list_refsets(&storage).unwrap();
+        assert_eq!(refsets[0].kind, "Simple");
+        assert_eq!(refsets[0].member_count, 2);
+
+        let page = TerminologyQueries::refset_members(&storage, "32570071000036102", 1, 10).unwrap().unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.members[0].code, "73211009", "Second member in code order");
+        assert!(TerminologyQueries::refset_members(&storage, "123", 0, 10).unwrap().is_none());
+
+        assert!(TerminologyQueries::is_refset_member(&storage, "32570071000036102", "22298006").unwrap());
+        assert!(!TerminologyQueries::is_refset_member(&storage, "32570071000036102", "195967001").unwrap());
+
+        // A later inactive row removes the member, and the summary keeps its type
+        importer.insert_refset_batch(vec![member("m1", "20240131", false, "22298006")]).unwrap();
+        importer.rebuild_refset_index(HashMap::new()).unwrap();
+        assert!(!storage.is_refset_member("32570071000036102", "22298006").unwrap());
+        assert_eq!(storage.get_snomed_refset("32570071000036102").unwrap().unwrap().kind, "Simple");
+
+        let ecl = TerminologyQueries::evaluate_ecl(&storage, "^ 32570071000036102", 10).unwrap();
+        assert_eq!(ecl.total, 1);
+        assert_eq!(ecl.concepts[0].code, "73211009");
+    }
+
+    #[test]
+    fn test_inactive_concept_associations() {
+        use crate::parsers::snomed_rf2::FSN_TYPE_ID;
+        use crate::queries::TerminologyQueries;
+
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let concept = |id: &str, active: bool| crate::parsers::SnomedConcept {
+            id: id.to_string(),
+            effective_time: "20200131".to_string(),
+            active,
+            module_id: "32506021000036107".to_string(),
+            definition_status_id: "900000000000074008".to_string(),
+        };
+        let fsn = |id: &str, concept_id: &str, term: &str| crate::parsers::SnomedDescription {
+            id: id.to_string(),
+            effective_time: "20200131".to_string(),
+            active: true,
+            module_id: "32506021000036107".to_string(),
+            concept_id: concept_id.to_string(),
+            language_code: "en".to_string(),
+            type_id: FSN_TYPE_ID.to_string(),
+            term: term.to_string(),
+            case_significance_id: "900000000000448009".to_string(),
+        };
+        let row = |id: &str, refset_id: &str, active: bool, target: &str| crate::parsers::RefsetMember {
+            id: id.to_string(),
+            effective_time: "20200131".to_string(),
+            active,
+            module_id: "32506021000036107".to_string(),
+            refset_id: refset_id.to_string(),
+            referenced_component_id: "1000001".to_string(),
+            attributes: vec![target.to_string()],
+        };
+
+        importer
+            .insert_concept_batch(vec![concept("1000001", false), concept("22298006", true)], false)
+            .unwrap();
+        importer
+            .insert_description_batch(
+                vec![
+                    fsn("d1", "22298006", "Myocardial infarction (disorder)"),
+                    fsn("d2", "900000000000482003", "Duplicate component (foundation metadata concept)"),
+                ],
+                false,
+            )
+            .unwrap();
+        importer
+            .insert_refset_batch(vec![
+                row("i1", "900000000000489007", true, "900000000000482003"),
+                row("a1", "900000000000527005", true, "22298006"),
+                row("a2", "900000000000523009", false, "195967001"),
+            ])
+            .unwrap();
+
+        let result = TerminologyQueries::lookup_snomed_code(&storage, "1000001").unwrap().unwrap();
+        assert!(!result.active);
+        let inactivation = result.inactivation.expect("Inactive concept has an inactivation");
+        assert_eq!(inactivation.reason_code.as_deref(), Some("900000000000482003"));
+        assert_eq!(
+            inactivation.reason.as_deref(),
+            Some("Duplicate component (foundation metadata concept)")
+        );
+        assert_eq!(inactivation.associations.len(), 1, "Inactive association rows are ignored");
+        assert_eq!(inactivation.associations[0].association, "SAME AS");
+        assert_eq!(inactivation.associations[0].code, "22298006");
+        assert_eq!(
+            inactivation.associations[0].display.as_deref(),
+            Some("Myocardial infarction (disorder)")
+        );
+
+        let active = TerminologyQueries::lookup_snomed_code(&storage, "22298006").unwrap().unwrap();
+        assert!(active.inactivation.is_none());
+    }
+
+    #[test]
+    fn test_complex_map_translation() {
+        use crate::queries::TerminologyQueries;
+
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let map_row = |id: &str, active: bool, code: &str, group: &str, priority: &str, target: &str| {
+            crate::parsers::RefsetMember {
+                id: id.to_string(),
+                effective_time: "20200131".to_string(),
+                active,
+                module_id: "32506021000036107".to_string(),
+                refset_id: "1000000".to_string(),
+                referenced_component_id: code.to_string(),
+                attributes: [group, priority, "TRUE", "ALWAYS I21.9", target, "447561005"]
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+            }
+        };
+
+        importer
+            .insert_refset_batch(vec![
+                map_row("m1", true, "22298006", "1", "1", "I21.9"),
+                map_row("m2", true, "22298006", "2", "1", ""),
+                map_row("m3", true, "57054005", "1", "1", "I21.9"),
+                map_row("m4", false, "195967001", "1", "1", "J45.9"),
+            ])
+            .unwrap();
+        let kinds = HashMap::from([("1000000".to_string(), "ComplexMap".to_string())]);
+        importer.rebuild_refset_index(kinds).unwrap();
+        assert_eq!(importer.rebuild_map_index().unwrap(), 3, "Inactive rows are not mapped");
+
+        let forward = TerminologyQueries::translate_code(&storage, "snomed", "22298006", None).unwrap();
+        assert_eq!(forward.matches.len(), 2);
+        assert_eq!(forward.matches[0].code.as_deref(), Some("I21.9"));
+        assert_eq!(forward.matches[0].map_rule.as_deref(), Some("TRUE"));
+        assert_eq!(forward.matches[0].correlation_id.as_deref(), Some("447561005"));
+        assert_eq!(forward.matches[1].map_group, 2);
+        assert!(forward.matches[1].code.is_none(), "Empty target column");
+
+        let reverse = TerminologyQueries::translate_code(&storage, "icd-10-am", "I21.9", None).unwrap();
+        let sources: Vec<_> = reverse.matches.iter().filter_map(|m| m.code.as_deref()).collect();
+        assert_eq!(sources, ["22298006", "57054005"]);
+
+        let other = TerminologyQueries::translate_code(&storage, "snomed", "22298006", Some("123")).unwrap();
+        assert!(other.matches.is_empty());
+        assert!(TerminologyQueries::translate_code(&storage, "snomed", "195967001", None)
+            .unwrap()
+            .matches
+            .is_empty());
+    }
+
+    #[test]
+    fn test_concept_map_translation() {
+        use crate::parsers::{ConceptMapElement, ConceptMapEntry, ConceptMapGroup, ConceptMapTarget, ConceptMapUnmapped};
+        use crate::queries::TerminologyQueries;
+
+        const PATH: &str = "http://example.org/CodeSystem/path";
+        const SCT: &str = "http://snomed.info/sct";
+
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let map = |url: &str, elements: Vec<(&str, &str, &str)>, unmapped: Option<ConceptMapUnmapped>| {
+            ConceptMapEntry {
+                url: url.to_string(),
+                version: None,
+                name: None,
+                title: None,
+                status: Some("active".to_string()),
+                description: None,
+                publisher: None,
+                source: None,
+                target: None,
+                groups: vec![ConceptMapGroup {
+                    source: Some(PATH.to_string()),
+                    target: Some(SCT.to_string()),
+                    elements: elements
+                        .into_iter()
+                        .map(|(code, target, equivalence)| ConceptMapElement {
+                            code: code.to_string(),
+                            display: None,
+                            targets: vec![ConceptMapTarget {
+                                code: Some(target.to_string()),
+                                display: None,
+                                equivalence: equivalence.to_string(),
+                                comment: None,
+                            }],
+                        })
+                        .collect(),
+                    unmapped,
+                    ..Default::default()
+                }],
+            }
+        };
+        let other_map = |url: &str| ConceptMapUnmapped {
+            mode: "other-map".to_string(),
+            code: None,
+            display: None,
+            url: Some(url.to_string()),
+        };
+
+        importer
+            .insert_concept_map_batch(vec![
+                map(
+                    "http://example.org/ConceptMap/a",
+                    vec![("GLU", "33747003", "equivalent"), ("HB", "38082009", "wider")],
+                    Some(other_map("http://example.org/ConceptMap/b")),
+                ),
+                map(
+                    "http://example.org/ConceptMap/b",
+                    vec![("NA", "39972003", "equivalent")],
+                    Some(other_map("http://example.org/ConceptMap/a")),
+                ),
+            ])
+            .unwrap();
+
+        let forward = TerminologyQueries::translate(&storage, PATH, "GLU", None, None, false).unwrap();
+        assert!(forward.result);
+        assert_eq!(forward.matches.len(), 1);
+        assert_eq!(forward.matches[0].code.as_deref(), Some("33747003"));
+        assert_eq!(forward.matches[0].source, "http://example.org/ConceptMap/a");
+
+        let reverse = TerminologyQueries::translate(&storage, SCT, "38082009", None, None, true).unwrap();
+        assert_eq!(reverse.matches[0].code.as_deref(), Some("HB"));
+        assert_eq!(reverse.matches[0].equivalence, "narrower", "Reversed equivalence");
+
+        // Map a falls back to map b for NA, and the b -> a fallback does not loop
+        let via_other = TerminologyQueries::translate(
+            &storage,
+            PATH,
+            "NA",
+            Some("http://example.org/ConceptMap/a"),
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(via_other.matches.len(), 1);
+        assert_eq!(via_other.matches[0].source, "http://example.org/ConceptMap/b");
+
+        let none = TerminologyQueries::translate(&storage, PATH, "XYZ", None, Some(SCT), false).unwrap();
+        assert!(!none.result);
+        assert!(none.message.is_some());
+        assert!(TerminologyQueries::translate(&storage, PATH, "GLU", Some("http://missing"), None, false).is_err());
+
+        // Re-importing a ConceptMap replaces its rows
+        let fixed = ConceptMapUnmapped {
+            mode: "fixed".to_string(),
+            code: Some("261665006".to_string()),
+            display: Some("Unknown".to_string()),
+            url: None,
+        };
+        importer
+            .insert_concept_map_batch(vec![map("http://example.org/ConceptMap/a", vec![], Some(fixed))])
+            .unwrap();
+        let replaced = TerminologyQueries::translate(
+            &storage,
+            PATH,
+            "GLU",
+            Some("http://example.org/ConceptMap/a"),
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(replaced.matches.len(), 1);
+        assert_eq!(replaced.matches[0].code.as_deref(), Some("261665006"));
+        assert!(storage.get_concept_map_rows_by_target(SCT, "33747003").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_codesystem_lookup_and_expansion() {
+        use crate::parsers::BundleResource;
+        use crate::queries::TerminologyQueries;
+
+        let (dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        let bundle = r#"{
+            "resourceType": "Bundle",
+            "entry": [
+                { "resource": {
+                    "resourceType": "CodeSystem",
+                    "url": "http://example.org/CodeSystem/care-setting",
+                    "title": "Care Setting",
+                    "content": "complete",
+                    "concept": [
+                        { "code": "hospital", "display": "Hospital", "concept": [
+                            { "code": "ed", "display": "Emergency department",
+                              "designation": [ { "language": "en-AU", "value": "ED" } ] },
+                            { "code": "ward", "display": "Ward",
+                              "property": [ { "code": "status", "valueCode": "retired" } ] }
+                        ] },
+                        { "code": "community", "display": "Community" }
+                    ]
+                } },
+                { "resource": {
+                    "resourceType": "ValueSet",
+                    "url": "http://example.org/ValueSet/hospital",
+                    "compose": { "include": [ {
+                        "system": "http://example.org/CodeSystem/care-setting",
+                        "filter": [ { "property": "concept", "op": "is-a", "value": "hospital" } ]
+                    } ] }
+                } }
+            ]
+        }"#;
+        let path = dir.path().join("bundle.json");
+        std::fs::write(&path, bundle).unwrap();
+
+        let mut valuesets = Vec::new();
+        ValueSetR4Parser::parse_bundle(&path, |resource| {
+            match resource {
+                BundleResource::CodeSystem(codesystem) => importer.insert_codesystem(codesystem)?,
+                BundleResource::ValueSet(valueset) => valuesets.push(valueset),
+                BundleResource::ConceptMap(_) => {}
+            }
+            Ok(())
+        })
+        .unwrap();
+        importer.insert_valueset_batch(valuesets).unwrap();
+
+        let system = "http://example.org/CodeSystem/care-setting";
+        assert_eq!(storage.get_codesystem(system).unwrap().unwrap().concept_count, 4);
+
+        let hospital = TerminologyQueries::lookup_codesystem_code(&storage, system, "hospital").unwrap().unwrap();
+        assert_eq!(hospital.display, "Hospital");
+        let children: Vec<_> = hospital.properties.iter().filter(|p| p.code == "child").map(|p| p.value.as_str()).collect();
+        assert_eq!(children, ["ed", "ward"]);
+
+        let ed = TerminologyQueries::lookup_codesystem_code(&storage, system, "ed").unwrap().unwrap();
+        assert!(ed.active);
+        assert_eq!(ed.synonyms, ["ED"]);
+        assert_eq!(ed.designations[0].language.as_deref(), Some("en-AU"));
+        assert!(ed.properties.iter().any(|p| p.code == "parent" && p.value == "hospital"));
+
+        let ward = TerminologyQueries::lookup_codesystem_code(&storage, system, "ward").unwrap().unwrap();
+        assert!(!ward.active, "Retired concepts are inactive");
+        assert!(TerminologyQueries::lookup_codesystem_code(&storage, system, "nope").unwrap().is_none());
+
+        let expansion = TerminologyQueries::expand_valueset(&storage, "http://example.org/ValueSet/hospital")
+            .unwrap()
+            .unwrap();
+        let codes: Vec<_> = expansion.concepts.iter().map(|c| c.code.as_str()).collect();
+        assert_eq!(codes, ["ed", "hospital", "ward"]);
+        assert_eq!(expansion.concepts[0].display.as_deref(), Some("Emergency department"));
+    }
+
+    #[test]
+    fn test_snomed_delta_base_version_check() {
+        let (_dir, storage) = test_storage();
+        let base = "http://snomed.info/sct/32506021000036107/version/20250131";
+
+        let importer = TerminologyImporter::new(&storage, 0);
+        assert!(importer.check_snomed_base_version(None).is_err(), "Delta must declare a base version");
+        assert!(
+            importer.check_snomed_base_version(Some(base)).is_err(),
+            "Nothing imported yet"
+        );
+
+        let snapshot_id = storage
+            .record_version("snomed", "20250131", None, "", None, Some(base), None, None)
+            .unwrap();
+        storage.mark_imported(snapshot_id).unwrap();
+        storage.set_live_release(ContentFamily::Snomed, Some(snapshot_id)).unwrap();
+
+        assert!(importer.check_snomed_base_version(Some(base)).is_ok());
+        let error = importer
+            .check_snomed_base_version(Some("http://snomed.info/sct/32506021000036107/version/20241231"))
+            .unwrap_err();
+        assert!(error.to_string().contains("20250131"));
+
+        // A delta moves the live release on, until another release is promoted over it
+        let delta_version = "http://snomed.info/sct/32506021000036107/version/20250228";
+        let delta_id = storage
+            .record_version("snomed-delta", "20250228", None, "", None, Some(delta_version), None, Some(base))
+            .unwrap();
+        let delta = storage.get_version(delta_id).unwrap().unwrap();
+        storage.stage_release(&delta).unwrap().promote_staged_delta().unwrap();
+        assert_eq!(storage.delta_base(delta_id).unwrap(), Some(snapshot_id));
+        assert!(importer.check_snomed_base_version(Some(delta_version)).is_ok());
+        assert!(importer.check_snomed_base_version(Some(base)).is_err());
+
+        let other_version = "http://snomed.info/sct/32506021000036107/version/20250331";
+        let other_id = storage
+            .record_version("snomed", "20250331", None, "", None, Some(other_version), None, None)
+            .unwrap();
+        storage.mark_imported(other_id).unwrap();
+        storage.set_live_release(ContentFamily::Snomed, Some(other_id)).unwrap();
+        assert!(importer.check_snomed_base_version(Some(other_version)).is_ok());
+        assert!(
+            importer.check_snomed_base_version(Some(delta_version)).is_err(),
+            "The delta was applied to the demoted release"
+        );
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let (_dir, storage) = test_storage();
+        let id = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        let version = storage.get_version(id).unwrap().unwrap();
+        let staging = storage.stage_release(&version).unwrap();
+        let jobs = crate::jobs::JobRegistry::new(&storage).unwrap();
+        let total = 2 * BATCH_SIZE + 500;
+        let parse = |emit: &mut dyn FnMut(usize) -> Result<()>| -> Result<usize> {
+            (0..total).try_for_each(&mut *emit)?;
+            Ok(total)
+        };
+
+        // The first run is cancelled once its first batch is committed
+        let job = jobs.start(&storage, &version).unwrap();
+        let importer = TerminologyImporter::new(&staging, id).with_job(&job);
+        importer.resume_checkpoint().unwrap();
+        let mut inserted = Vec::new();
+        let result = importer.import_rows("concepts", || Ok(total), parse, |batch| {
+            inserted.extend(batch);
+            let write_txn = staging.database().begin_write()?;
+            importer.save_checkpoint(&write_txn)?;
+            write_txn.commit()?;
+            job.cancel();
+            Ok(())
+        });
+        assert!(result.unwrap_err().is::<crate::jobs::Cancelled>());
+        assert_eq!(inserted, (0..BATCH_SIZE).collect::<Vec<_>>());
+        let checkpoint = storage.get_import_checkpoint(id).unwrap().unwrap();
+        assert_eq!(checkpoint.phase.as_deref(), Some("concepts"));
+        assert_eq!(checkpoint.rows, BATCH_SIZE as u64);
+
+        // Staging again keeps the committed rows, and the resumed run inserts only what is left
+        let staging = storage.stage_release(&version).unwrap();
+        let job = jobs.start(&storage, &version).unwrap();
+        let importer = TerminologyImporter::new(&staging, id).with_job(&job);
+        importer.resume_checkpoint().unwrap();
+        let mut inserted = Vec::new();
+        let count = importer
+            .import_rows("concepts", || Ok(total), parse, |batch| {
+                inserted.extend(batch);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, total);
+        assert_eq!(inserted, (BATCH_SIZE..total).collect::<Vec<_>>());
+        assert_eq!(importer.resume_from("concepts"), None, "A completed phase is skipped");
+        assert!(storage.get_jobs().unwrap().iter().any(|job| job.resumed));
+
+        storage.mark_imported(id).unwrap();
+        assert!(storage.get_import_checkpoint(id).unwrap().is_none());
+    }
+
+    /// Cancels a job once a phase reports a committed batch
+    struct CancelAfterBatch<'a>(&'a JobControl, &'static str);
+
+    impl ProgressReporter for CancelAfterBatch<'_> {
+        fn import_progress(&self, progress: &ImportProgress) {
+            if progress.phase == self.1 && progress.current > 0 {
+                self.0.cancel();
+            }
+        }
+    }
+
+    #[test]
+    fn test_refset_import_resumes() {
+        use redb::ReadableTableMetadata;
+        use std::io::Write;
+        let (dir, storage) = test_storage();
+        let id = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        let version = storage.get_version(id).unwrap().unwrap();
+        let jobs = crate::jobs::JobRegistry::new(&storage).unwrap();
+
+        let zip_path = dir.path().join("refsets.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        let refsets = [("Simple", "32570071000036102", BATCH_SIZE + 200), ("Ordered", "32570081000036100", BATCH_SIZE)];
+        for (file, refset_id, rows) in refsets {
+            zip.start_file(format!("Snapshot/Refset/der2_Refset_{}Snapshot_AU1000036_20250131.txt", file), options)
+                .unwrap();
+            writeln!(zip, "id\teffectiveTime\tactive\tmoduleId\trefsetId\treferencedComponentId").unwrap();
+            for row in 0..rows {
+                writeln!(zip, "{}-{}\t20250131\t1\t32506021000036107\t{}\t{}", file, row, refset_id, row).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+        let members = |storage: &TerminologyStorage| {
+            let read_txn = storage.database().begin_read().unwrap();
+            read_txn.open_table(storage.table(SNOMED_REFSET_MEMBERS)).unwrap().len().unwrap()
+        };
+
+        // The first run is cancelled once its first batch is committed
+        let staging = storage.stage_release(&version).unwrap();
+        let mut archive = Rf2Archive::open(&zip_path).unwrap();
+        let files = archive.find_refset_files("Snapshot");
+        let job = jobs.start(&storage, &version).unwrap();
+        let cancel = CancelAfterBatch(&job, "Importing Reference Set Members");
+        let importer = TerminologyImporter::new(&staging, id).with_job(&job).with_progress(&cancel);
+        importer.resume_checkpoint().unwrap();
+        let result = importer.import_refsets(&mut archive, &zip_path, &files);
+        assert!(result.unwrap_err().is::<crate::jobs::Cancelled>());
+        assert_eq!(members(&staging), BATCH_SIZE as u64);
+        assert_eq!(storage.get_import_checkpoint(id).unwrap().unwrap().rows, BATCH_SIZE as u64);
+
+        // The resumed run imports the rest, and still knows the type of every refset
+        let staging = storage.stage_release(&version).unwrap();
+        let job = jobs.start(&storage, &version).unwrap();
+        let importer = TerminologyImporter::new(&staging, id).with_job(&job);
+        importer.resume_checkpoint().unwrap();
+        let (count, kinds) = importer.import_refsets(&mut archive, &zip_path, &files).unwrap();
+        assert_eq!(count, 2 * BATCH_SIZE + 200);
+        assert_eq!(members(&staging), count as u64);
+        assert_eq!(kinds.get("32570071000036102").map(String::as_str), Some("Simple"));
+        assert_eq!(kinds.get("32570081000036100").map(String::as_str), Some("Ordered"));
+    }
+
+    #[test]
+    fn test_import_rows_pipeline_errors() {
+        let (_dir, storage) = test_storage();
+        let importer = TerminologyImporter::new(&storage, 1);
+
+        // The parser would block on a full pipeline forever if the failed write did not close it
+        let mut parsed = 0;
+        let err = importer
+            .import_rows(
+                "concepts",
+                || Ok(100 * BATCH_SIZE),
+                |emit: &mut dyn FnMut(usize) -> Result<()>| {
+                    for row in 0..100 * BATCH_SIZE {
+                        emit(row)?;
+                        parsed += 1;
+                    }
+                    Ok(parsed)
+                },
+                |_| anyhow::bail!("Disk full"),
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Disk full");
+        assert!(parsed < 100 * BATCH_SIZE);
+
+        let mut inserted = Vec::new();
+        let err = importer
+            .import_rows(
+                "descriptions",
+                || Ok(0),
+                |emit: &mut dyn FnMut(usize) -> Result<()>| {
+                    (0..BATCH_SIZE + 10).try_for_each(&mut *emit)?;
+                    anyhow::bail!("Malformed line")
+                },
+                |batch| {
+                    inserted.extend(batch);
+                    Ok(())
+                },
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Malformed line");
+        assert_eq!(inserted, (0..BATCH_SIZE).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,143 @@
+//! Long-running import jobs: ids, cancellation and the record of how each run ended.
+//! Jobs are stored in redb so they can be listed after a restart; the checkpoints that let an
+//! interrupted SNOMED import resume are written by the importer (see `ImportCheckpoint`).
+
+use crate::storage::{ImportJob, JobStatus, TerminologyStorage, TerminologyVersion};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Error an import stops with when its job is cancelled
+#[derive(Debug, Error)]
+#[error("Import cancelled")]
+pub struct Cancelled;
+
+/// Cancel flag of a running job, checked by the importer between batches
+#[derive(Debug)]
+pub struct JobControl {
+    id: u64,
+    cancelled: AtomicBool,
+}
+
+impl JobControl {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Ask the job to stop at its next batch
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fail with `Cancelled` once the job has been cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+}
+
+/// Import jobs running in this process
+pub struct JobRegistry {
+    running: Mutex<HashMap<u64, Arc<JobControl>>>,
+}
+
+impl JobRegistry {
+    /// Create the registry, marking jobs a previous process left running as interrupted
+    pub fn new(storage: &TerminologyStorage) -> Result<Self> {
+        for job in storage.get_jobs()? {
+            if job.status == JobStatus::Running {
+                storage.finish_job(job.id, JobStatus::Interrupted, Some("Stopped before it finished".to_string()))?;
+            }
+        }
+        Ok(Self {
+            running: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Record a running job for importing a version
+    pub fn start(&self, storage: &TerminologyStorage, version: &TerminologyVersion) -> Result<Arc<JobControl>> {
+        let job = storage.create_job(version)?;
+        let control = Arc::new(JobControl {
+            id: job.id,
+            cancelled: AtomicBool::new(false),
+        });
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(job.id, Arc::clone(&control));
+        Ok(control)
+    }
+
+    /// Record how a job ended: completed, cancelled or failed with its error
+    pub fn finish(&self, storage: &TerminologyStorage, job: &JobControl, result: &Result<String>) -> Result<()> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner()).remove(&job.id);
+        let (status, message) = match result {
+            Ok(message) => (JobStatus::Completed, message.clone()),
+            Err(e) if e.is::<Cancelled>() => (JobStatus::Cancelled, e.to_string()),
+            Err(e) => (JobStatus::Failed, format!("{:#}", e)),
+        };
+        storage.finish_job(job.id, status, Some(message))?;
+        Ok(())
+    }
+
+    /// Cancel a running job; returns false if no job with that id is running
+    pub fn cancel(&self, job_id: u64) -> bool {
+        match self.running.lock().unwrap_or_else(|e| e.into_inner()).get(&job_id) {
+            Some(job) => {
+                job.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel every running job, returning how many were running
+    pub fn cancel_all(&self) -> usize {
+        let running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        running.values().for_each(|job| job.cancel());
+        running.len()
+    }
+
+    /// All recorded jobs, newest first
+    pub fn list(&self, storage: &TerminologyStorage) -> Result<Vec<ImportJob>> {
+        Ok(storage.get_jobs()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_storage;
+
+    #[test]
+    fn test_interrupted_jobs() {
+        let (_dir, storage) = test_storage();
+        let id = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        let version = storage.get_version(id).unwrap().unwrap();
+
+        let jobs = JobRegistry::new(&storage).unwrap();
+        let first = jobs.start(&storage, &version).unwrap();
+        let second = jobs.start(&storage, &version).unwrap();
+        assert!(jobs.cancel(second.id()));
+        assert!(second.check().unwrap_err().is::<Cancelled>());
+        jobs.finish(&storage, &second, &Err(Cancelled.into())).unwrap();
+        assert!(!jobs.cancel(second.id()), "A finished job cannot be cancelled");
+        assert!(first.check().is_ok());
+
+        let listed = JobRegistry::new(&storage).unwrap().list(&storage).unwrap();
+        let statuses: Vec<_> = listed.iter().map(|job| (job.id, job.status)).collect();
+        assert_eq!(
+            statuses,
+            [(second.id(), JobStatus::Cancelled), (first.id(), JobStatus::Interrupted)]
+        );
+        assert!(listed.iter().all(|job| job.finished_at.is_some()));
+    }
+}
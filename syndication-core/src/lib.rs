@@ -0,0 +1,25 @@
+//! Terminology storage, search, NCTS syndication and import, independent of the desktop runtime.
+//! Progress is reported through the `ProgressReporter` trait rather than Tauri events.
+
+pub mod auth;
+pub mod ecl;
+pub mod import;
+pub mod jobs;
+pub mod local;
+pub mod ncts;
+pub mod parsers;
+pub mod paths;
+pub mod progress;
+pub mod queries;
+pub mod search;
+pub mod storage;
+pub mod sync;
+
+pub use auth::TokenManager;
+pub use import::TerminologyImporter;
+pub use jobs::JobRegistry;
+pub use ncts::{NctsClient, TerminologyType};
+pub use paths::DataPaths;
+pub use progress::{ImportProgress, ProgressReporter, SyncProgress};
+pub use search::TerminologySearch;
+pub use storage::TerminologyStorage;
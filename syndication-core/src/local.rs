@@ -0,0 +1,234 @@
+//! Releases supplied as local files, for machines without NCTS access (air-gapped mode).
+//! The terminology type and version are read from the file itself instead of the syndication feed.
+
+use crate::parsers::{Rf2Archive, SnomedRf2Parser};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Version metadata of a local release file, in the form `record_version` takes
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalRelease {
+    pub terminology_type: String,
+    pub version: String,
+    pub effective_date: Option<String>,
+    pub content_item_identifier: Option<String>,
+    pub content_item_version: Option<String>,
+    pub sha256_hash: String,
+}
+
+/// Identify an RF2 ZIP (snomed or snomed-full), AMT CSV (amt) or FHIR bundle (valuesets)
+/// Files without a version of their own are versioned by their SHA-256 hash
+pub fn identify_release(path: &Path) -> Result<LocalRelease> {
+    let mut head = [0u8; 4];
+    let read = File::open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?
+        .read(&mut head)?;
+    let sha256_hash = file_sha256(path)?;
+
+    let release = if head[..read].starts_with(b"PK\x03\x04") {
+        identify_rf2(path, sha256_hash)?
+    } else if first_char(path)? == Some('{') {
+        identify_bundle(path, sha256_hash)?
+    } else if is_amt_csv(path)? {
+        identify_amt(path, sha256_hash)
+    } else {
+        anyhow::bail!("{:?} is not an RF2 ZIP, AMT CSV or FHIR bundle", path);
+    };
+    println!(
+        "Identified {:?} as {} version {}",
+        path, release.terminology_type, release.version
+    );
+    Ok(release)
+}
+
+/// SNOMED CT release: dated by its RF2 file names, with the edition module from the module dependency refset
+fn identify_rf2(path: &Path, sha256_hash: String) -> Result<LocalRelease> {
+    let mut archive = Rf2Archive::open(path)?;
+    let (terminology_type, release) = if !archive.find_files("sct2_Concept_Full").is_empty() {
+        ("snomed-full", "Full")
+    } else if !archive.find_files("sct2_Concept_Snapshot").is_empty() {
+        ("snomed", "Snapshot")
+    } else if !archive.find_files("sct2_Concept_Delta").is_empty() {
+        anyhow::bail!(
+            "{:?} is an RF2 delta, which does not record the release it applies to; import a snapshot or full release",
+            path
+        );
+    } else {
+        anyhow::bail!("{:?} is a ZIP archive without RF2 concept files", path);
+    };
+
+    let concept_file = archive.find_file(&format!("sct2_Concept_{}", release))?;
+    let date = date_in_name(&concept_file)
+        .with_context(|| format!("No release date in RF2 file name {}", concept_file))?;
+
+    let module = match archive
+        .find_refset_files(release)
+        .into_iter()
+        .find(|(_, kind)| kind == "ModuleDependency")
+    {
+        Some((file, _)) => edition_module(&mut archive, &file)?,
+        None => None,
+    };
+    let (version, content_item_identifier, content_item_version) = match module {
+        Some(module) => {
+            let uri = format!("http://snomed.info/sct/{}/version/{}", module, date);
+            (uri.clone(), Some(format!("http://snomed.info/sct/{}", module)), Some(uri))
+        }
+        None => (date.clone(), None, None),
+    };
+
+    Ok(LocalRelease {
+        terminology_type: terminology_type.to_string(),
+        version,
+        effective_date: Some(iso_date(&date)),
+        content_item_identifier,
+        content_item_version,
+        sha256_hash,
+    })
+}
+
+/// The module no other module depends on, i.e. the edition that packages the rest
+fn edition_module(archive: &mut Rf2Archive, file: &str) -> Result<Option<String>> {
+    let mut modules = HashSet::new();
+    let mut dependencies = HashSet::new();
+    SnomedRf2Parser::parse_refset(archive.reader(file)?, |member| {
+        if member.active {
+            modules.insert(member.module_id);
+            dependencies.insert(member.referenced_component_id);
+        }
+        Ok(())
+    })?;
+
+    let mut editions = modules.difference(&dependencies);
+    Ok(match (editions.next(), editions.next()) {
+        (Some(edition), None) => Some(edition.clone()),
+        _ => None,
+    })
+}
+
+/// AMT CSV: the file has no metadata, so it is dated by its file name when that holds a date
+fn identify_amt(path: &Path, sha256_hash: String) -> LocalRelease {
+    let date = path.file_name().and_then(|name| date_in_name(&name.to_string_lossy()));
+    LocalRelease {
+        terminology_type: "amt".to_string(),
+        version: date.clone().unwrap_or_else(|| hash_version(&sha256_hash)),
+        effective_date: date.as_deref().map(iso_date),
+        content_item_identifier: None,
+        content_item_version: None,
+        sha256_hash,
+    }
+}
+
+/// FHIR bundle: versioned by its identifier or version id, else the time it was assembled
+fn identify_bundle(path: &Path, sha256_hash: String) -> Result<LocalRelease> {
+    let reader = BufReader::new(File::open(path).with_context(|| format!("Failed to open {:?}", path))?);
+    let bundle: Value = serde_json::from_reader(reader).context("Failed to parse JSON")?;
+    match bundle.get("resourceType").and_then(Value::as_str) {
+        Some("Bundle" | "ValueSet" | "CodeSystem" | "ConceptMap") => {}
+        other => anyhow::bail!("{:?} is JSON but not a FHIR bundle (resourceType {:?})", path, other),
+    }
+
+    let text = |pointer: &str| bundle.pointer(pointer).and_then(Value::as_str).map(str::to_string);
+    let timestamp = text("/timestamp").or_else(|| text("/meta/lastUpdated"));
+    let version = text("/identifier/value")
+        .or_else(|| text("/meta/versionId"))
+        .or_else(|| text("/version"))
+        .or_else(|| timestamp.clone())
+        .unwrap_or_else(|| hash_version(&sha256_hash));
+
+    Ok(LocalRelease {
+        terminology_type: "valuesets".to_string(),
+        version,
+        effective_date: timestamp.map(|t| t.chars().take(10).collect()),
+        content_item_identifier: text("/identifier/system"),
+        content_item_version: None,
+        sha256_hash,
+    })
+}
+
+/// The first character of a text file after any byte order mark and leading whitespace
+fn first_char(path: &Path) -> Result<Option<char>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(1024).read_to_end(&mut head)?;
+    let head = String::from_utf8_lossy(&head);
+    Ok(head.trim_start_matches('\u{feff}').trim_start().chars().next())
+}
+
+/// An AMT CSV header names its SCTID columns by product type, starting with the CTPP
+fn is_amt_csv(path: &Path) -> Result<bool> {
+    let mut header = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut header)?;
+    Ok(header.to_ascii_uppercase().contains("CTPP SCTID"))
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {:?} for hashing", path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).context("Failed to read file for hashing")?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// The last YYYYMMDD date in a file name, e.g. 20240131 in sct2_Concept_Snapshot_AU1000036_20240131.txt
+fn date_in_name(name: &str) -> Option<String> {
+    let name = name.rsplit('/').next()?;
+    name.rsplit(|c: char| !c.is_ascii_digit())
+        .find(|digits| digits.len() == 8 && (digits.starts_with("19") || digits.starts_with("20")))
+        .map(str::to_string)
+}
+
+fn iso_date(date: &str) -> String {
+    format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..8])
+}
+
+fn hash_version(sha256_hash: &str) -> String {
+    format!("local-{}", &sha256_hash[..12])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_identify_release() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+        let rf2 = dir.path().join("release.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&rf2).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("Snapshot/Terminology/sct2_Concept_Snapshot_AU1000036_20250131.txt", options).unwrap();
+        writeln!(zip, "id\teffectiveTime\tactive\tmoduleId\tdefinitionStatusId").unwrap();
+        zip.start_file("Snapshot/Refset/der2_ssRefset_ModuleDependencySnapshot_AU1000036_20250131.txt", options)
+            .unwrap();
+        writeln!(zip, "id\teffectiveTime\tactive\tmoduleId\trefsetId\treferencedComponentId\tsourceEffectiveTime\ttargetEffectiveTime").unwrap();
+        writeln!(zip, "a\t20250131\t1\t32506021000036107\t900000000000534007\t900000000000207008\t20250131\t20241201").unwrap();
+        writeln!(zip, "b\t20250131\t1\t900000000000207008\t900000000000534007\t900000000000012004\t20241201\t20241201").unwrap();
+        zip.finish().unwrap();
+        let release = identify_release(&rf2).unwrap();
+        assert_eq!(release.terminology_type, "snomed");
+        assert_eq!(release.version, "http://snomed.info/sct/32506021000036107/version/20250131");
+        assert_eq!(release.content_item_identifier.as_deref(), Some("http://snomed.info/sct/32506021000036107"));
+        assert_eq!(release.effective_date.as_deref(), Some("2025-01-31"));
+
+        let amt = dir.path().join("AMT_20250228.csv");
+        std::fs::write(&amt, "CTPP SCTID,CTPP PT,ARTG_ID,MP SCTID,MP PT\n1,a,2,3,b\n").unwrap();
+        let release = identify_release(&amt).unwrap();
+        assert_eq!((release.terminology_type.as_str(), release.version.as_str()), ("amt", "20250228"));
+
+        let bundle = dir.path().join("valuesets.json");
+        std::fs::write(&bundle, r#"{"resourceType": "Bundle", "timestamp": "2025-03-01T10:00:00Z", "entry": []}"#).unwrap();
+        let release = identify_release(&bundle).unwrap();
+        assert_eq!(release.terminology_type, "valuesets");
+        assert_eq!(release.version, "2025-03-01T10:00:00Z");
+        assert_eq!(release.effective_date.as_deref(), Some("2025-03-01"));
+
+        let unknown = dir.path().join("notes.txt");
+        std::fs::write(&unknown, "not a release\n").unwrap();
+        assert!(identify_release(&unknown).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::auth::TokenManager;
+use crate::progress::{ProgressReporter, SyncProgress};
 use anyhow::{Context, Result};
 use atom_syndication::{Entry, Feed};
 use chrono::{DateTime, Utc};
@@ -12,6 +13,8 @@ pub const SYNDICATION_FEED_URL: &str = "https://api.healthterminologies.gov.au/s
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TerminologyType {
     Snomed,
+    SnomedFull,
+    SnomedDelta,
     Loinc,
     ValueSets,
     Amt,
@@ -21,7 +24,9 @@ impl TerminologyType {
     /// Returns the category terms used in NCTS feed to identify this terminology type
     pub fn category_terms(&self) -> Vec<&str> {
         match self {
-            TerminologyType::Snomed => vec!["SCT_RF2_SNAPSHOT"], // Only SNAPSHOT - server doesn't expose DELTA
+            TerminologyType::Snomed => vec!["SCT_RF2_SNAPSHOT"],
+            TerminologyType::SnomedFull => vec!["SCT_RF2_FULL"], // Every historical row, for point-in-time queries
+            TerminologyType::SnomedDelta => vec!["SCT_RF2_DELTA"], // Applied on top of the imported sctBaseVersion
             TerminologyType::Loinc => vec!["LOINC"], // Not available - proprietary binary only
             TerminologyType::ValueSets => vec!["FHIR_Bundle"], // FHIR R4 Bundles only
             TerminologyType::Amt => vec!["AMT_CSV"], // CSV format only
@@ -36,6 +41,8 @@ impl TerminologyType {
     pub fn display_name(&self) -> &str {
         match self {
             TerminologyType::Snomed => "SNOMED CT-AU",
+            TerminologyType::SnomedFull => "SNOMED CT-AU (Full)",
+            TerminologyType::SnomedDelta => "SNOMED CT-AU (Delta)",
             TerminologyType::Loinc => "LOINC",
             TerminologyType::ValueSets => "Value Sets",
             TerminologyType::Amt => "Australian Medicines Terminology",
@@ -55,6 +62,23 @@ impl TerminologyType {
     }
 }
 
+impl std::str::FromStr for TerminologyType {
+    type Err = anyhow::Error;
+
+    /// Parse the lowercase names used by commands and the CLI (snomed, snomed-full, snomed-delta, loinc, valuesets, amt)
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "snomed" => Ok(TerminologyType::Snomed),
+            "snomed-full" => Ok(TerminologyType::SnomedFull),
+            "snomed-delta" => Ok(TerminologyType::SnomedDelta),
+            "loinc" => Ok(TerminologyType::Loinc),
+            "valuesets" => Ok(TerminologyType::ValueSets),
+            "amt" => Ok(TerminologyType::Amt),
+            _ => anyhow::bail!("Unknown terminology type: {}", s),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeedEntry {
     pub id: String,
@@ -247,21 +271,20 @@ impl NctsClient {
         &self,
         url: &str,
         destination: &std::path::Path,
-        app_handle: Option<tauri::AppHandle>,
+        progress: Option<&dyn ProgressReporter>,
     ) -> Result<()> {
-        use tauri::Emitter;
         use futures::StreamExt;
         use tokio::io::AsyncWriteExt;
 
         println!("Downloading from: {}", url);
 
         // Emit initial progress
-        if let Some(handle) = &app_handle {
-            let _ = handle.emit("sync-progress", serde_json::json!({
-                "phase": "Downloading",
-                "message": "Starting download...".to_string(),
-                "percentage": 0.0,
-            }));
+        if let Some(reporter) = progress {
+            reporter.sync_progress(&SyncProgress {
+                phase: "Downloading".to_string(),
+                message: "Starting download...".to_string(),
+                percentage: 0.0,
+            });
         }
 
         // Get access token
@@ -303,7 +326,7 @@ impl NctsClient {
             downloaded += chunk.len() as u64;
 
             // Emit progress update
-            if let Some(handle) = &app_handle {
+            if let Some(reporter) = progress {
                 let downloaded_mb = downloaded as f64 / 1_048_576.0;
 
                 let (message, percentage) = if let Some(total) = total_size {
@@ -321,11 +344,11 @@ impl NctsClient {
                     )
                 };
 
-                let _ = handle.emit("sync-progress", serde_json::json!({
-                    "phase": "Downloading",
-                    "message": message,
-                    "percentage": percentage,
-                }));
+                reporter.sync_progress(&SyncProgress {
+                    phase: "Downloading".to_string(),
+                    message,
+                    percentage,
+                });
             }
         }
 
@@ -333,12 +356,12 @@ impl NctsClient {
         file.flush().await.context("Failed to flush file")?;
 
         // Emit completion progress
-        if let Some(handle) = &app_handle {
-            let _ = handle.emit("sync-progress", serde_json::json!({
-                "phase": "Downloaded",
-                "message": "File saved successfully".to_string(),
-                "percentage": 100.0,
-            }));
+        if let Some(reporter) = progress {
+            reporter.sync_progress(&SyncProgress {
+                phase: "Downloaded".to_string(),
+                message: "File saved successfully".to_string(),
+                percentage: 100.0,
+            });
         }
 
         println!("Downloaded to: {:?} ({} bytes)", destination, downloaded);
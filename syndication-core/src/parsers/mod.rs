@@ -0,0 +1,14 @@
+pub mod snomed_rf2;
+pub mod amt_csv;
+pub mod valueset_r4;
+
+// Re-export commonly used items
+pub use snomed_rf2::{
+    LanguageRefsetMember, RefsetMember, Rf2Archive, SnomedConcept, SnomedDescription, SnomedRelationship, SnomedRf2Parser,
+};
+pub use amt_csv::{AmtCode, AmtCsvParser};
+pub use valueset_r4::{
+    BundleResource, CodeSystemConcept, CodeSystemEntry, ComposeFilter, ComposeRule, ConceptDesignation,
+    ConceptMapElement, ConceptMapEntry, ConceptMapGroup, ConceptMapTarget, ConceptMapUnmapped, ConceptProperty,
+    ValueSetCompose, ValueSetEntry, ValueSetR4Parser,
+};
@@ -0,0 +1,525 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::path::Path;
+
+/// Relationship typeId for the SNOMED CT "Is a" attribute (116680003 |Is a|)
+pub const IS_A_TYPE_ID: &str = "116680003";
+
+/// Description typeIds (900000000000003001 |Fully specified name|, 900000000000013009 |Synonym|)
+pub const FSN_TYPE_ID: &str = "900000000000003001";
+pub const SYNONYM_TYPE_ID: &str = "900000000000013009";
+
+/// 32570271000036106 |Australian English language reference set|
+pub const EN_AU_LANGUAGE_REFSET_ID: &str = "32570271000036106";
+
+/// Language refset acceptabilityIds (900000000000548007 |Preferred|, 900000000000549004 |Acceptable|)
+pub const PREFERRED_ACCEPTABILITY_ID: &str = "900000000000548007";
+pub const ACCEPTABLE_ACCEPTABILITY_ID: &str = "900000000000549004";
+
+/// 900000000000489007 |Concept inactivation indicator reference set| (valueId = inactivation reason)
+pub const CONCEPT_INACTIVATION_REFSET_ID: &str = "900000000000489007";
+
+/// Historical association refsets (targetComponentId = suggested concept), with the association name
+pub const HISTORICAL_ASSOCIATION_REFSETS: &[(&str, &str)] = &[
+    ("900000000000527005", "SAME AS"),
+    ("900000000000526001", "REPLACED BY"),
+    ("1186921001", "POSSIBLY REPLACED BY"),
+    ("900000000000523009", "POSSIBLY EQUIVALENT TO"),
+    ("900000000000524003", "MOVED TO"),
+];
+
+/// SNOMED CT Concept (from Concept_Snapshot file)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedConcept {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub definition_status_id: String,
+}
+
+/// SNOMED CT Description (from Description_Snapshot file)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedDescription {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub concept_id: String,
+    pub language_code: String,
+    pub type_id: String,
+    pub term: String,
+    pub case_significance_id: String,
+}
+
+/// SNOMED CT Relationship (from Relationship_Snapshot file)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedRelationship {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub source_id: String,
+    pub destination_id: String,
+    pub relationship_group: i32,
+    pub type_id: String,
+    pub characteristic_type_id: String,
+    pub modifier_id: String,
+}
+
+/// SNOMED CT language reference set member (from der2_cRefset_Language files)
+/// States whether a description is preferred or acceptable in one language/dialect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageRefsetMember {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub refset_id: String,
+    pub referenced_component_id: String,
+    pub acceptability_id: String,
+}
+
+/// SNOMED CT reference set member from any der2_*Refset_* file
+/// `attributes` holds the pattern-specific columns after referencedComponentId (none for simple refsets)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefsetMember {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub refset_id: String,
+    pub referenced_component_id: String,
+    pub attributes: Vec<String>,
+}
+
+pub struct SnomedRf2Parser;
+
+impl SnomedRf2Parser {
+    /// Parse SNOMED CT Concepts from a TSV reader
+    pub fn parse_concepts<R: BufRead, F>(reader: R, mut callback: F) -> Result<usize>
+    where
+        F: FnMut(SnomedConcept) -> Result<()>,
+    {
+        let mut lines = reader.lines();
+
+        // Skip header line
+        lines.next().context("No header line in concepts file")?
+            .context("Failed to read header line")?;
+
+        let mut count = 0;
+        for line in lines {
+            let line = line.context("Failed to read line")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 5 {
+                continue; // Skip malformed lines
+            }
+
+            let concept = SnomedConcept {
+                id: fields[0].to_string(),
+                effective_time: fields[1].to_string(),
+                active: fields[2] == "1",
+                module_id: fields[3].to_string(),
+                definition_status_id: fields[4].to_string(),
+            };
+
+            callback(concept)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Parse SNOMED CT Descriptions from a TSV reader
+    pub fn parse_descriptions<R: BufRead, F>(reader: R, mut callback: F) -> Result<usize>
+    where
+        F: FnMut(SnomedDescription) -> Result<()>,
+    {
+        let mut lines = reader.lines();
+
+        // Skip header line
+        lines.next().context("No header line in descriptions file")?
+            .context("Failed to read header line")?;
+
+        let mut count = 0;
+        for line in lines {
+            let line = line.context("Failed to read line")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 9 {
+                continue; // Skip malformed lines
+            }
+
+            let description = SnomedDescription {
+                id: fields[0].to_string(),
+                effective_time: fields[1].to_string(),
+                active: fields[2] == "1",
+                module_id: fields[3].to_string(),
+                concept_id: fields[4].to_string(),
+                language_code: fields[5].to_string(),
+                type_id: fields[6].to_string(),
+                term: fields[7].to_string(),
+                case_significance_id: fields[8].to_string(),
+            };
+
+            callback(description)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Parse SNOMED CT Relationships from a TSV reader
+    pub fn parse_relationships<R: BufRead, F>(reader: R, mut callback: F) -> Result<usize>
+    where
+        F: FnMut(SnomedRelationship) -> Result<()>,
+    {
+        let mut lines = reader.lines();
+
+        // Skip header line
+        lines.next().context("No header line in relationships file")?
+            .context("Failed to read header line")?;
+
+        let mut count = 0;
+        for line in lines {
+            let line = line.context("Failed to read line")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 10 {
+                continue; // Skip malformed lines
+            }
+
+            let relationship = SnomedRelationship {
+                id: fields[0].to_string(),
+                effective_time: fields[1].to_string(),
+                active: fields[2] == "1",
+                module_id: fields[3].to_string(),
+                source_id: fields[4].to_string(),
+                destination_id: fields[5].to_string(),
+                relationship_group: fields[6].parse().unwrap_or(0),
+                type_id: fields[7].to_string(),
+                characteristic_type_id: fields[8].to_string(),
+                modifier_id: fields[9].to_string(),
+            };
+
+            callback(relationship)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Parse SNOMED CT language reference set members from a TSV reader
+    pub fn parse_language_refset<R: BufRead, F>(reader: R, mut callback: F) -> Result<usize>
+    where
+        F: FnMut(LanguageRefsetMember) -> Result<()>,
+    {
+        let mut lines = reader.lines();
+
+        // Skip header line
+        lines.next().context("No header line in language refset file")?
+            .context("Failed to read header line")?;
+
+        let mut count = 0;
+        for line in lines {
+            let line = line.context("Failed to read line")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                continue; // Skip malformed lines
+            }
+
+            let member = LanguageRefsetMember {
+                id: fields[0].to_string(),
+                effective_time: fields[1].to_string(),
+                active: fields[2] == "1",
+                module_id: fields[3].to_string(),
+                refset_id: fields[4].to_string(),
+                referenced_component_id: fields[5].to_string(),
+                acceptability_id: fields[6].to_string(),
+            };
+
+            callback(member)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Parse SNOMED CT reference set members (simple, ordered, association, map, ...) from a TSV reader
+    pub fn parse_refset<R: BufRead, F>(reader: R, mut callback: F) -> Result<usize>
+    where
+        F: FnMut(RefsetMember) -> Result<()>,
+    {
+        let mut lines = reader.lines();
+
+        // Skip header line
+        lines.next().context("No header line in refset file")?
+            .context("Failed to read header line")?;
+
+        let mut count = 0;
+        for line in lines {
+            let line = line.context("Failed to read line")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 6 {
+                continue; // Skip malformed lines
+            }
+
+            let member = RefsetMember {
+                id: fields[0].to_string(),
+                effective_time: fields[1].to_string(),
+                active: fields[2] == "1",
+                module_id: fields[3].to_string(),
+                refset_id: fields[4].to_string(),
+                referenced_component_id: fields[5].to_string(),
+                attributes: fields[6..].iter().map(|f| f.to_string()).collect(),
+            };
+
+            callback(member)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Refset type named in an RF2 refset file name, e.g. "Simple" for
+    /// der2_Refset_SimpleSnapshot_AU1000036_20240131.txt when `release` is "Snapshot"
+    pub fn refset_file_kind(file_name: &str, release: &str) -> Option<String> {
+        let rest = file_name.strip_prefix("der2_")?;
+        let (_, rest) = rest.split_once("Refset_")?;
+        let kind = rest.split('_').next()?.strip_suffix(release)?;
+        (!kind.is_empty()).then(|| kind.to_string())
+    }
+}
+
+/// An RF2 release ZIP read in place: each file is decompressed as it is parsed, never extracted to disk
+pub struct Rf2Archive<R: Read + Seek = BufReader<File>> {
+    archive: zip::ZipArchive<R>,
+}
+
+impl Rf2Archive {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open ZIP file {:?}", path))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> Rf2Archive<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        let archive = zip::ZipArchive::new(reader).context("Failed to read ZIP archive")?;
+        Ok(Self { archive })
+    }
+
+    /// Every file whose name (without its folder) contains the pattern, sorted by path
+    pub fn find_files(&self, pattern: &str) -> Vec<String> {
+        let mut found: Vec<String> = self
+            .archive
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .filter(|name| name.rsplit('/').next().is_some_and(|file_name| file_name.contains(pattern)))
+            .map(str::to_string)
+            .collect();
+        found.sort();
+        found
+    }
+
+    /// The first file whose name contains the pattern
+    pub fn find_file(&self, pattern: &str) -> Result<String> {
+        self.find_files(pattern)
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("File matching '{}' not found", pattern))
+    }
+
+    /// The refset files of a release type other than language refsets, with the refset type each holds
+    pub fn find_refset_files(&self, release: &str) -> Vec<(String, String)> {
+        self.find_files("Refset_")
+            .into_iter()
+            .filter_map(|name| {
+                let file_name = name.rsplit('/').next()?;
+                let kind = SnomedRf2Parser::refset_file_kind(file_name, release)?;
+                (kind != "Language").then_some((name, kind))
+            })
+            .collect()
+    }
+
+    /// Stream a file out of the archive for one of the `SnomedRf2Parser` functions
+    pub fn reader(&mut self, name: &str) -> Result<BufReader<zip::read::ZipFile<'_>>> {
+        let file = self
+            .archive
+            .by_name(name)
+            .with_context(|| format!("Failed to read {} from ZIP archive", name))?;
+        Ok(BufReader::new(file))
+    }
+
+    /// Count the rows of a file (excluding header) by streaming through it
+    pub fn count_rows(&mut self, name: &str) -> Result<usize> {
+        let mut reader = self.reader(name)?;
+        let mut line = Vec::new();
+        let mut count: usize = 0;
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            count += 1;
+            line.clear();
+        }
+        Ok(count.saturating_sub(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_concept_line() {
+        // Test parsing a single concept
+        let test_data = "id\teffectiveTime\tactive\tmoduleId\tdefinitionStatusId\n\
+                         12345\t20230101\t1\t67890\t900000000000074008";
+
+        let reader = std::io::BufReader::new(test_data.as_bytes());
+        let mut count = 0;
+        let result = SnomedRf2Parser::parse_concepts(
+            reader,
+            |concept| {
+                assert_eq!(concept.id, "12345");
+                assert_eq!(concept.effective_time, "20230101");
+                assert!(concept.active);
+                count += 1;
+                Ok(())
+            }
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_parse_relationship_line() {
+        let test_data = "id\teffectiveTime\tactive\tmoduleId\tsourceId\tdestinationId\trelationshipGroup\ttypeId\tcharacteristicTypeId\tmodifierId\n\
+                         100022\t20230101\t1\t900000000000207008\t73211009\t126877002\t0\t116680003\t900000000000011006\t900000000000451002";
+
+        let reader = std::io::BufReader::new(test_data.as_bytes());
+        let mut relationships = Vec::new();
+        let count = SnomedRf2Parser::parse_relationships(reader, |relationship| {
+            relationships.push(relationship);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(relationships[0].source_id, "73211009");
+        assert_eq!(relationships[0].destination_id, "126877002");
+        assert_eq!(relationships[0].relationship_group, 0);
+        assert_eq!(relationships[0].type_id, IS_A_TYPE_ID);
+        assert!(relationships[0].active);
+    }
+
+    #[test]
+    fn test_parse_language_refset_line() {
+        let test_data = "id\teffectiveTime\tactive\tmoduleId\trefsetId\treferencedComponentId\tacceptabilityId\n\
+                         80000001-0000-0000-0000-000000000001\t20230101\t1\t32506021000036107\t32570271000036106\t1234567011\t900000000000548007";
+
+        let reader = std::io::BufReader::new(test_data.as_bytes());
+        let mut members = Vec::new();
+        let count = SnomedRf2Parser::parse_language_refset(reader, |member| {
+            members.push(member);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(members[0].refset_id, EN_AU_LANGUAGE_REFSET_ID);
+        assert_eq!(members[0].referenced_component_id, "1234567011");
+        assert_eq!(members[0].acceptability_id, PREFERRED_ACCEPTABILITY_ID);
+        assert!(members[0].active);
+    }
+
+    #[test]
+    fn test_parse_refset_attributes() {
+        let test_data = "id\teffectiveTime\tactive\tmoduleId\trefsetId\treferencedComponentId\torder\tlinkedTo\n\
+                         a1\t20230101\t1\t32506021000036107\t32570071000036102\t22298006\t1\t0\n\
+                         a2\t20230101\t0\t32506021000036107\t32570071000036102\t73211009";
+
+        let reader = std::io::BufReader::new(test_data.as_bytes());
+        let mut members = Vec::new();
+        let count = SnomedRf2Parser::parse_refset(reader, |member| {
+            members.push(member);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(members[0].attributes, vec!["1", "0"]);
+        assert!(members[1].attributes.is_empty());
+        assert!(!members[1].active);
+
+        assert_eq!(
+            SnomedRf2Parser::refset_file_kind("der2_Refset_SimpleSnapshot_AU1000036_20240131.txt", "Snapshot").as_deref(),
+            Some("Simple")
+        );
+        assert_eq!(
+            SnomedRf2Parser::refset_file_kind("der2_iisssciRefset_ComplexMapFull_AU1000036_20240131.txt", "Full").as_deref(),
+            Some("ComplexMap")
+        );
+        assert_eq!(SnomedRf2Parser::refset_file_kind("der2_Refset_SimpleDelta_AU1000036_20240131.txt", "Snapshot"), None);
+        assert_eq!(SnomedRf2Parser::refset_file_kind("sct2_Concept_Snapshot_AU1000036_20240131.txt", "Snapshot"), None);
+    }
+
+    #[test]
+    fn test_stream_files_from_archive() {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("SnomedCT_Release/Snapshot/Terminology/", options).unwrap();
+        zip.start_file("SnomedCT_Release/Snapshot/Terminology/sct2_Concept_Snapshot_AU1000036_20240131.txt", options)
+            .unwrap();
+        zip.write_all(b"id\teffectiveTime\tactive\tmoduleId\tdefinitionStatusId\n\
+                        12345\t20230101\t1\t67890\t900000000000074008\n\
+                        67890\t20230101\t0\t67890\t900000000000074008\n")
+            .unwrap();
+        zip.start_file("SnomedCT_Release/Snapshot/Refset/der2_Refset_SimpleSnapshot_AU1000036_20240131.txt", options)
+            .unwrap();
+        zip.start_file("SnomedCT_Release/Snapshot/Refset/der2_cRefset_LanguageSnapshot-en_AU1000036_20240131.txt", options)
+            .unwrap();
+        let mut archive = Rf2Archive::new(zip.finish().unwrap()).unwrap();
+
+        let concept_file = archive.find_file("sct2_Concept_Snapshot").unwrap();
+        assert!(archive.find_file("sct2_Concept_Full").is_err());
+        assert!(archive.find_files("Terminology").is_empty(), "Folder names are not matched");
+        assert_eq!(
+            archive.find_refset_files("Snapshot"),
+            [(
+                "SnomedCT_Release/Snapshot/Refset/der2_Refset_SimpleSnapshot_AU1000036_20240131.txt".to_string(),
+                "Simple".to_string()
+            )]
+        );
+
+        assert_eq!(archive.count_rows(&concept_file).unwrap(), 2);
+        let mut ids = Vec::new();
+        let count = SnomedRf2Parser::parse_concepts(archive.reader(&concept_file).unwrap(), |concept| {
+            ids.push(concept.id);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(ids, ["12345", "67890"]);
+    }
+}
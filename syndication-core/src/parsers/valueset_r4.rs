@@ -0,0 +1,905 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// FHIR R4 ValueSet entry (from Bundle)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSetEntry {
+    pub url: String,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    pub expansion: Option<Vec<ValueSetConcept>>,
+    /// Compose rules that can only be resolved against local content at expansion time
+    pub compose: Option<ValueSetCompose>,
+}
+
+/// Concept from ValueSet expansion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSetConcept {
+    pub system: String,
+    pub code: String,
+    pub display: Option<String>,
+}
+
+/// ValueSet compose definition (compose.include[] and compose.exclude[])
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValueSetCompose {
+    pub include: Vec<ComposeRule>,
+    pub exclude: Vec<ComposeRule>,
+}
+
+/// A single compose include/exclude rule
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComposeRule {
+    pub system: Option<String>,
+    pub version: Option<String>,
+    pub concepts: Vec<ValueSetConcept>,
+    pub filters: Vec<ComposeFilter>,
+    pub valuesets: Vec<String>,
+}
+
+/// A compose filter, e.g. concept is-a 73211009 or constraint = <ECL>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposeFilter {
+    pub property: String,
+    pub op: String,
+    pub value: String,
+}
+
+/// FHIR R4 ConceptMap entry (from Bundle)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapEntry {
+    pub url: String,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    /// Source and target ValueSets (sourceUri/sourceCanonical, targetUri/targetCanonical)
+    pub source: Option<String>,
+    pub target: Option<String>,
+    pub groups: Vec<ConceptMapGroup>,
+}
+
+/// A ConceptMap group: mappings from one source system to one target system
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConceptMapGroup {
+    pub source: Option<String>,
+    pub source_version: Option<String>,
+    pub target: Option<String>,
+    pub target_version: Option<String>,
+    pub elements: Vec<ConceptMapElement>,
+    pub unmapped: Option<ConceptMapUnmapped>,
+}
+
+/// A source code and its targets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapElement {
+    pub code: String,
+    pub display: Option<String>,
+    pub targets: Vec<ConceptMapTarget>,
+}
+
+/// A mapping target; `code` is None for unmatched or disjoint targets that name no code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapTarget {
+    pub code: Option<String>,
+    pub display: Option<String>,
+    /// R4 ConceptMapEquivalence code (equivalent, wider, narrower, inexact, unmatched, ...)
+    pub equivalence: String,
+    pub comment: Option<String>,
+}
+
+/// What to do with source codes that have no element in a group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapUnmapped {
+    /// provided (use the source code), fixed (use `code`) or other-map (translate with `url`)
+    pub mode: String,
+    pub code: Option<String>,
+    pub display: Option<String>,
+    pub url: Option<String>,
+}
+
+/// FHIR R4 CodeSystem entry (from Bundle), with its concept hierarchy flattened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeSystemEntry {
+    pub url: String,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    /// complete, example, fragment, not-present or supplement
+    pub content: Option<String>,
+    pub concepts: Vec<CodeSystemConcept>,
+}
+
+/// A CodeSystem concept; `parents` come from concept nesting and `parent` properties
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeSystemConcept {
+    pub code: String,
+    pub display: Option<String>,
+    pub definition: Option<String>,
+    pub parents: Vec<String>,
+    pub properties: Vec<ConceptProperty>,
+    pub designations: Vec<ConceptDesignation>,
+}
+
+/// A concept property value, kept as text with its FHIR value type (code, string, boolean, integer, ...)
+/// Coding values keep their code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConceptProperty {
+    pub code: String,
+    pub value: String,
+    pub value_type: String,
+}
+
+/// An additional term for a CodeSystem concept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptDesignation {
+    pub language: Option<String>,
+    pub use_code: Option<String>,
+    pub use_display: Option<String>,
+    pub value: String,
+}
+
+/// A terminology resource read from a FHIR Bundle
+#[derive(Debug, Clone)]
+pub enum BundleResource {
+    ValueSet(ValueSetEntry),
+    ConceptMap(ConceptMapEntry),
+    CodeSystem(CodeSystemEntry),
+}
+
+impl ValueSetCompose {
+    /// True when the compose cannot be expanded from its explicit concept lists alone
+    /// (filters, whole-system includes, nested ValueSet imports or excludes)
+    pub fn is_intensional(&self) -> bool {
+        !self.exclude.is_empty()
+            || self.include.iter().any(|rule| {
+                !rule.filters.is_empty()
+                    || !rule.valuesets.is_empty()
+                    || (rule.system.is_some() && rule.concepts.is_empty())
+            })
+    }
+
+    /// Concepts listed explicitly in compose.include[].concept[]
+    pub fn explicit_concepts(&self) -> Vec<ValueSetConcept> {
+        self.include
+            .iter()
+            .flat_map(|rule| rule.concepts.iter().cloned())
+            .collect()
+    }
+}
+
+pub struct ValueSetR4Parser;
+
+impl ValueSetR4Parser {
+    /// Parse FHIR R4 ValueSet Bundle from JSON file
+    /// The file should be a Bundle resource containing ValueSet, ConceptMap and CodeSystem entries
+    /// (a single resource of those types is accepted too); returns the number of resources read
+    pub fn parse_bundle<P: AsRef<Path>, F>(path: P, mut callback: F) -> Result<usize>
+    where
+        F: FnMut(BundleResource) -> Result<()>,
+    {
+        let content = std::fs::read_to_string(path.as_ref())
+            .context("Failed to read ValueSet bundle file")?;
+
+        let bundle: Value = serde_json::from_str(&content)
+            .context("Failed to parse JSON")?;
+
+        let mut count = 0;
+
+        // Check if this is a Bundle resource
+        if let Some(resource_type) = bundle.get("resourceType").and_then(|v| v.as_str()) {
+            if resource_type == "Bundle" {
+                // First pass: Build CodeSystem lookup for display names
+                let codesystem_lookup = Self::build_codesystem_lookup(&bundle);
+
+                // Second pass: Parse every resource, resolving ValueSet and ConceptMap displays with the lookup
+                if let Some(entries) = bundle.get("entry").and_then(|v| v.as_array()) {
+                    for entry in entries {
+                        if let Some(resource) = entry.get("resource") {
+                            if let Some(resource) = Self::parse_resource(resource, &codesystem_lookup) {
+                                callback(resource)?;
+                                count += 1;
+                            }
+                        }
+                    }
+                }
+            } else if let Some(resource) = Self::parse_resource(&bundle, &HashMap::new()) {
+                // Single resource (no other CodeSystems available)
+                callback(resource)?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Build a lookup table: (CodeSystem URL, code) -> display
+    fn build_codesystem_lookup(bundle: &Value) -> HashMap<(String, String), String> {
+        let mut lookup = HashMap::new();
+
+        if let Some(entries) = bundle.get("entry").and_then(|v| v.as_array()) {
+            for entry in entries {
+                if let Some(resource) = entry.get("resource") {
+                    if let Some("CodeSystem") = resource.get("resourceType").and_then(|v| v.as_str()) {
+                        if let Some(url) = resource.get("url").and_then(|v| v.as_str()) {
+                            if let Some(concepts) = resource.get("concept").and_then(|v| v.as_array()) {
+                                for concept in concepts {
+                                    if let (Some(code), Some(display)) = (
+                                        concept.get("code").and_then(|v| v.as_str()),
+                                        concept.get("display").and_then(|v| v.as_str()),
+                                    ) {
+                                        lookup.insert(
+                                            (url.to_string(), code.to_string()),
+                                            display.to_string(),
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        lookup
+    }
+
+    /// Parse a ValueSet, ConceptMap or CodeSystem resource; other resource types and invalid resources are skipped
+    fn parse_resource(
+        resource: &Value,
+        codesystem_lookup: &HashMap<(String, String), String>,
+    ) -> Option<BundleResource> {
+        match resource.get("resourceType").and_then(|v| v.as_str()) {
+            Some("ValueSet") => Self::parse_valueset(resource, codesystem_lookup)
+                .ok()
+                .map(BundleResource::ValueSet),
+            Some("ConceptMap") => Self::parse_concept_map(resource, codesystem_lookup)
+                .ok()
+                .map(BundleResource::ConceptMap),
+            Some("CodeSystem") => Self::parse_codesystem(resource).ok().map(BundleResource::CodeSystem),
+            _ => None,
+        }
+    }
+
+    /// Parse a single ValueSet resource
+    fn parse_valueset(
+        resource: &Value,
+        codesystem_lookup: &HashMap<(String, String), String>,
+    ) -> Result<ValueSetEntry> {
+        let url = resource
+            .get("url")
+            .and_then(|v| v.as_str())
+            .context("ValueSet missing required 'url' field")?
+            .to_string();
+
+        let version = resource
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let name = resource
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let title = resource
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let status = resource
+            .get("status")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let description = resource
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let publisher = resource
+            .get("publisher")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let compose = resource
+            .get("compose")
+            .map(|compose_obj| Self::parse_compose(compose_obj, codesystem_lookup));
+
+        // Parse expansion if present (pre-expanded ValueSets)
+        let (expansion, compose) = if let Some(expansion_obj) = resource.get("expansion") {
+            (Self::parse_expansion(expansion_obj), None)
+        } else if let Some(compose) = compose {
+            // Generate expansion from the explicit compose concepts, and keep the compose
+            // itself when it needs resolving against local content at expansion time
+            let concepts = compose.explicit_concepts();
+            let expansion = if concepts.is_empty() { None } else { Some(concepts) };
+            (expansion, Some(compose).filter(|c| c.is_intensional()))
+        } else {
+            (None, None)
+        };
+
+        Ok(ValueSetEntry {
+            url,
+            version,
+            name,
+            title,
+            status,
+            description,
+            publisher,
+            expansion,
+            compose,
+        })
+    }
+
+    /// Parse a single CodeSystem resource
+    fn parse_codesystem(resource: &Value) -> Result<CodeSystemEntry> {
+        let text = |key: &str| resource.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        let url = text("url").context("CodeSystem missing required 'url' field")?;
+        let mut concepts = Vec::new();
+        if let Some(concept_array) = resource.get("concept").and_then(|v| v.as_array()) {
+            Self::parse_codesystem_concepts(concept_array, None, &mut concepts);
+        }
+
+        Ok(CodeSystemEntry {
+            url,
+            version: text("version"),
+            name: text("name"),
+            title: text("title"),
+            status: text("status"),
+            description: text("description"),
+            publisher: text("publisher"),
+            content: text("content"),
+            concepts,
+        })
+    }
+
+    /// Flatten nested CodeSystem concepts, recording each nested concept's parent
+    fn parse_codesystem_concepts(
+        concept_array: &[Value],
+        parent: Option<&str>,
+        concepts: &mut Vec<CodeSystemConcept>,
+    ) {
+        let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        for concept in concept_array {
+            let Some(code) = text(concept, "code") else {
+                continue;
+            };
+
+            let properties: Vec<ConceptProperty> = concept
+                .get("property")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|property| {
+                    let code = text(property, "code")?;
+                    let (key, value) = property
+                        .as_object()?
+                        .iter()
+                        .find(|(key, _)| key.starts_with("value"))?;
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        Value::Object(coding) => coding.get("code")?.as_str()?.to_string(),
+                        other => other.to_string(),
+                    };
+                    let value_type = key.trim_start_matches("value");
+                    let mut chars = value_type.chars();
+                    let value_type = match chars.next() {
+                        Some(first) => first.to_lowercase().chain(chars).collect(),
+                        None => String::new(),
+                    };
+                    Some(ConceptProperty { code, value, value_type })
+                })
+                .collect();
+
+            let mut parents: Vec<String> = parent.map(|p| p.to_string()).into_iter().collect();
+            for property in properties.iter().filter(|p| p.code == "parent") {
+                if !parents.contains(&property.value) {
+                    parents.push(property.value.clone());
+                }
+            }
+
+            let designations = concept
+                .get("designation")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|designation| {
+                    let use_coding = designation.get("use");
+                    Some(ConceptDesignation {
+                        language: text(designation, "language"),
+                        use_code: use_coding.and_then(|u| text(u, "code")),
+                        use_display: use_coding.and_then(|u| text(u, "display")),
+                        value: text(designation, "value")?,
+                    })
+                })
+                .collect();
+
+            concepts.push(CodeSystemConcept {
+                code: code.clone(),
+                display: text(concept, "display"),
+                definition: text(concept, "definition"),
+                parents,
+                properties: properties.into_iter().filter(|p| p.code != "parent" && p.code != "child").collect(),
+                designations,
+            });
+
+            if let Some(children) = concept.get("concept").and_then(|v| v.as_array()) {
+                Self::parse_codesystem_concepts(children, Some(&code), concepts);
+            }
+        }
+    }
+
+    /// Parse a single ConceptMap resource
+    /// Missing displays are resolved from the CodeSystem lookup
+    fn parse_concept_map(
+        resource: &Value,
+        codesystem_lookup: &HashMap<(String, String), String>,
+    ) -> Result<ConceptMapEntry> {
+        let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        let url = text(resource, "url").context("ConceptMap missing required 'url' field")?;
+        let groups = resource
+            .get("group")
+            .and_then(|v| v.as_array())
+            .map(|groups| {
+                groups
+                    .iter()
+                    .map(|group| Self::parse_concept_map_group(group, codesystem_lookup))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ConceptMapEntry {
+            url,
+            version: text(resource, "version"),
+            name: text(resource, "name"),
+            title: text(resource, "title"),
+            status: text(resource, "status"),
+            description: text(resource, "description"),
+            publisher: text(resource, "publisher"),
+            source: text(resource, "sourceUri").or_else(|| text(resource, "sourceCanonical")),
+            target: text(resource, "targetUri").or_else(|| text(resource, "targetCanonical")),
+            groups,
+        })
+    }
+
+    /// Parse a ConceptMap group with its elements and unmapped rule
+    fn parse_concept_map_group(
+        group: &Value,
+        codesystem_lookup: &HashMap<(String, String), String>,
+    ) -> ConceptMapGroup {
+        let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+        let source = text(group, "source");
+        let target = text(group, "target");
+        let display = |system: &Option<String>, value: &Value, code: &Option<String>| {
+            text(value, "display").or_else(|| {
+                codesystem_lookup
+                    .get(&(system.clone()?, code.clone()?))
+                    .cloned()
+            })
+        };
+
+        let elements = group
+            .get("element")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|element| {
+                let code = text(element, "code");
+                let targets = element
+                    .get("target")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .map(|t| {
+                        let target_code = text(t, "code");
+                        ConceptMapTarget {
+                            display: display(&target, t, &target_code),
+                            code: target_code,
+                            // Required in R4; a missing value is read as the loosest mapping
+                            equivalence: text(t, "equivalence").unwrap_or_else(|| "inexact".to_string()),
+                            comment: text(t, "comment"),
+                        }
+                    })
+                    .collect();
+
+                Some(ConceptMapElement {
+                    display: display(&source, element, &code),
+                    code: code?,
+                    targets,
+                })
+            })
+            .collect();
+
+        let unmapped = group.get("unmapped").and_then(|unmapped| {
+            Some(ConceptMapUnmapped {
+                mode: text(unmapped, "mode")?,
+                code: text(unmapped, "code"),
+                display: text(unmapped, "display"),
+                url: text(unmapped, "url"),
+            })
+        });
+
+        ConceptMapGroup {
+            source_version: text(group, "sourceVersion"),
+            target_version: text(group, "targetVersion"),
+            source,
+            target,
+            elements,
+            unmapped,
+        }
+    }
+
+    /// Parse the expansion section of a ValueSet
+    fn parse_expansion(expansion: &Value) -> Option<Vec<ValueSetConcept>> {
+        let contains = expansion.get("contains")?.as_array()?;
+
+        let concepts: Vec<ValueSetConcept> = contains
+            .iter()
+            .filter_map(|item| {
+                let system = item.get("system")?.as_str()?.to_string();
+                let code = item.get("code")?.as_str()?.to_string();
+                let display = item
+                    .get("display")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+
+                Some(ValueSetConcept {
+                    system,
+                    code,
+                    display,
+                })
+            })
+            .collect();
+
+        if concepts.is_empty() {
+            None
+        } else {
+            Some(concepts)
+        }
+    }
+
+    /// Parse the compose section into include/exclude rules
+    /// Explicit concepts get their display names resolved from the CodeSystem lookup
+    fn parse_compose(
+        compose: &Value,
+        codesystem_lookup: &HashMap<(String, String), String>,
+    ) -> ValueSetCompose {
+        let parse_rules = |key: &str| -> Vec<ComposeRule> {
+            compose
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|rules| {
+                    rules
+                        .iter()
+                        .map(|rule| Self::parse_compose_rule(rule, codesystem_lookup))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        ValueSetCompose {
+            include: parse_rules("include"),
+            exclude: parse_rules("exclude"),
+        }
+    }
+
+    /// Parse a single compose.include[] or compose.exclude[] entry
+    fn parse_compose_rule(
+        rule: &Value,
+        codesystem_lookup: &HashMap<(String, String), String>,
+    ) -> ComposeRule {
+        let system = rule
+            .get("system")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let version = rule
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let mut concepts = Vec::new();
+        if let (Some(system), Some(concept_array)) =
+            (&system, rule.get("concept").and_then(|v| v.as_array()))
+        {
+            for concept_obj in concept_array {
+                if let Some(code) = concept_obj.get("code").and_then(|v| v.as_str()) {
+                    // Try to get display from concept object first
+                    let mut display = concept_obj
+                        .get("display")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+
+                    // If not present, look up from CodeSystem
+                    if display.is_none() {
+                        display = codesystem_lookup
+                            .get(&(system.to_string(), code.to_string()))
+                            .cloned();
+                    }
+
+                    concepts.push(ValueSetConcept {
+                        system: system.to_string(),
+                        code: code.to_string(),
+                        display,
+                    });
+                }
+            }
+        }
+
+        let filters = rule
+            .get("filter")
+            .and_then(|v| v.as_array())
+            .map(|filters| {
+                filters
+                    .iter()
+                    .filter_map(|filter| {
+                        Some(ComposeFilter {
+                            property: filter.get("property")?.as_str()?.to_string(),
+                            op: filter.get("op")?.as_str()?.to_string(),
+                            value: filter.get("value")?.as_str()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let valuesets = rule
+            .get("valueSet")
+            .and_then(|v| v.as_array())
+            .map(|urls| {
+                urls.iter()
+                    .filter_map(|url| url.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ComposeRule {
+            system,
+            version,
+            concepts,
+            filters,
+            valuesets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valueset_basic() {
+        let json = r#"
+        {
+            "resourceType": "ValueSet",
+            "url": "http://example.org/ValueSet/test",
+            "version": "1.0.0",
+            "name": "TestValueSet",
+            "title": "Test Value Set",
+            "status": "active",
+            "expansion": {
+                "contains": [
+                    {
+                        "system": "http://snomed.info/sct",
+                        "code": "12345",
+                        "display": "Test Concept"
+                    }
+                ]
+            }
+        }
+        "#;
+
+        let resource: Value = serde_json::from_str(json).unwrap();
+        let empty_lookup = HashMap::new();
+        let valueset = ValueSetR4Parser::parse_valueset(&resource, &empty_lookup).unwrap();
+
+        assert_eq!(valueset.url, "http://example.org/ValueSet/test");
+        assert_eq!(valueset.version, Some("1.0.0".to_string()));
+        assert_eq!(valueset.name, Some("TestValueSet".to_string()));
+        assert!(valueset.expansion.is_some());
+
+        let expansion = valueset.expansion.unwrap();
+        assert_eq!(expansion.len(), 1);
+        assert_eq!(expansion[0].code, "12345");
+    }
+
+    #[test]
+    fn test_parse_compose_filters_and_excludes() {
+        let json = r#"
+        {
+            "resourceType": "ValueSet",
+            "url": "http://example.org/ValueSet/diabetes",
+            "compose": {
+                "include": [
+                    {
+                        "system": "http://snomed.info/sct",
+                        "filter": [
+                            { "property": "concept", "op": "is-a", "value": "73211009" }
+                        ]
+                    },
+                    {
+                        "system": "http://snomed.info/sct",
+                        "concept": [ { "code": "22298006" } ]
+                    },
+                    { "valueSet": [ "http://example.org/ValueSet/other" ] }
+                ],
+                "exclude": [
+                    {
+                        "system": "http://snomed.info/sct",
+                        "concept": [ { "code": "46635009" } ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+        let resource: Value = serde_json::from_str(json).unwrap();
+        let mut lookup = HashMap::new();
+        lookup.insert(
+            ("http://snomed.info/sct".to_string(), "22298006".to_string()),
+            "Myocardial infarction".to_string(),
+        );
+        let valueset = ValueSetR4Parser::parse_valueset(&resource, &lookup).unwrap();
+
+        // Explicit concepts are still stored as the extensional part
+        let expansion = valueset.expansion.unwrap();
+        assert_eq!(expansion.len(), 1);
+        assert_eq!(expansion[0].display.as_deref(), Some("Myocardial infarction"));
+
+        let compose = valueset.compose.expect("Filter-based compose should be kept");
+        assert_eq!(compose.include.len(), 3);
+        assert_eq!(compose.include[0].filters[0].op, "is-a");
+        assert_eq!(compose.include[0].filters[0].value, "73211009");
+        assert_eq!(compose.include[2].valuesets, vec!["http://example.org/ValueSet/other"]);
+        assert_eq!(compose.exclude[0].concepts[0].code, "46635009");
+    }
+
+    #[test]
+    fn test_parse_compose_explicit_only_is_not_kept() {
+        let json = r#"
+        {
+            "resourceType": "ValueSet",
+            "url": "http://example.org/ValueSet/explicit",
+            "compose": {
+                "include": [
+                    {
+                        "system": "http://snomed.info/sct",
+                        "concept": [ { "code": "12345", "display": "Test Concept" } ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+        let resource: Value = serde_json::from_str(json).unwrap();
+        let valueset = ValueSetR4Parser::parse_valueset(&resource, &HashMap::new()).unwrap();
+
+        assert_eq!(valueset.expansion.unwrap().len(), 1);
+        assert!(valueset.compose.is_none());
+    }
+
+    #[test]
+    fn test_parse_bundle_concept_map() {
+        let json = r#"
+        {
+            "resourceType": "Bundle",
+            "entry": [
+                {
+                    "resource": {
+                        "resourceType": "CodeSystem",
+                        "url": "http://example.org/CodeSystem/path",
+                        "concept": [ { "code": "GLU", "display": "Glucose" } ]
+                    }
+                },
+                {
+                    "resource": {
+                        "resourceType": "ConceptMap",
+                        "url": "http://example.org/ConceptMap/path-to-sct",
+                        "sourceCanonical": "http://example.org/ValueSet/path",
+                        "group": [
+                            {
+                                "source": "http://example.org/CodeSystem/path",
+                                "target": "http://snomed.info/sct",
+                                "element": [
+                                    {
+                                        "code": "GLU",
+                                        "target": [ { "code": "33747003", "equivalence": "equivalent" } ]
+                                    },
+                                    { "display": "No code" }
+                                ],
+                                "unmapped": { "mode": "fixed", "code": "261665006", "display": "Unknown" }
+                            }
+                        ]
+                    }
+                }
+            ]
+        }
+        "#;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.json");
+        std::fs::write(&path, json).unwrap();
+
+        let mut resources = Vec::new();
+        let count = ValueSetR4Parser::parse_bundle(&path, |resource| {
+            resources.push(resource);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+        assert!(matches!(resources[0], BundleResource::CodeSystem(_)));
+
+        let BundleResource::ConceptMap(map) = &resources[1] else {
+            panic!("Expected a ConceptMap");
+        };
+        assert_eq!(map.source.as_deref(), Some("http://example.org/ValueSet/path"));
+        let group = &map.groups[0];
+        assert_eq!(group.elements.len(), 1, "Elements without a code are skipped");
+        assert_eq!(group.elements[0].display.as_deref(), Some("Glucose"));
+        assert_eq!(group.elements[0].targets[0].code.as_deref(), Some("33747003"));
+        assert_eq!(group.elements[0].targets[0].equivalence, "equivalent");
+        assert_eq!(group.unmapped.as_ref().unwrap().mode, "fixed");
+    }
+
+    #[test]
+    fn test_parse_codesystem_hierarchy() {
+        let json = r#"
+        {
+            "resourceType": "CodeSystem",
+            "url": "http://example.org/CodeSystem/setting",
+            "version": "1.0.0",
+            "content": "complete",
+            "concept": [
+                {
+                    "code": "hospital",
+                    "display": "Hospital",
+                    "concept": [
+                        {
+                            "code": "ed",
+                            "display": "Emergency department",
+                            "property": [
+                                { "code": "parent", "valueCode": "acute" },
+                                { "code": "beds", "valueInteger": 40 },
+                                { "code": "notSelectable", "valueBoolean": true }
+                            ],
+                            "designation": [
+                                { "language": "en-AU", "use": { "code": "900000000000013009" }, "value": "ED" }
+                            ]
+                        }
+                    ]
+                },
+                { "code": "acute", "display": "Acute care" }
+            ]
+        }
+        "#;
+
+        let resource: Value = serde_json::from_str(json).unwrap();
+        let codesystem = ValueSetR4Parser::parse_codesystem(&resource).unwrap();
+
+        assert_eq!(codesystem.version.as_deref(), Some("1.0.0"));
+        assert_eq!(codesystem.concepts.len(), 3);
+
+        let ed = codesystem.concepts.iter().find(|c| c.code == "ed").unwrap();
+        assert_eq!(ed.parents, ["hospital", "acute"], "Nesting and parent properties both give parents");
+        let kinds: Vec<_> = ed.properties.iter().map(|p| (p.code.as_str(), p.value_type.as_str())).collect();
+        assert_eq!(kinds, [("beds", "integer"), ("notSelectable", "boolean")]);
+        assert_eq!(ed.designations[0].value, "ED");
+        assert_eq!(ed.designations[0].use_code.as_deref(), Some("900000000000013009"));
+    }
+}
@@ -0,0 +1,23 @@
+use directories::ProjectDirs;
+use std::path::PathBuf;
+
+/// On-disk locations shared by the desktop app and the headless CLI
+pub struct DataPaths {
+    pub db_path: PathBuf,
+    pub terminology_data_dir: PathBuf,
+    pub index_dir: PathBuf,
+}
+
+impl DataPaths {
+    /// Resolve the platform data directory for com.ncts.syndication
+    pub fn from_project_dirs() -> Option<Self> {
+        let project_dirs = ProjectDirs::from("com", "ncts", "syndication")?;
+        let data_dir = project_dirs.data_dir();
+
+        Some(Self {
+            db_path: data_dir.join("syndication.redb"),
+            terminology_data_dir: data_dir.join("terminology"),
+            index_dir: data_dir.join("indexes"),
+        })
+    }
+}
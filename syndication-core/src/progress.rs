@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Progress of a terminology import phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub phase: String,
+    pub phase_status: String, // "pending", "in_progress", "completed"
+    pub current: usize,
+    pub total: Option<usize>,
+    pub percentage: f32,
+    pub message: String,
+}
+
+/// Progress of a download or other long-running sync step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncProgress {
+    pub phase: String,
+    pub message: String,
+    pub percentage: f32,
+}
+
+/// Receives progress updates from imports and downloads
+/// The desktop app forwards these as Tauri events; embedders can log them or ignore them
+pub trait ProgressReporter: Send + Sync {
+    fn import_progress(&self, _progress: &ImportProgress) {}
+
+    fn sync_progress(&self, _progress: &SyncProgress) {}
+}
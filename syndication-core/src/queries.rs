@@ -0,0 +1,1302 @@
+use crate::ecl::{EclEvaluator, EclParser};
+use crate::parsers::snomed_rf2::{
+    ACCEPTABLE_ACCEPTABILITY_ID, CONCEPT_INACTIVATION_REFSET_ID, EN_AU_LANGUAGE_REFSET_ID, FSN_TYPE_ID,
+    HISTORICAL_ASSOCIATION_REFSETS, PREFERRED_ACCEPTABILITY_ID,
+};
+use crate::parsers::{ComposeFilter, ComposeRule, ConceptProperty, ValueSetCompose};
+use crate::search::{SearchResult, TerminologySearch};
+use crate::storage::TerminologyStorage;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Code lookup result with synonyms
+/// For SNOMED, display is the en-AU preferred term (the FSN if the language refset was not imported)
+/// For other CodeSystems, display is the concept's display and designations are its additional terms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeLookupResult {
+    pub code: String,
+    pub system: String,
+    pub display: String,
+    pub active: bool,
+    /// Every active term, preferred terms first
+    pub synonyms: Vec<String>,
+    pub designations: Vec<Designation>,
+    /// Why an inactive SNOMED concept was retired and what to use instead (None while active)
+    #[serde(default)]
+    pub inactivation: Option<Inactivation>,
+    /// CodeSystem concept properties, including `parent` and `child` codes (empty for SNOMED and AMT)
+    #[serde(default)]
+    pub properties: Vec<ConceptProperty>,
+}
+
+/// Inactivation reason and historical associations of an inactive SNOMED concept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inactivation {
+    /// Value of the concept inactivation indicator refset, e.g. 900000000000482003 |Duplicate component|
+    pub reason_code: Option<String>,
+    pub reason: Option<String>,
+    /// Suggested replacements, strongest association first (SAME AS, REPLACED BY, ...)
+    pub associations: Vec<HistoricalAssociation>,
+}
+
+/// Target of a historical association refset row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoricalAssociation {
+    /// Association name, e.g. "REPLACED BY"
+    pub association: String,
+    pub refset_id: String,
+    pub code: String,
+    pub display: Option<String>,
+}
+
+/// Description type of a designation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DesignationKind {
+    Fsn,
+    Synonym,
+}
+
+/// Acceptability of a description in the en-AU language reference set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Acceptability {
+    Preferred,
+    Acceptable,
+}
+
+/// A term of a code labelled with its type and en-AU acceptability (None if not in the refset)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Designation {
+    pub value: String,
+    pub kind: DesignationKind,
+    pub acceptability: Option<Acceptability>,
+    /// Language of the term, e.g. en-AU for SNOMED terms in the en-AU language refset
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// Direct is-a neighbours of a SNOMED concept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedHierarchy {
+    pub code: String,
+    pub parents: Vec<String>,
+    pub children: Vec<String>,
+}
+
+/// Outcome of a subsumption test, using the FHIR CodeSystem/$subsumes outcome codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubsumptionOutcome {
+    Equivalent,
+    Subsumes,
+    SubsumedBy,
+    NotSubsumed,
+}
+
+/// Subsumption test result for a pair of SNOMED concepts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsumptionResult {
+    pub code_a: String,
+    pub code_b: String,
+    pub outcome: SubsumptionOutcome,
+}
+
+/// Result of evaluating an ECL expression: the full match count plus one page of concepts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EclResult {
+    pub expression: String,
+    pub total: usize,
+    pub concepts: Vec<ValueSetConceptResult>,
+}
+
+/// An imported SNOMED reference set, named by its refset concept's preferred term
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefsetSummary {
+    pub refset_id: String,
+    pub display: Option<String>,
+    pub kind: String,
+    pub member_count: u64,
+}
+
+/// One page of a reference set's active members, ordered by code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefsetMembers {
+    pub refset_id: String,
+    pub total: usize,
+    pub offset: usize,
+    pub members: Vec<ValueSetConceptResult>,
+}
+
+/// Map rows found for a code in the complex and extended map refsets
+/// From SNOMED CT-AU, `matches` hold the map targets; from any other system, the SNOMED concepts mapped to the code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Translation {
+    pub system: String,
+    pub code: String,
+    pub matches: Vec<TranslationMatch>,
+}
+
+/// One map row: the code on the other side with its map group, priority, rule, advice and correlation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationMatch {
+    pub refset_id: String,
+    pub refset_display: Option<String>,
+    /// None when the source concept cannot be mapped (the row explains why in its advice)
+    pub code: Option<String>,
+    /// Preferred term when `code` is a SNOMED concept
+    pub display: Option<String>,
+    pub map_group: u32,
+    pub map_priority: u32,
+    pub map_rule: Option<String>,
+    pub map_advice: Option<String>,
+    pub correlation_id: Option<String>,
+    pub correlation: Option<String>,
+    pub map_category_id: Option<String>,
+    pub map_category: Option<String>,
+}
+
+/// Result of translating a code with the imported FHIR ConceptMaps ($translate semantics)
+/// `result` is true when at least one match is neither unmatched nor disjoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapTranslation {
+    pub result: bool,
+    pub message: Option<String>,
+    pub matches: Vec<ConceptMapMatch>,
+}
+
+/// A translated code with its R4 equivalence and the URL of the ConceptMap that produced it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapMatch {
+    pub equivalence: String,
+    pub system: Option<String>,
+    pub code: Option<String>,
+    pub display: Option<String>,
+    pub source: String,
+    pub comment: Option<String>,
+}
+
+/// ValueSet expansion result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSetExpansion {
+    pub url: String,
+    pub version: Option<String>,
+    pub title: Option<String>,
+    pub total: usize,
+    pub concepts: Vec<ValueSetConceptResult>,
+}
+
+/// Concept in ValueSet expansion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSetConceptResult {
+    pub system: String,
+    pub code: String,
+    pub display: Option<String>,
+}
+
+/// Code validation result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationResult {
+    pub valid: bool,
+    pub message: Option<String>,
+}
+
+/// Simplified list result for browse operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSetListItem {
+    pub url: String,
+    pub title: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+pub const SNOMED_SYSTEM: &str = "http://snomed.info/sct";
+pub const AMT_SYSTEM: &str = "http://hl7.org/fhir/sid/ncts-amt";
+
+pub struct TerminologyQueries;
+
+impl TerminologyQueries {
+    /// Look up a SNOMED concept by code with all its synonyms
+    pub fn lookup_snomed_code(
+        storage: &TerminologyStorage,
+        code: &str,
+    ) -> Result<Option<CodeLookupResult>> {
+        // Get the concept
+        let concept = storage.get_snomed_concept(code)?;
+
+        if let Some(concept) = concept {
+            // Get all descriptions for synonyms
+            let descriptions = storage.get_snomed_descriptions(code)?;
+            Ok(Some(Self::snomed_lookup_result(storage, code, concept.active, &descriptions)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Look up a SNOMED concept as it stood on `as_of` (YYYY-MM-DD or YYYYMMDD)
+    /// Needs the RF2 Full history; a concept that did not exist yet on that date is not found
+    /// Acceptability and inactivation details come from the current refset rows, which have no history
+    pub fn lookup_snomed_code_as_of(
+        storage: &TerminologyStorage,
+        code: &str,
+        as_of: &str,
+    ) -> Result<Option<CodeLookupResult>> {
+        let as_of = Self::require_snomed_history(storage, as_of)?;
+
+        match storage.get_snomed_concept_as_of(code, &as_of)? {
+            Some(concept) => {
+                let descriptions = storage.get_snomed_descriptions_as_of(code, &as_of)?;
+                Ok(Some(Self::snomed_lookup_result(storage, code, concept.active, &descriptions)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn snomed_lookup_result(
+        storage: &TerminologyStorage,
+        code: &str,
+        active: bool,
+        descriptions: &[crate::storage::SnomedDescription],
+    ) -> Result<CodeLookupResult> {
+        let acceptability = storage.get_snomed_acceptability(descriptions, EN_AU_LANGUAGE_REFSET_ID)?;
+
+        // Active terms ordered FSN first, then preferred, acceptable and unlisted synonyms
+        let mut designations: Vec<Designation> = descriptions
+            .iter()
+            .filter(|d| d.active)
+            .map(|d| {
+                let acceptability = match acceptability.get(&d.id).map(String::as_str) {
+                    Some(PREFERRED_ACCEPTABILITY_ID) => Some(Acceptability::Preferred),
+                    Some(ACCEPTABLE_ACCEPTABILITY_ID) => Some(Acceptability::Acceptable),
+                    _ => None,
+                };
+                Designation {
+                    value: d.term.clone(),
+                    kind: if d.type_id == FSN_TYPE_ID { DesignationKind::Fsn } else { DesignationKind::Synonym },
+                    acceptability,
+                    language: acceptability.map(|_| "en-AU".to_string()),
+                }
+            })
+            .collect();
+        designations.sort_by_key(|d| (d.kind, d.acceptability.is_none(), d.acceptability));
+
+        // Display the en-AU preferred term, falling back to the FSN
+        let display = designations
+            .iter()
+            .find(|d| d.kind == DesignationKind::Synonym && d.acceptability == Some(Acceptability::Preferred))
+            .or_else(|| designations.iter().find(|d| d.kind == DesignationKind::Fsn))
+            .map(|d| d.value.clone())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let mut synonyms: Vec<String> = designations
+            .iter()
+            .filter(|d| d.kind == DesignationKind::Synonym)
+            .map(|d| d.value.clone())
+            .collect();
+        synonyms.extend(designations.iter().filter(|d| d.kind == DesignationKind::Fsn).map(|d| d.value.clone()));
+
+        Ok(CodeLookupResult {
+            code: code.to_string(),
+            system: SNOMED_SYSTEM.to_string(),
+            display,
+            active,
+            synonyms,
+            designations,
+            inactivation: if active { None } else { Self::snomed_inactivation(storage, code)? },
+            properties: Vec::new(),
+        })
+    }
+
+    /// Read the inactivation indicator and historical associations of an inactive concept
+    /// None if the release has no rows for it (e.g. the refsets were not imported)
+    fn snomed_inactivation(storage: &TerminologyStorage, code: &str) -> Result<Option<Inactivation>> {
+        let reason_code = storage
+            .get_refset_rows(CONCEPT_INACTIVATION_REFSET_ID, code)?
+            .into_iter()
+            .find_map(|row| row.attributes.into_iter().next());
+
+        let mut associations = Vec::new();
+        for (refset_id, name) in HISTORICAL_ASSOCIATION_REFSETS {
+            for row in storage.get_refset_rows(refset_id, code)? {
+                if let Some(target) = row.attributes.into_iter().next() {
+                    associations.push(HistoricalAssociation {
+                        association: name.to_string(),
+                        refset_id: refset_id.to_string(),
+                        code: target,
+                        display: None,
+                    });
+                }
+            }
+        }
+
+        if reason_code.is_none() && associations.is_empty() {
+            return Ok(None);
+        }
+
+        let codes: Vec<String> = reason_code
+            .iter()
+            .cloned()
+            .chain(associations.iter().map(|a| a.code.clone()))
+            .collect();
+        let displays = Self::snomed_display_names(storage, &codes)?;
+
+        for association in &mut associations {
+            association.display = displays.get(&association.code).cloned();
+        }
+
+        Ok(Some(Inactivation {
+            reason: reason_code.as_ref().and_then(|c| displays.get(c).cloned()),
+            reason_code,
+            associations,
+        }))
+    }
+
+    /// Normalise a point-in-time date (YYYY-MM-DD or YYYYMMDD) to the RF2 effectiveTime format
+    pub fn parse_as_of(as_of: &str) -> Result<String> {
+        let as_of = as_of.trim();
+        let date = chrono::NaiveDate::parse_from_str(as_of, "%Y-%m-%d")
+            .or_else(|_| chrono::NaiveDate::parse_from_str(as_of, "%Y%m%d"))
+            .map_err(|_| anyhow::anyhow!("Invalid date '{}': expected YYYY-MM-DD or YYYYMMDD", as_of))?;
+        Ok(date.format("%Y%m%d").to_string())
+    }
+
+    fn require_snomed_history(storage: &TerminologyStorage, as_of: &str) -> Result<String> {
+        let as_of = Self::parse_as_of(as_of)?;
+        if !storage.has_snomed_full_release()? {
+            anyhow::bail!("Point-in-time queries need an imported SNOMED CT-AU RF2 Full release (snomed-full)");
+        }
+        Ok(as_of)
+    }
+
+    /// Get the direct supertypes and subtypes of a SNOMED concept
+    pub fn snomed_hierarchy(
+        storage: &TerminologyStorage,
+        code: &str,
+    ) -> Result<Option<SnomedHierarchy>> {
+        if storage.get_snomed_concept(code)?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(SnomedHierarchy {
+            code: code.to_string(),
+            parents: storage.get_snomed_parents(code)?,
+            children: storage.get_snomed_children(code)?,
+        }))
+    }
+
+    /// Test whether SNOMED concept `code_a` subsumes `code_b` (or vice versa)
+    /// Answered from the precomputed transitive closure, so this is two key lookups
+    pub fn subsumes(
+        storage: &TerminologyStorage,
+        code_a: &str,
+        code_b: &str,
+    ) -> Result<SubsumptionResult> {
+        for code in [code_a, code_b] {
+            if storage.get_snomed_concept(code)?.is_none() {
+                anyhow::bail!("Unknown SNOMED concept: {}", code);
+            }
+        }
+
+        let outcome = if code_a == code_b {
+            SubsumptionOutcome::Equivalent
+        } else if storage.snomed_is_ancestor(code_a, code_b)? {
+            SubsumptionOutcome::Subsumes
+        } else if storage.snomed_is_ancestor(code_b, code_a)? {
+            SubsumptionOutcome::SubsumedBy
+        } else {
+            SubsumptionOutcome::NotSubsumed
+        };
+
+        Ok(SubsumptionResult {
+            code_a: code_a.to_string(),
+            code_b: code_b.to_string(),
+            outcome,
+        })
+    }
+
+    /// List the imported SNOMED reference sets, ordered by name
+    pub fn list_refsets(storage: &TerminologyStorage) -> Result<Vec<RefsetSummary>> {
+        let refsets = storage.get_snomed_refsets()?;
+        let ids: Vec<String> = refsets.iter().map(|r| r.refset_id.clone()).collect();
+        let displays = Self::snomed_display_names(storage, &ids)?;
+
+        let mut summaries: Vec<RefsetSummary> = refsets
+            .into_iter()
+            .map(|refset| RefsetSummary {
+                display: displays.get(&refset.refset_id).cloned(),
+                refset_id: refset.refset_id,
+                kind: refset.kind,
+                member_count: refset.member_count,
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.display.cmp(&b.display).then_with(|| a.refset_id.cmp(&b.refset_id)));
+
+        Ok(summaries)
+    }
+
+    /// Page through a reference set's active members; None if the refset has not been imported
+    pub fn refset_members(
+        storage: &TerminologyStorage,
+        refset_id: &str,
+        offset: usize,
+        count: usize,
+    ) -> Result<Option<RefsetMembers>> {
+        if storage.get_snomed_refset(refset_id)?.is_none() {
+            return Ok(None);
+        }
+
+        let codes = Self::sorted_sctids(storage.get_refset_member_ids(refset_id)?);
+        let total = codes.len();
+        let page: Vec<String> = codes.into_iter().skip(offset).take(count).collect();
+
+        let displays = Self::snomed_display_names(storage, &page)?;
+        let members = page
+            .into_iter()
+            .map(|code| ValueSetConceptResult {
+                system: SNOMED_SYSTEM.to_string(),
+                display: displays.get(&code).cloned(),
+                code,
+            })
+            .collect();
+
+        Ok(Some(RefsetMembers {
+            refset_id: refset_id.to_string(),
+            total,
+            offset,
+            members,
+        }))
+    }
+
+    /// Test whether a code is an active member of a reference set
+    pub fn is_refset_member(storage: &TerminologyStorage, refset_id: &str, code: &str) -> Result<bool> {
+        if storage.get_snomed_refset(refset_id)?.is_none() {
+            anyhow::bail!("Reference set {} has not been imported", refset_id);
+        }
+        Ok(storage.is_refset_member(refset_id, code)?)
+    }
+
+    /// Translate a code through the complex and extended map refsets, optionally limited to one refset
+    /// A SNOMED CT-AU code (system `snomed` or its URI) is mapped forward to its targets; a code from
+    /// any other system is looked up as a map target and mapped back to SNOMED CT-AU
+    pub fn translate_code(
+        storage: &TerminologyStorage,
+        system: &str,
+        code: &str,
+        refset_id: Option<&str>,
+    ) -> Result<Translation> {
+        let forward = system == SNOMED_SYSTEM || system.eq_ignore_ascii_case("snomed");
+        let entries = if forward {
+            storage.get_snomed_map_entries(code)?
+        } else {
+            storage.get_snomed_map_entries_by_target(code)?
+        };
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|e| refset_id.is_none_or(|id| e.refset_id == id))
+            .collect();
+
+        // Refsets, correlations, categories and (in reverse) the matched concepts are all SNOMED concepts
+        let mut concepts: Vec<String> = Vec::new();
+        for entry in &entries {
+            concepts.push(entry.refset_id.clone());
+            concepts.extend(entry.correlation_id.iter().cloned());
+            concepts.extend(entry.map_category_id.iter().cloned());
+            if !forward {
+                concepts.push(entry.source_code.clone());
+            }
+        }
+        concepts.sort();
+        concepts.dedup();
+        let displays = Self::snomed_display_names(storage, &concepts)?;
+        let display = |id: &Option<String>| id.as_ref().and_then(|id| displays.get(id).cloned());
+
+        let matches = entries
+            .into_iter()
+            .map(|entry| {
+                let code = if forward { entry.map_target } else { Some(entry.source_code) };
+                TranslationMatch {
+                    refset_display: displays.get(&entry.refset_id).cloned(),
+                    display: if forward { None } else { display(&code) },
+                    correlation: display(&entry.correlation_id),
+                    map_category: display(&entry.map_category_id),
+                    refset_id: entry.refset_id,
+                    code,
+                    map_group: entry.map_group,
+                    map_priority: entry.map_priority,
+                    map_rule: entry.map_rule,
+                    map_advice: entry.map_advice,
+                    correlation_id: entry.correlation_id,
+                    map_category_id: entry.map_category_id,
+                }
+            })
+            .collect();
+
+        Ok(Translation {
+            system: system.to_string(),
+            code: code.to_string(),
+            matches,
+        })
+    }
+
+    /// Evaluate a SNOMED ECL expression, returning up to `limit` concepts ordered by code
+    pub fn evaluate_ecl(
+        storage: &TerminologyStorage,
+        expression: &str,
+        limit: usize,
+    ) -> Result<EclResult> {
+        let parsed = EclParser::parse(expression)?;
+        let matches = EclEvaluator::new(storage).evaluate(&parsed)?;
+
+        let mut codes = Self::sorted_sctids(matches);
+        let total = codes.len();
+        codes.truncate(limit);
+
+        let displays = Self::snomed_display_names(storage, &codes)?;
+        let concepts = codes
+            .into_iter()
+            .map(|code| ValueSetConceptResult {
+                system: SNOMED_SYSTEM.to_string(),
+                display: displays.get(&code).cloned(),
+                code,
+            })
+            .collect();
+
+        Ok(EclResult {
+            expression: expression.to_string(),
+            total,
+            concepts,
+        })
+    }
+
+    /// Get the display of each of the given SNOMED concepts: its en-AU preferred term, else its active FSN
+    fn snomed_display_names(
+        storage: &TerminologyStorage,
+        codes: &[String],
+    ) -> Result<HashMap<String, String>> {
+        if codes.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut displays = storage.get_snomed_preferred_terms(codes.iter().map(String::as_str))?;
+
+        let missing: HashSet<String> = codes.iter().filter(|c| !displays.contains_key(*c)).cloned().collect();
+        if !missing.is_empty() {
+            displays.extend(
+                storage
+                    .get_snomed_descriptions_for(&missing)?
+                    .into_iter()
+                    .filter(|d| d.type_id == FSN_TYPE_ID && d.active)
+                    .map(|d| (d.concept_id, d.term)),
+            );
+        }
+
+        Ok(displays)
+    }
+
+    /// SCTIDs are numeric, so order by length first to sort them numerically
+    fn sorted_sctids(codes: HashSet<String>) -> Vec<String> {
+        let mut codes: Vec<String> = codes.into_iter().collect();
+        codes.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        codes
+    }
+
+    /// Resolve a ValueSet compose: union of the includes, minus the union of the excludes
+    /// `stack` holds the ValueSet URLs being resolved, to detect import cycles
+    fn evaluate_compose(
+        storage: &TerminologyStorage,
+        compose: &ValueSetCompose,
+        stack: &mut Vec<String>,
+    ) -> Result<Vec<ValueSetConceptResult>> {
+        let mut seen = HashSet::new();
+        let mut concepts = Vec::new();
+        for rule in &compose.include {
+            for concept in Self::evaluate_compose_rule(storage, rule, stack)? {
+                if seen.insert((concept.system.clone(), concept.code.clone())) {
+                    concepts.push(concept);
+                }
+            }
+        }
+
+        let mut excluded = HashSet::new();
+        for rule in &compose.exclude {
+            for concept in Self::evaluate_compose_rule(storage, rule, stack)? {
+                excluded.insert((concept.system, concept.code));
+            }
+        }
+
+        concepts.retain(|c| !excluded.contains(&(c.system.clone(), c.code.clone())));
+        Ok(concepts)
+    }
+
+    /// Resolve one include/exclude rule: the system part (explicit concepts, filters or the
+    /// whole system) intersected with every imported ValueSet
+    fn evaluate_compose_rule(
+        storage: &TerminologyStorage,
+        rule: &ComposeRule,
+        stack: &mut Vec<String>,
+    ) -> Result<Vec<ValueSetConceptResult>> {
+        let mut result: Option<Vec<ValueSetConceptResult>> = match &rule.system {
+            Some(_) if !rule.concepts.is_empty() => Some(
+                rule.concepts
+                    .iter()
+                    .map(|c| ValueSetConceptResult {
+                        system: c.system.clone(),
+                        code: c.code.clone(),
+                        display: c.display.clone(),
+                    })
+                    .collect(),
+            ),
+            Some(system) => Some(
+                Self::system_codes(storage, system, &rule.filters)?
+                    .into_iter()
+                    .map(|code| ValueSetConceptResult {
+                        system: system.clone(),
+                        code,
+                        display: None,
+                    })
+                    .collect(),
+            ),
+            None => None,
+        };
+
+        for url in &rule.valuesets {
+            let imported = Self::resolve_imported_valueset(storage, url, stack)?;
+            result = Some(match result {
+                None => imported,
+                Some(current) => {
+                    let keys: HashSet<(String, String)> =
+                        imported.into_iter().map(|c| (c.system, c.code)).collect();
+                    current
+                        .into_iter()
+                        .filter(|c| keys.contains(&(c.system.clone(), c.code.clone())))
+                        .collect()
+                }
+            });
+        }
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Expand a ValueSet referenced from compose.include[].valueSet[]
+    fn resolve_imported_valueset(
+        storage: &TerminologyStorage,
+        url: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<Vec<ValueSetConceptResult>> {
+        if stack.iter().any(|u| u == url) {
+            anyhow::bail!("ValueSet import cycle: {} -> {}", stack.join(" -> "), url);
+        }
+        if storage.get_valueset(url)?.is_none() {
+            anyhow::bail!("Imported ValueSet not found: {}", url);
+        }
+
+        stack.push(url.to_string());
+        let concepts = match storage.get_valueset_compose(url)? {
+            Some(compose) => Self::evaluate_compose(storage, &compose, stack)?,
+            None => storage
+                .get_valueset_concepts(url)?
+                .into_iter()
+                .map(|c| ValueSetConceptResult {
+                    system: c.system,
+                    code: c.code,
+                    display: c.display,
+                })
+                .collect(),
+        };
+        stack.pop();
+
+        Ok(concepts)
+    }
+
+    /// Codes in a code system matching all the given filters (or every code if there are none)
+    fn system_codes(
+        storage: &TerminologyStorage,
+        system: &str,
+        filters: &[ComposeFilter],
+    ) -> Result<Vec<String>> {
+        match system {
+            SNOMED_SYSTEM => {
+                let ecl = if filters.is_empty() {
+                    "*".to_string()
+                } else {
+                    filters
+                        .iter()
+                        .map(|f| Self::snomed_filter_ecl(f).map(|ecl| format!("({})", ecl)))
+                        .collect::<Result<Vec<_>>>()?
+                        .join(" AND ")
+                };
+
+                let parsed = EclParser::parse(&ecl)?;
+                let matches = EclEvaluator::new(storage).evaluate(&parsed)?;
+                Ok(Self::sorted_sctids(matches))
+            }
+            AMT_SYSTEM => {
+                if !filters.is_empty() {
+                    anyhow::bail!("Filters are not supported for {}", system);
+                }
+                let codes: HashSet<String> =
+                    storage.get_all_amt_codes()?.into_iter().map(|c| c.id).collect();
+                Ok(Self::sorted_sctids(codes))
+            }
+            _ => {
+                if storage.get_codesystem(system)?.is_none() {
+                    anyhow::bail!(
+                        "Cannot resolve filter or whole-system include for {}: code system not available locally",
+                        system
+                    );
+                }
+                Self::codesystem_codes(storage, system, filters)
+            }
+        }
+    }
+
+    /// Codes of an imported CodeSystem matching all the given filters, in code order
+    /// Supports concept is-a, descendent-of, is-not-a, = and in, and property = / in
+    fn codesystem_codes(
+        storage: &TerminologyStorage,
+        system: &str,
+        filters: &[ComposeFilter],
+    ) -> Result<Vec<String>> {
+        let concepts = storage.get_codesystem_concepts(system)?;
+        let children: HashMap<&str, &[String]> =
+            concepts.iter().map(|c| (c.code.as_str(), c.children.as_slice())).collect();
+        let descendants_or_self = |code: &str| -> HashSet<String> {
+            let mut found = HashSet::from([code.to_string()]);
+            let mut queue = vec![code.to_string()];
+            while let Some(next) = queue.pop() {
+                for child in children.get(next.as_str()).copied().unwrap_or_default() {
+                    if found.insert(child.clone()) {
+                        queue.push(child.clone());
+                    }
+                }
+            }
+            found
+        };
+
+        let mut codes: Vec<String> = concepts.iter().map(|c| c.code.clone()).collect();
+        for filter in filters {
+            let value = filter.value.trim();
+            let values: HashSet<&str> = value.split(',').map(str::trim).collect();
+            // Codes the filter selects, or rejects when negated (is-not-a)
+            let (selected, negated): (HashSet<String>, bool) = match (filter.property.as_str(), filter.op.as_str()) {
+                ("concept", "is-a") => (descendants_or_self(value), false),
+                ("concept", "descendent-of") | ("concept", "descendant-of") => {
+                    let mut descendants = descendants_or_self(value);
+                    descendants.remove(value);
+                    (descendants, false)
+                }
+                ("concept", "is-not-a") => (descendants_or_self(value), true),
+                ("concept", "=") | ("concept", "in") => (values.iter().map(|v| v.to_string()).collect(), false),
+                (property, "=") | (property, "in") => {
+                    let selected = concepts
+                        .iter()
+                        .filter(|c| {
+                            c.properties
+                                .iter()
+                                .any(|p| p.code == property && values.contains(p.value.as_str()))
+                        })
+                        .map(|c| c.code.clone())
+                        .collect();
+                    (selected, false)
+                }
+                (property, op) => anyhow::bail!("Unsupported filter for {}: {} {} {}", system, property, op, value),
+            };
+            codes.retain(|code| selected.contains(code) != negated);
+        }
+
+        Ok(codes)
+    }
+
+    /// Translate a SNOMED compose filter into the equivalent ECL
+    fn snomed_filter_ecl(filter: &ComposeFilter) -> Result<String> {
+        let value = filter.value.trim();
+        let ecl = match (filter.property.as_str(), filter.op.as_str()) {
+            ("concept", "is-a") => format!("<< {}", value),
+            // FHIR spells this "descendent-of"; accept the common misspelling too
+            ("concept", "descendent-of") | ("concept", "descendant-of") => format!("< {}", value),
+            ("concept", "is-not-a") => format!("* MINUS << {}", value),
+            ("concept", "=") => value.to_string(),
+            // For SNOMED, concept "in" names reference sets (comma separated)
+            ("concept", "in") => value
+                .split(',')
+                .map(|refset| format!("^ {}", refset.trim()))
+                .collect::<Vec<_>>()
+                .join(" OR "),
+            ("constraint", "=") | ("expression", "=") => value.to_string(),
+            (property, op) => anyhow::bail!("Unsupported SNOMED filter: {} {} {}", property, op, value),
+        };
+
+        Ok(ecl)
+    }
+
+    /// Fill in missing displays: SNOMED preferred terms first, then AMT preferred terms, then imported CodeSystems
+    fn fill_displays(storage: &TerminologyStorage, concepts: &mut [ValueSetConceptResult]) -> Result<()> {
+        let missing: Vec<String> = concepts
+            .iter()
+            .filter(|c| c.display.is_none() && c.system == SNOMED_SYSTEM)
+            .map(|c| c.code.clone())
+            .collect();
+        let snomed_displays = Self::snomed_display_names(storage, &missing)?;
+
+        for concept in concepts.iter_mut().filter(|c| c.display.is_none()) {
+            if concept.system == SNOMED_SYSTEM {
+                concept.display = snomed_displays.get(&concept.code).cloned();
+            }
+        }
+
+        // AMT codes are SCTIDs too, so fall back to AMT preferred terms for either system
+        let needs_amt = |c: &ValueSetConceptResult| {
+            c.display.is_none() && (c.system == SNOMED_SYSTEM || c.system == AMT_SYSTEM)
+        };
+        if concepts.iter().any(needs_amt) {
+            let amt_displays: HashMap<String, String> = storage
+                .get_all_amt_codes()?
+                .into_iter()
+                .map(|c| (c.id, c.preferred_term))
+                .collect();
+
+            for concept in concepts.iter_mut().filter(|c| needs_amt(c)) {
+                concept.display = amt_displays.get(&concept.code).cloned();
+            }
+        }
+
+        for concept in concepts.iter_mut().filter(|c| c.display.is_none()) {
+            if concept.system != SNOMED_SYSTEM && concept.system != AMT_SYSTEM {
+                concept.display = storage
+                    .get_codesystem_concept(&concept.system, &concept.code)?
+                    .and_then(|c| c.display);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look up an AMT code by ID
+    pub fn lookup_amt_code(
+        storage: &TerminologyStorage,
+        code: &str,
+    ) -> Result<Option<CodeLookupResult>> {
+        let amt_code = storage.get_amt_code(code)?;
+
+        if let Some(amt_code) = amt_code {
+            Ok(Some(CodeLookupResult {
+                code: amt_code.id.clone(),
+                system: AMT_SYSTEM.to_string(),
+                display: amt_code.preferred_term.clone(),
+                active: true,
+                synonyms: vec![amt_code.preferred_term.clone()],
+                designations: vec![Designation {
+                    value: amt_code.preferred_term,
+                    kind: DesignationKind::Synonym,
+                    acceptability: Some(Acceptability::Preferred),
+                    language: None,
+                }],
+                inactivation: None,
+                properties: Vec::new(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Look up a concept of an imported FHIR CodeSystem
+    /// A concept is inactive when its `inactive` property is true or its `status` is retired or inactive
+    pub fn lookup_codesystem_code(
+        storage: &TerminologyStorage,
+        system: &str,
+        code: &str,
+    ) -> Result<Option<CodeLookupResult>> {
+        let Some(concept) = storage.get_codesystem_concept(system, code)? else {
+            return Ok(None);
+        };
+
+        let active = !concept.properties.iter().any(|p| {
+            (p.code == "inactive" && p.value == "true")
+                || (p.code == "status" && matches!(p.value.as_str(), "retired" | "inactive"))
+        });
+
+        let designations: Vec<Designation> = concept
+            .designations
+            .iter()
+            .map(|d| Designation {
+                value: d.value.clone(),
+                kind: if d.use_code.as_deref() == Some(FSN_TYPE_ID) {
+                    DesignationKind::Fsn
+                } else {
+                    DesignationKind::Synonym
+                },
+                acceptability: None,
+                language: d.language.clone(),
+            })
+            .collect();
+
+        let hierarchy = |property: &str, codes: &[String]| -> Vec<ConceptProperty> {
+            codes
+                .iter()
+                .map(|code| ConceptProperty {
+                    code: property.to_string(),
+                    value: code.clone(),
+                    value_type: "code".to_string(),
+                })
+                .collect()
+        };
+        let mut properties = hierarchy("parent", &concept.parents);
+        properties.extend(hierarchy("child", &concept.children));
+        properties.extend(concept.properties);
+
+        Ok(Some(CodeLookupResult {
+            code: concept.code.clone(),
+            system: concept.system,
+            display: concept.display.unwrap_or(concept.code),
+            active,
+            synonyms: designations.iter().map(|d| d.value.clone()).collect(),
+            designations,
+            inactivation: None,
+            properties,
+        }))
+    }
+
+    /// Search SNOMED descriptions using Tantivy
+    /// Each concept is listed once, under its en-AU preferred term, with the term that matched
+    pub fn search_snomed(
+        searcher: &TerminologySearch,
+        storage: &TerminologyStorage,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        // Over-fetch, since several descriptions of one concept collapse into a single result
+        let hits = searcher.search_snomed(query, limit.saturating_mul(4).max(100))?;
+        let mut results = Self::with_preferred_terms(storage, hits)?;
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Show SNOMED hits under their concept's en-AU preferred term, keeping the best hit per concept
+    /// Results from other terminologies pass through unchanged
+    fn with_preferred_terms(storage: &TerminologyStorage, hits: Vec<SearchResult>) -> Result<Vec<SearchResult>> {
+        let preferred_terms = storage.get_snomed_preferred_terms(
+            hits.iter().filter(|h| h.system == SNOMED_SYSTEM).map(|h| h.code.as_str()),
+        )?;
+
+        let mut seen = HashSet::new();
+        let mut results = Vec::with_capacity(hits.len());
+        for mut hit in hits {
+            if hit.system != SNOMED_SYSTEM {
+                results.push(hit);
+                continue;
+            }
+            if !seen.insert(hit.code.clone()) {
+                continue;
+            }
+            if let Some(term) = preferred_terms.get(&hit.code) {
+                if *term != hit.display {
+                    hit.matched_term = Some(std::mem::replace(&mut hit.display, term.clone()));
+                }
+            }
+            results.push(hit);
+        }
+
+        Ok(results)
+    }
+
+    /// Search SNOMED descriptions as they stood on `as_of` (YYYY-MM-DD or YYYYMMDD)
+    /// The index holds current terms only, so hits are checked against the RF2 Full history:
+    /// a hit is kept if its concept existed on the date and the matched term was one of its
+    /// descriptions then, and `active` reports the concept's status on that date
+    pub fn search_snomed_as_of(
+        searcher: &TerminologySearch,
+        storage: &TerminologyStorage,
+        query: &str,
+        limit: usize,
+        as_of: &str,
+    ) -> Result<Vec<SearchResult>> {
+        let as_of = Self::require_snomed_history(storage, as_of)?;
+
+        // Over-fetch, since some hits will be filtered out by the date
+        let hits = searcher.search_snomed(query, limit.saturating_mul(4).max(100))?;
+
+        let mut results = Vec::new();
+        for mut hit in hits {
+            let Some(concept) = storage.get_snomed_concept_as_of(&hit.code, &as_of)? else {
+                continue;
+            };
+            let descriptions = storage.get_snomed_descriptions_as_of(&hit.code, &as_of)?;
+            if !descriptions.iter().any(|d| d.term == hit.display) {
+                continue;
+            }
+
+            hit.active = concept.active;
+            results.push(hit);
+            if results.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Search AMT codes using Tantivy with optional code type filtering
+    pub fn search_amt(
+        searcher: &TerminologySearch,
+        query: &str,
+        limit: usize,
+        code_types: Option<&[String]>,
+    ) -> Result<Vec<SearchResult>> {
+        searcher.search_amt(query, limit, code_types)
+    }
+
+    /// Search ValueSets using Tantivy
+    pub fn search_valuesets(
+        searcher: &TerminologySearch,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        searcher.search_valuesets(query, limit)
+    }
+
+    /// Search across all terminologies
+    pub fn search_all(
+        searcher: &TerminologySearch,
+        storage: &TerminologyStorage,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        Self::with_preferred_terms(storage, searcher.search_all(query, limit)?)
+    }
+
+    /// Expand a ValueSet to get all its concepts
+    pub fn expand_valueset(
+        storage: &TerminologyStorage,
+        valueset_url: &str,
+    ) -> Result<Option<ValueSetExpansion>> {
+        // Get ValueSet metadata
+        let valueset = storage.get_valueset(valueset_url)?;
+
+        if let Some(valueset) = valueset {
+            // Intensional ValueSets are resolved against local content; the rest were
+            // expanded at import time
+            let concept_results = match storage.get_valueset_compose(valueset_url)? {
+                Some(compose) => {
+                    let mut stack = vec![valueset_url.to_string()];
+                    let mut concepts = Self::evaluate_compose(storage, &compose, &mut stack)?;
+                    Self::fill_displays(storage, &mut concepts)?;
+                    concepts
+                }
+                None => storage
+                    .get_valueset_concepts(valueset_url)?
+                    .into_iter()
+                    .map(|c| ValueSetConceptResult {
+                        system: c.system,
+                        code: c.code,
+                        display: c.display,
+                    })
+                    .collect(),
+            };
+
+            Ok(Some(ValueSetExpansion {
+                url: valueset.url,
+                version: valueset.version,
+                title: valueset.title,
+                total: concept_results.len(),
+                concepts: concept_results,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Validate that a code exists in a ValueSet
+    pub fn validate_code(
+        storage: &TerminologyStorage,
+        code: &str,
+        system: &str,
+        valueset_url: &str,
+    ) -> Result<ValidationResult> {
+        let is_valid = match storage.get_valueset_compose(valueset_url)? {
+            Some(compose) => {
+                let mut stack = vec![valueset_url.to_string()];
+                Self::evaluate_compose(storage, &compose, &mut stack)?
+                    .iter()
+                    .any(|c| c.system == system && c.code == code)
+            }
+            None => storage.valueset_contains_code(valueset_url, system, code)?,
+        };
+
+        if is_valid {
+            Ok(ValidationResult {
+                valid: true,
+                message: Some(format!("Code {} is valid in ValueSet {}", code, valueset_url)),
+            })
+        } else {
+            Ok(ValidationResult {
+                valid: false,
+                message: Some(format!("Code {} not found in ValueSet {}", code, valueset_url)),
+            })
+        }
+    }
+
+    /// Translate a code with the imported ConceptMaps, or only the one at `url`
+    /// Forward, (system, code) is looked up as a source code and codes without an element fall back to their
+    /// group's unmapped rule; in reverse it is looked up as a target and the equivalences are inverted.
+    /// `target_system` keeps only matches in that system (the source system when reversed)
+    pub fn translate(
+        storage: &TerminologyStorage,
+        system: &str,
+        code: &str,
+        url: Option<&str>,
+        target_system: Option<&str>,
+        reverse: bool,
+    ) -> Result<ConceptMapTranslation> {
+        if let Some(url) = url {
+            if storage.get_concept_map(url)?.is_none() {
+                anyhow::bail!("ConceptMap not found: {}", url);
+            }
+        }
+
+        let matches: Vec<ConceptMapMatch> = if reverse {
+            storage
+                .get_concept_map_rows_by_target(system, code)?
+                .into_iter()
+                .filter(|row| url.is_none_or(|url| row.map_url == url))
+                .filter(|row| target_system.is_none_or(|ts| row.source_system.as_deref() == Some(ts)))
+                .map(|row| ConceptMapMatch {
+                    equivalence: Self::inverse_equivalence(&row.equivalence).to_string(),
+                    system: row.source_system,
+                    code: Some(row.source_code),
+                    display: row.source_display,
+                    source: row.map_url,
+                    comment: row.comment,
+                })
+                .collect()
+        } else {
+            // Across every ConceptMap, an other-map fallback can reach a map that was already searched
+            let mut seen = HashSet::new();
+            let mut stack = Vec::new();
+            Self::translate_forward(storage, system, code, url, target_system, &mut stack)?
+                .into_iter()
+                .filter(|m| seen.insert((m.source.clone(), m.system.clone(), m.code.clone())))
+                .collect()
+        };
+
+        let result = matches
+            .iter()
+            .any(|m| !matches!(m.equivalence.as_str(), "unmatched" | "disjoint"));
+        let message = (!result).then(|| format!("No mapping found for code {} in {}", code, system));
+
+        Ok(ConceptMapTranslation { result, message, matches })
+    }
+
+    /// Forward translation; `stack` holds the ConceptMaps being resolved, to stop other-map cycles
+    fn translate_forward(
+        storage: &TerminologyStorage,
+        system: &str,
+        code: &str,
+        url: Option<&str>,
+        target_system: Option<&str>,
+        stack: &mut Vec<String>,
+    ) -> Result<Vec<ConceptMapMatch>> {
+        let target_matches = |target: &Option<String>| target_system.is_none_or(|ts| target.as_deref() == Some(ts));
+
+        let rows: Vec<_> = storage
+            .get_concept_map_rows(system, code)?
+            .into_iter()
+            .filter(|row| url.is_none_or(|url| row.map_url == url))
+            .collect();
+        let mapped_groups: HashSet<(String, u32)> = rows.iter().map(|row| (row.map_url.clone(), row.group)).collect();
+
+        let mut matches: Vec<ConceptMapMatch> = rows
+            .into_iter()
+            .filter(|row| target_matches(&row.target_system))
+            .map(|row| ConceptMapMatch {
+                equivalence: row.equivalence,
+                system: row.target_system,
+                code: row.target_code,
+                display: row.target_display,
+                source: row.map_url,
+                comment: row.comment,
+            })
+            .collect();
+
+        // Groups with no element for the code apply their unmapped rule
+        // A group without a source system only applies when its ConceptMap was asked for by URL
+        let maps = match url {
+            Some(url) => storage.get_concept_map(url)?.into_iter().collect(),
+            None => storage.get_all_concept_maps()?,
+        };
+        for map in maps {
+            for (index, group) in map.groups.iter().enumerate() {
+                let applies = match &group.source {
+                    Some(source) => source == system,
+                    None => url.is_some(),
+                };
+                let mapped = mapped_groups.contains(&(map.url.clone(), index as u32));
+                if !applies || mapped || !target_matches(&group.target) {
+                    continue;
+                }
+                let Some(unmapped) = &group.unmapped else {
+                    continue;
+                };
+
+                let (equivalence, target_code, display) = match unmapped.mode.as_str() {
+                    "provided" => ("equal", code.to_string(), None),
+                    "fixed" => match &unmapped.code {
+                        Some(fixed) => ("inexact", fixed.clone(), unmapped.display.clone()),
+                        None => continue,
+                    },
+                    "other-map" => {
+                        if let Some(other) = unmapped.url.as_deref().filter(|other| *other != map.url) {
+                            if !stack.iter().any(|url| url == other) && storage.get_concept_map(other)?.is_some() {
+                                stack.push(map.url.clone());
+                                let other_matches =
+                                    Self::translate_forward(storage, system, code, Some(other), target_system, stack)?;
+                                stack.pop();
+                                matches.extend(other_matches);
+                            }
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                matches.push(ConceptMapMatch {
+                    equivalence: equivalence.to_string(),
+                    system: group.target.clone(),
+                    code: Some(target_code),
+                    display,
+                    source: map.url.clone(),
+                    comment: None,
+                });
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Equivalence of a mapping read from target to source
+    fn inverse_equivalence(equivalence: &str) -> &str {
+        match equivalence {
+            "wider" => "narrower",
+            "narrower" => "wider",
+            "subsumes" => "specializes",
+            "specializes" => "subsumes",
+            other => other,
+        }
+    }
+
+    /// List all available ValueSets
+    pub fn list_valuesets(storage: &TerminologyStorage) -> Result<Vec<ValueSetListItem>> {
+        let valuesets = storage.get_all_valuesets()?;
+
+        let items: Vec<ValueSetListItem> = valuesets
+            .into_iter()
+            .map(|vs| ValueSetListItem {
+                url: vs.url,
+                title: vs.title,
+                name: vs.name,
+                description: vs.description,
+            })
+            .collect();
+
+        Ok(items)
+    }
+}
@@ -0,0 +1,802 @@
+use crate::storage::{ContentFamily, TerminologyStorage};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, Occur, QueryParser, TermQuery};
+use tantivy::schema::*;
+use tantivy::tokenizer::NgramTokenizer;
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, Term};
+
+/// Search result from Tantivy indexes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub code: String,
+    pub system: String,
+    pub display: String,
+    pub terminology_type: String,
+    pub active: bool,
+    pub score: f32,
+    pub subtype: Option<String>,
+    /// Term that matched the query, when it differs from display (SNOMED preferred terms)
+    #[serde(default)]
+    pub matched_term: Option<String>,
+}
+
+/// Tantivy search engine for terminology search
+/// Shared between readers and a writer: searches see the last commit while documents are added,
+/// so an import can rebuild an index without blocking queries
+/// Each family's index lives in its own directory and is only closed while it is swapped for
+/// another release's on promotion (see `promote_release`)
+pub struct TerminologySearch {
+    snomed: RwLock<Option<FamilyIndex>>,
+    amt: RwLock<Option<FamilyIndex>>,
+    valuesets: RwLock<Option<FamilyIndex>>,
+
+    index_dir: PathBuf,
+    /// Indexes of side-by-side releases, opened on first use
+    releases: Mutex<HashMap<u64, Arc<TerminologySearch>>>,
+}
+
+/// One family's index with its reader and writer
+struct FamilyIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+}
+
+impl FamilyIndex {
+    fn open(dir: &Path, family: ContentFamily) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let index = match family {
+            ContentFamily::Snomed => TerminologySearch::create_snomed_index(dir)?,
+            ContentFamily::Amt => TerminologySearch::create_amt_index(dir)?,
+            ContentFamily::ValueSets => TerminologySearch::create_valueset_index(dir)?,
+        };
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()?;
+        let writer = index.writer(50_000_000)?; // 50MB heap
+        Ok(Self {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+        })
+    }
+}
+
+/// An open family index, held for the length of one operation so promotion waits for it
+struct FamilyGuard<'a>(RwLockReadGuard<'a, Option<FamilyIndex>>);
+
+impl Deref for FamilyGuard<'_> {
+    type Target = FamilyIndex;
+
+    fn deref(&self) -> &FamilyIndex {
+        self.0.as_ref().expect("Family index checked open when the guard was taken")
+    }
+}
+
+const FAMILIES: [ContentFamily; 3] = [ContentFamily::Snomed, ContentFamily::Amt, ContentFamily::ValueSets];
+
+/// Directory of a family's index under an index directory
+fn family_dir_name(family: ContentFamily) -> &'static str {
+    match family {
+        ContentFamily::Snomed => "snomed",
+        ContentFamily::Amt => "amt",
+        ContentFamily::ValueSets => "valuesets",
+    }
+}
+
+/// Search indexes for a storage view: the live indexes, or a side-by-side release's own
+pub enum ReleaseSearch<'a> {
+    Live(&'a TerminologySearch),
+    Release(Arc<TerminologySearch>),
+}
+
+impl Deref for ReleaseSearch<'_> {
+    type Target = TerminologySearch;
+
+    fn deref(&self) -> &TerminologySearch {
+        match self {
+            Self::Live(search) => search,
+            Self::Release(search) => search,
+        }
+    }
+}
+
+/// Total size of the files under a directory (0 if it does not exist)
+fn dir_size(dir: &Path) -> Result<u64> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
+
+/// Lock an index writer, carrying on after a panic in another writer (the index itself stays consistent)
+fn lock(writer: &Mutex<IndexWriter>) -> MutexGuard<'_, IndexWriter> {
+    writer.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl TerminologySearch {
+    /// Create a new search engine with indexes stored in the given directory
+    pub fn new(index_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(index_dir)?;
+
+        let open = |family| -> Result<_> {
+            Ok(RwLock::new(Some(FamilyIndex::open(&index_dir.join(family_dir_name(family)), family)?)))
+        };
+        Ok(Self {
+            snomed: open(ContentFamily::Snomed)?,
+            amt: open(ContentFamily::Amt)?,
+            valuesets: open(ContentFamily::ValueSets)?,
+            index_dir: index_dir.to_path_buf(),
+            releases: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn slot(&self, family: ContentFamily) -> &RwLock<Option<FamilyIndex>> {
+        match family {
+            ContentFamily::Snomed => &self.snomed,
+            ContentFamily::Amt => &self.amt,
+            ContentFamily::ValueSets => &self.valuesets,
+        }
+    }
+
+    /// A family's open index; fails once the index was closed by a promotion that moved it away
+    fn family(&self, family: ContentFamily) -> Result<FamilyGuard<'_>> {
+        let guard = self.slot(family).read().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            anyhow::bail!("The {} search index was closed", family_dir_name(family));
+        }
+        Ok(FamilyGuard(guard))
+    }
+
+    /// Close every index of a side-by-side release and forget it, waiting for searches using it to finish
+    fn close_release(&self, version_id: u64) {
+        let release = self.releases.lock().unwrap_or_else(|e| e.into_inner()).remove(&version_id);
+        if let Some(release) = release {
+            for family in FAMILIES {
+                release.slot(family).write().unwrap_or_else(|e| e.into_inner()).take();
+            }
+        }
+    }
+
+    fn release_dir(&self, version_id: u64) -> PathBuf {
+        self.index_dir.join("releases").join(format!("v{}", version_id))
+    }
+
+    /// Search indexes of a release imported beside the live one
+    pub fn for_release(&self, version_id: u64) -> Result<Arc<TerminologySearch>> {
+        let mut releases = self.releases.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(release) = releases.get(&version_id) {
+            return Ok(Arc::clone(release));
+        }
+        let release = Arc::new(Self::new(&self.release_dir(version_id))?);
+        releases.insert(version_id, Arc::clone(&release));
+        Ok(release)
+    }
+
+    /// Search indexes matching a storage view (see `TerminologyStorage::select_release`)
+    pub fn for_view(&self, storage: &TerminologyStorage) -> Result<ReleaseSearch<'_>> {
+        match storage.release() {
+            Some(version_id) => Ok(ReleaseSearch::Release(self.for_release(version_id)?)),
+            None => Ok(ReleaseSearch::Live(self)),
+        }
+    }
+
+    /// Bytes a family's search index takes on disk, for the live index or a side-by-side release's
+    pub fn index_bytes(&self, family: ContentFamily, release: Option<u64>) -> Result<u64> {
+        let dir = match release {
+            Some(version_id) => self.release_dir(version_id),
+            None => self.index_dir.clone(),
+        };
+        dir_size(&dir.join(family_dir_name(family)))
+    }
+
+    /// Close and delete the search indexes of a side-by-side release
+    /// Searches already using the release's indexes finish first
+    pub fn remove_release(&self, version_id: u64) -> Result<()> {
+        self.close_release(version_id);
+        let dir = self.release_dir(version_id);
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Start a release's index of a family as a copy of the live one, for a delta to be applied to
+    /// The copy replaces the live index when the delta is promoted (see `promote_release`)
+    pub fn stage_from_live(&self, family: ContentFamily, version_id: u64) -> Result<()> {
+        self.close_release(version_id);
+        let name = family_dir_name(family);
+        let staged_dir = self.release_dir(version_id).join(name);
+        if staged_dir.exists() {
+            std::fs::remove_dir_all(&staged_dir)?;
+        }
+        std::fs::create_dir_all(&staged_dir)?;
+
+        // Commits wait for the copy, but background merges can still replace segments and garbage
+        // collect their files, so only the committed segments are copied, again if meta.json changed meanwhile
+        let live = self.family(family)?;
+        let _writer = lock(&live.writer);
+        let live_dir = self.index_dir.join(name);
+        loop {
+            let meta = std::fs::read(live_dir.join("meta.json"))?;
+            for segment in live.index.searchable_segment_metas()? {
+                // Not every segment has every component file
+                for file in segment.list_files() {
+                    match std::fs::copy(live_dir.join(&file), staged_dir.join(&file)) {
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        copied => {
+                            copied?;
+                        }
+                    }
+                }
+            }
+            if std::fs::read(live_dir.join("meta.json"))? == meta {
+                std::fs::write(staged_dir.join("meta.json"), meta)?;
+                return Ok(());
+            }
+        }
+    }
+
+    /// Make a release's index of a family the live one, as `TerminologyStorage::promote_release` swaps its
+    /// tables: the live index moves into the demoted release's directory (or is dropped if there is none)
+    /// and the release's own index moves into its place. Nothing is re-indexed
+    /// Returns false when the release had no index of its own, leaving an empty live index to rebuild
+    pub fn promote_release(&self, family: ContentFamily, version_id: u64, demoted: Option<u64>) -> Result<bool> {
+        self.close_release(version_id);
+        if let Some(demoted) = demoted {
+            self.close_release(demoted);
+        }
+
+        let name = family_dir_name(family);
+        let live_dir = self.index_dir.join(name);
+        let release_dir = self.release_dir(version_id);
+        let promoted_dir = release_dir.join(name);
+        let found = promoted_dir.exists();
+
+        // Searches on the live index finish before it is closed, and new ones wait until it is reopened
+        let mut slot = self.slot(family).write().unwrap_or_else(|e| e.into_inner());
+        slot.take();
+        let moved = (|| -> Result<()> {
+            match demoted {
+                Some(demoted) => {
+                    let demoted_dir = self.release_dir(demoted).join(name);
+                    if demoted_dir.exists() {
+                        std::fs::remove_dir_all(&demoted_dir)?;
+                    }
+                    std::fs::create_dir_all(self.release_dir(demoted))?;
+                    std::fs::rename(&live_dir, &demoted_dir)?;
+                }
+                None => std::fs::remove_dir_all(&live_dir)?,
+            }
+            if found {
+                std::fs::rename(&promoted_dir, &live_dir)?;
+            }
+            // The rest of the release's directory only holds the empty indexes of other families
+            if release_dir.exists() {
+                std::fs::remove_dir_all(&release_dir)?;
+            }
+            Ok(())
+        })();
+
+        // Reopen whatever is live now, so a failed move still leaves an index to search
+        *slot = Some(FamilyIndex::open(&live_dir, family)?);
+        moved?;
+        Ok(found)
+    }
+
+    /// Create SNOMED description index schema
+    fn create_snomed_index(index_dir: &Path) -> Result<Index> {
+        let mut schema_builder = Schema::builder();
+
+        schema_builder.add_text_field("concept_id", STRING | STORED);
+
+        // Configure term field with trigram tokenizer for better fuzzy search
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_tokenizer("trigram")
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+
+        let text_options = TextOptions::default()
+            .set_indexing_options(text_field_indexing)
+            .set_stored();
+
+        schema_builder.add_text_field("term", text_options);
+        schema_builder.add_text_field("type_id", STRING);
+        schema_builder.add_u64_field("active", INDEXED | STORED);
+
+        let schema = schema_builder.build();
+        let index = Index::open_or_create(tantivy::directory::MmapDirectory::open(index_dir)?, schema)?;
+
+        // Register trigram tokenizer BEFORE any indexing operations
+        index.tokenizers().register(
+            "trigram",
+            NgramTokenizer::new(3, 3, false).unwrap(),
+        );
+
+        Ok(index)
+    }
+
+    /// Create AMT code index schema
+    fn create_amt_index(index_dir: &Path) -> Result<Index> {
+        let mut schema_builder = Schema::builder();
+
+        schema_builder.add_text_field("code", STRING | STORED);
+
+        // Configure preferred_term field with trigram tokenizer for better fuzzy search
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_tokenizer("trigram")
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+
+        let text_options = TextOptions::default()
+            .set_indexing_options(text_field_indexing)
+            .set_stored();
+
+        schema_builder.add_text_field("preferred_term", text_options);
+        schema_builder.add_text_field("code_type", STRING | STORED | FAST);
+
+        let schema = schema_builder.build();
+        let index = Index::open_or_create(tantivy::directory::MmapDirectory::open(index_dir)?, schema)?;
+
+        // Register trigram tokenizer BEFORE any indexing operations
+        index.tokenizers().register(
+            "trigram",
+            NgramTokenizer::new(3, 3, false).unwrap(),
+        );
+
+        Ok(index)
+    }
+
+    /// Create ValueSet index schema
+    fn create_valueset_index(index_dir: &Path) -> Result<Index> {
+        let mut schema_builder = Schema::builder();
+
+        schema_builder.add_text_field("url", STRING | STORED);
+        schema_builder.add_text_field("title", TEXT | STORED);
+        schema_builder.add_text_field("name", TEXT);
+        schema_builder.add_text_field("description", TEXT);
+
+        let schema = schema_builder.build();
+        let index = Index::open_or_create(tantivy::directory::MmapDirectory::open(index_dir)?, schema)?;
+
+        Ok(index)
+    }
+
+    /// Index a SNOMED description
+    pub fn index_snomed_description(
+        &self,
+        concept_id: &str,
+        term: &str,
+        type_id: &str,
+        active: bool,
+    ) -> Result<()> {
+        let snomed = self.family(ContentFamily::Snomed)?;
+        let schema = snomed.index.schema();
+        let concept_field = schema.get_field("concept_id")?;
+        let term_field = schema.get_field("term")?;
+        let type_field = schema.get_field("type_id")?;
+        let active_field = schema.get_field("active")?;
+
+        lock(&snomed.writer).add_document(doc!(
+            concept_field => concept_id,
+            term_field => term,
+            type_field => type_id,
+            active_field => if active { 1u64 } else { 0u64 },
+        ))?;
+
+        Ok(())
+    }
+
+    /// Remove every indexed description of a SNOMED concept (takes effect on the next commit)
+    pub fn delete_snomed_concept(&self, concept_id: &str) -> Result<()> {
+        let snomed = self.family(ContentFamily::Snomed)?;
+        let concept_field = snomed.index.schema().get_field("concept_id")?;
+        lock(&snomed.writer).delete_term(Term::from_field_text(concept_field, concept_id));
+        Ok(())
+    }
+
+    /// Index an AMT code
+    pub fn index_amt_code(
+        &self,
+        code: &str,
+        preferred_term: &str,
+        code_type: &str,
+    ) -> Result<()> {
+        let amt = self.family(ContentFamily::Amt)?;
+        let schema = amt.index.schema();
+        let code_field = schema.get_field("code")?;
+        let term_field = schema.get_field("preferred_term")?;
+        let type_field = schema.get_field("code_type")?;
+
+        lock(&amt.writer).add_document(doc!(
+            code_field => code,
+            term_field => preferred_term,
+            type_field => code_type,
+        ))?;
+
+        Ok(())
+    }
+
+    /// Index a ValueSet
+    pub fn index_valueset(
+        &self,
+        url: &str,
+        title: Option<&str>,
+        name: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()> {
+        let valuesets = self.family(ContentFamily::ValueSets)?;
+        let schema = valuesets.index.schema();
+        let url_field = schema.get_field("url")?;
+        let title_field = schema.get_field("title")?;
+        let name_field = schema.get_field("name")?;
+        let desc_field = schema.get_field("description")?;
+
+        let mut doc = doc!(url_field => url);
+
+        if let Some(t) = title {
+            doc.add_text(title_field, t);
+        }
+        if let Some(n) = name {
+            doc.add_text(name_field, n);
+        }
+        if let Some(d) = description {
+            doc.add_text(desc_field, d);
+        }
+
+        lock(&valuesets.writer).add_document(doc)?;
+
+        Ok(())
+    }
+
+    /// Commit a family's pending changes, making them visible to searches
+    /// Other families' pending changes belong to whoever is writing them and are left alone
+    pub fn commit(&self, family: ContentFamily) -> Result<()> {
+        let index = self.family(family)?;
+        lock(&index.writer).commit()?;
+        index.reader.reload()?;
+        Ok(())
+    }
+
+    /// Discard a family's changes made since its last commit, so searches keep the last committed documents
+    pub fn rollback(&self, family: ContentFamily) -> Result<()> {
+        lock(&self.family(family)?.writer).rollback()?;
+        Ok(())
+    }
+
+    /// Run a rebuild of a family's index and commit it, or roll it back if it fails part way
+    /// so a failed rebuild never replaces the committed documents with a partial set
+    pub fn rebuild<T>(&self, family: ContentFamily, build: impl FnOnce() -> Result<T>) -> Result<T> {
+        match build().and_then(|built| self.commit(family).map(|_| built)) {
+            Ok(built) => Ok(built),
+            Err(e) => {
+                self.rollback(family)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Clear all indexed data (for re-import)
+    pub fn clear_all(&self) -> Result<()> {
+        for family in FAMILIES {
+            lock(&self.family(family)?.writer).delete_all_documents()?;
+            self.commit(family)?;
+        }
+        Ok(())
+    }
+
+    /// Search SNOMED descriptions
+    pub fn search_snomed(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let snomed = self.family(ContentFamily::Snomed)?;
+        let schema = snomed.index.schema();
+        let term_field = schema.get_field("term")?;
+        let concept_field = schema.get_field("concept_id")?;
+        let active_field = schema.get_field("active")?;
+
+        let searcher = snomed.reader.searcher();
+        let query_parser = QueryParser::for_index(&snomed.index, vec![term_field]);
+
+        // Parse query (trigram tokenizer handles fuzzy matching naturally)
+        let query = query_parser.parse_query(query)?;
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+
+        let mut results = Vec::new();
+        for (score, doc_address) in top_docs {
+            let retrieved_doc: tantivy::TantivyDocument = searcher.doc(doc_address)?;
+
+            let concept_id = retrieved_doc
+                .get_first(concept_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let term = retrieved_doc
+                .get_first(term_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let active = retrieved_doc
+                .get_first(active_field)
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) == 1;
+
+            results.push(SearchResult {
+                code: concept_id,
+                system: "http://snomed.info/sct".to_string(),
+                display: term,
+                terminology_type: "snomed".to_string(),
+                active,
+                score,
+                subtype: None,
+                matched_term: None,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Search AMT codes with optional code type filtering
+    pub fn search_amt(&self, query: &str, limit: usize, code_types: Option<&[String]>) -> Result<Vec<SearchResult>> {
+        let amt = self.family(ContentFamily::Amt)?;
+        let schema = amt.index.schema();
+        let term_field = schema.get_field("preferred_term")?;
+        let code_field = schema.get_field("code")?;
+        let type_field = schema.get_field("code_type")?;
+
+        let searcher = amt.reader.searcher();
+
+        // Build term query for preferred_term field
+        let query_parser = QueryParser::for_index(&amt.index, vec![term_field]);
+        let term_query = query_parser.parse_query(query)?;
+
+        // Build final query with optional code type filter
+        let final_query: Box<dyn tantivy::query::Query> = if let Some(types) = code_types {
+            if types.is_empty() {
+                Box::new(term_query)
+            } else {
+                // Build nested BooleanQuery structure:
+                // Top level: (term MUST match) AND (code_type filter MUST match)
+                // Nested level: (type1 SHOULD match) OR (type2 SHOULD match) OR ...
+
+                // Build the nested code_type filter (at least one type must match)
+                let type_queries: Vec<(Occur, Box<dyn tantivy::query::Query>)> = types.iter()
+                    .map(|t| {
+                        let term = Term::from_field_text(type_field, t);
+                        (Occur::Should, Box::new(TermQuery::new(term, tantivy::schema::IndexRecordOption::Basic)) as Box<dyn tantivy::query::Query>)
+                    })
+                    .collect();
+
+                let type_filter = BooleanQuery::new(type_queries);
+
+                // Build top-level query: both clauses are MUST
+                let final_clauses = vec![
+                    (Occur::Must, Box::new(term_query) as Box<dyn tantivy::query::Query>),
+                    (Occur::Must, Box::new(type_filter) as Box<dyn tantivy::query::Query>),
+                ];
+
+                Box::new(BooleanQuery::new(final_clauses))
+            }
+        } else {
+            Box::new(term_query)
+        };
+
+        let top_docs = searcher.search(&*final_query, &TopDocs::with_limit(limit))?;
+
+        let mut results = Vec::new();
+        for (score, doc_address) in top_docs {
+            let retrieved_doc: tantivy::TantivyDocument = searcher.doc(doc_address)?;
+
+            let code = retrieved_doc
+                .get_first(code_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let term = retrieved_doc
+                .get_first(term_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let code_type = retrieved_doc
+                .get_first(type_field)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            results.push(SearchResult {
+                code,
+                system: "http://hl7.org/fhir/sid/ncts-amt".to_string(),
+                display: term,
+                terminology_type: "amt".to_string(),
+                active: true,
+                score,
+                subtype: code_type,
+                matched_term: None,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Search ValueSets
+    pub fn search_valuesets(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let valuesets = self.family(ContentFamily::ValueSets)?;
+        let schema = valuesets.index.schema();
+        let url_field = schema.get_field("url")?;
+        let title_field = schema.get_field("title")?;
+        let name_field = schema.get_field("name")?;
+        let desc_field = schema.get_field("description")?;
+
+        let searcher = valuesets.reader.searcher();
+        let query_parser = QueryParser::for_index(
+            &valuesets.index,
+            vec![title_field, name_field, desc_field, url_field],
+        );
+
+        let query = query_parser.parse_query(query)?;
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+
+        let mut results = Vec::new();
+        for (score, doc_address) in top_docs {
+            let retrieved_doc: tantivy::TantivyDocument = searcher.doc(doc_address)?;
+
+            let url = retrieved_doc
+                .get_first(url_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let title = retrieved_doc
+                .get_first(title_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or(&url)
+                .to_string();
+
+            results.push(SearchResult {
+                code: url.clone(),
+                system: "http://hl7.org/fhir/ValueSet".to_string(),
+                display: title,
+                terminology_type: "valuesets".to_string(),
+                active: true,
+                score,
+                subtype: None,
+                matched_term: None,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Search across all terminologies
+    pub fn search_all(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let per_terminology = limit / 3;
+
+        let mut results = Vec::new();
+        results.extend(self.search_snomed(query, per_terminology)?);
+        results.extend(self.search_amt(query, per_terminology, None)?);
+        results.extend(self.search_valuesets(query, per_terminology)?);
+
+        // Sort by score descending
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        results.truncate(limit);
+
+        Ok(results)
+    }
+
+    /// Check if SNOMED index is empty
+    pub fn is_snomed_empty(&self) -> Result<bool> {
+        let searcher = self.family(ContentFamily::Snomed)?.reader.searcher();
+        Ok(searcher.num_docs() == 0)
+    }
+
+    /// Check if AMT index is empty
+    pub fn is_amt_empty(&self) -> Result<bool> {
+        let searcher = self.family(ContentFamily::Amt)?.reader.searcher();
+        Ok(searcher.num_docs() == 0)
+    }
+
+    /// Check if ValueSet index is empty
+    pub fn is_valueset_empty(&self) -> Result<bool> {
+        let searcher = self.family(ContentFamily::ValueSets)?.reader.searcher();
+        Ok(searcher.num_docs() == 0)
+    }
+
+    /// Clear SNOMED index only
+    /// Takes effect on the next commit, so a rebuild replaces the old documents in one step
+    pub fn clear_snomed(&self) -> Result<()> {
+        lock(&self.family(ContentFamily::Snomed)?.writer).delete_all_documents()?;
+        Ok(())
+    }
+
+    /// Clear AMT index only (takes effect on the next commit)
+    pub fn clear_amt(&self) -> Result<()> {
+        lock(&self.family(ContentFamily::Amt)?.writer).delete_all_documents()?;
+        Ok(())
+    }
+
+    /// Clear ValueSet index only (takes effect on the next commit)
+    pub fn clear_valuesets(&self) -> Result<()> {
+        lock(&self.family(ContentFamily::ValueSets)?.writer).delete_all_documents()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_during_rebuild() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let search = Arc::new(TerminologySearch::new(dir.path()).expect("Failed to create indexes"));
+        search.index_snomed_description("73211009", "Diabetes mellitus", "900000000000013009", true).unwrap();
+        search.commit(ContentFamily::Snomed).unwrap();
+
+        // Stage a rebuild that drops the old documents without committing it
+        search.clear_snomed().unwrap();
+        search.index_snomed_description("38341003", "Hypertensive disorder", "900000000000013009", true).unwrap();
+
+        let reader = Arc::clone(&search);
+        let during = std::thread::spawn(move || reader.search_snomed("mellitus", 10).unwrap()).join().unwrap();
+        assert_eq!(during.len(), 1, "The uncommitted rebuild is not visible yet");
+
+        search.commit(ContentFamily::Snomed).unwrap();
+        assert!(search.search_snomed("mellitus", 10).unwrap().is_empty());
+        assert_eq!(search.search_snomed("disorder", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rebuild_is_scoped_to_family() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let search = TerminologySearch::new(dir.path()).expect("Failed to create indexes");
+        search.index_snomed_description("73211009", "Diabetes mellitus", "900000000000013009", true).unwrap();
+
+        let failed = search.rebuild(ContentFamily::Amt, || -> Result<()> {
+            search.index_amt_code("21360011000036101", "paracetamol", "MP")?;
+            anyhow::bail!("AMT file truncated")
+        });
+        assert!(failed.is_err());
+        search
+            .rebuild(ContentFamily::ValueSets, || search.index_valueset("http://example.org/vs", None, None, None))
+            .unwrap();
+        assert!(search.is_snomed_empty().unwrap(), "Other families' rebuilds do not commit SNOMED documents");
+        assert!(!search.is_valueset_empty().unwrap());
+
+        search.commit(ContentFamily::Snomed).unwrap();
+        assert_eq!(search.search_snomed("mellitus", 10).unwrap().len(), 1, "The failed AMT rebuild kept them pending");
+        assert!(search.is_amt_empty().unwrap());
+    }
+
+    #[test]
+    fn test_stage_from_live() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let search = TerminologySearch::new(dir.path()).expect("Failed to create indexes");
+        search.index_snomed_description("73211009", "Diabetes mellitus", "900000000000013009", true).unwrap();
+        search.commit(ContentFamily::Snomed).unwrap();
+
+        search.stage_from_live(ContentFamily::Snomed, 7).unwrap();
+        let staged = search.for_release(7).unwrap();
+        assert_eq!(staged.search_snomed("mellitus", 10).unwrap().len(), 1);
+        staged.index_snomed_description("44054006", "Type 2 diabetes mellitus", "900000000000013009", true).unwrap();
+        staged.commit(ContentFamily::Snomed).unwrap();
+        assert_eq!(search.search_snomed("mellitus", 10).unwrap().len(), 1, "Live index is untouched");
+
+        drop(staged);
+        assert!(search.promote_release(ContentFamily::Snomed, 7, None).unwrap());
+        assert_eq!(search.search_snomed("mellitus", 10).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,3076 @@
+use crate::parsers::snomed_rf2::IS_A_TYPE_ID;
+use crate::parsers::{ConceptDesignation, ConceptMapUnmapped, ConceptProperty, ValueSetCompose};
+use chrono::{DateTime, Utc};
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition, TableHandle};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("IO error: {0}")]
+    Io(String),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("Incomplete release: {0}")]
+    IncompleteRelease(String),
+    #[error(
+        "The database at {path} uses storage schema v{found}, but this version of the app only supports up to \
+         v{supported}. Update the app, or move the database aside and re-import"
+    )]
+    SchemaTooNew { path: String, found: u64, supported: u64 },
+    #[error("Failed to upgrade the database from storage schema v{from} ({step}): {reason}. The database was left unchanged")]
+    Migration { from: u64, step: &'static str, reason: String },
+}
+
+impl From<redb::DatabaseError> for StorageError {
+    fn from(err: redb::DatabaseError) -> Self {
+        StorageError::Database(err.to_string())
+    }
+}
+
+impl From<redb::TransactionError> for StorageError {
+    fn from(err: redb::TransactionError) -> Self {
+        StorageError::Database(err.to_string())
+    }
+}
+
+impl From<redb::TableError> for StorageError {
+    fn from(err: redb::TableError) -> Self {
+        StorageError::Database(err.to_string())
+    }
+}
+
+impl From<redb::StorageError> for StorageError {
+    fn from(err: redb::StorageError) -> Self {
+        StorageError::Database(err.to_string())
+    }
+}
+
+impl From<redb::CommitError> for StorageError {
+    fn from(err: redb::CommitError) -> Self {
+        StorageError::Database(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        StorageError::Io(err.to_string())
+    }
+}
+
+impl From<bincode::Error> for StorageError {
+    fn from(err: bincode::Error) -> Self {
+        StorageError::Serialization(err.to_string())
+    }
+}
+
+// Table definitions
+const TERMINOLOGY_VERSIONS: TableDefinition<u64, &[u8]> = TableDefinition::new("terminology_versions");
+const TERMINOLOGY_VERSION_COUNTER: TableDefinition<&str, u64> = TableDefinition::new("version_counter");
+const SNOMED_CONCEPTS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_concepts");
+const SNOMED_DESCRIPTIONS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_descriptions");
+// SNOMED_CONCEPT_DESCRIPTIONS indexes descriptions by concept as (concept_id, description_id), kept with each batch
+const SNOMED_CONCEPT_DESCRIPTIONS: TableDefinition<(&str, &str), ()> =
+    TableDefinition::new("snomed_concept_descriptions");
+// SNOMED_RELATIONSHIPS uses composite key (source_id, relationship_id) so a concept's outgoing relationships are a range scan
+const SNOMED_RELATIONSHIPS: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("snomed_relationships");
+// SNOMED_ISA_CHILDREN indexes active is-a relationships as (parent_id, child_id) -> version_id
+const SNOMED_ISA_CHILDREN: TableDefinition<(&str, &str), u64> = TableDefinition::new("snomed_isa_children");
+// SNOMED_CLOSURE is the transitive closure of is-a as (ancestor_id, descendant_id) -> version_id
+const SNOMED_CLOSURE: TableDefinition<(&str, &str), u64> = TableDefinition::new("snomed_closure");
+// SNOMED_CONCEPT_HISTORY keeps every dated concept row as (concept_id, effective_time) for point-in-time queries
+const SNOMED_CONCEPT_HISTORY: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("snomed_concept_history");
+// SNOMED_DESCRIPTION_HISTORY keeps every dated description row as (concept_id, description_id, effective_time)
+const SNOMED_DESCRIPTION_HISTORY: TableDefinition<(&str, &str, &str), &[u8]> =
+    TableDefinition::new("snomed_description_history");
+// SNOMED_LANGUAGE_REFSET holds language refset members as (description_id, refset_id), latest row per key
+const SNOMED_LANGUAGE_REFSET: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("snomed_language_refset");
+// SNOMED_PREFERRED_TERMS maps a concept to its en-AU preferred synonym, derived after each import
+const SNOMED_PREFERRED_TERMS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_preferred_terms");
+// SNOMED_REFSET_MEMBERS holds every other refset's rows as (refset_id, referenced_component_id, member_id)
+const SNOMED_REFSET_MEMBERS: TableDefinition<(&str, &str, &str), &[u8]> =
+    TableDefinition::new("snomed_refset_members");
+// SNOMED_REFSETS summarises each imported refset (type and active member count), derived after each import
+const SNOMED_REFSETS: TableDefinition<&str, &[u8]> = TableDefinition::new("snomed_refsets");
+// SNOMED_MAP_ENTRIES holds active complex/extended map rows as (source_code, refset_id, member_id), derived after each import
+const SNOMED_MAP_ENTRIES: TableDefinition<(&str, &str, &str), &[u8]> = TableDefinition::new("snomed_map_entries");
+// SNOMED_MAP_TARGETS indexes the same rows as (map_target, refset_id, member_id) -> source_code for reverse lookups
+const SNOMED_MAP_TARGETS: TableDefinition<(&str, &str, &str), &str> = TableDefinition::new("snomed_map_targets");
+// AMT_CODES uses composite key (SCTID, code_type) because same SCTID can appear in multiple product types
+const AMT_CODES: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("amt_codes");
+const VALUESETS: TableDefinition<&str, &[u8]> = TableDefinition::new("valuesets");
+const VALUESET_CONCEPTS: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("valueset_concepts");
+// VALUESET_MEMBERS indexes expansion membership as (valueset_url, system, code) for validate-code
+const VALUESET_MEMBERS: TableDefinition<(&str, &str, &str), ()> = TableDefinition::new("valueset_members");
+// VALUESET_COMPOSE holds intensional compose rules (filters, imports, excludes) keyed by ValueSet URL
+const VALUESET_COMPOSE: TableDefinition<&str, &[u8]> = TableDefinition::new("valueset_compose");
+// CODESYSTEMS holds FHIR CodeSystem metadata keyed by URL; CODESYSTEM_CONCEPTS holds their concepts as (system, code)
+const CODESYSTEMS: TableDefinition<&str, &[u8]> = TableDefinition::new("codesystems");
+const CODESYSTEM_CONCEPTS: TableDefinition<(&str, &str), &[u8]> = TableDefinition::new("codesystem_concepts");
+// CONCEPT_MAPS holds ConceptMap metadata and each group's unmapped rule keyed by URL
+const CONCEPT_MAPS: TableDefinition<&str, &[u8]> = TableDefinition::new("concept_maps");
+// CONCEPT_MAP_ELEMENTS holds one row per mapping as (source_system, source_code, map_url, row);
+// CONCEPT_MAP_TARGETS holds the same rows as (target_system, target_code, map_url, row) for reverse translation
+const CONCEPT_MAP_ELEMENTS: TableDefinition<(&str, &str, &str, u64), &[u8]> =
+    TableDefinition::new("concept_map_elements");
+const CONCEPT_MAP_TARGETS: TableDefinition<(&str, &str, &str, u64), &[u8]> =
+    TableDefinition::new("concept_map_targets");
+// LIVE_RELEASES: family -> version id whose content is in the plain tables (0 once it has been deleted)
+const LIVE_RELEASES: TableDefinition<&str, u64> = TableDefinition::new("live_releases");
+// IMPORT_JOBS: job id -> bincode ImportJob; IMPORT_CHECKPOINTS: version id -> bincode ImportCheckpoint
+const IMPORT_JOBS: TableDefinition<u64, &[u8]> = TableDefinition::new("import_jobs");
+const IMPORT_CHECKPOINTS: TableDefinition<u64, &[u8]> = TableDefinition::new("import_checkpoints");
+// SNOMED_DELTA_BASES: delta version id -> id of the SNOMED release it was applied to
+const SNOMED_DELTA_BASES: TableDefinition<u64, u64> = TableDefinition::new("snomed_delta_bases");
+// SCHEMA_META: "version" -> storage schema version the database was last upgraded to
+const SCHEMA_META: TableDefinition<&str, u64> = TableDefinition::new("schema_meta");
+
+/// Storage schema version this build reads and writes
+/// Bump it and add an entry to `MIGRATIONS` whenever a table or a stored record changes layout
+pub const SCHEMA_VERSION: u64 = 4;
+
+/// Schema version of databases created before the version was recorded
+const UNVERSIONED_SCHEMA: u64 = 1;
+
+type Migration = fn(&TerminologyStorage, &redb::WriteTransaction) -> Result<(), StorageError>;
+
+/// Upgrades run in order when an older database is opened: (schema version it upgrades from, what it does, step)
+/// Records are bincode without field names, so a struct change needs a step that decodes the old layout and
+/// rewrites every record. All steps run in one transaction with the new version, so a failure changes nothing
+const MIGRATIONS: &[(u64, &str, Migration)] = &[
+    (1, "index descriptions by concept and ValueSets by member", TerminologyStorage::backfill_indexes),
+    (2, "record the live release of each terminology", TerminologyStorage::record_live_releases),
+    (3, "record the release each SNOMED delta was applied to", TerminologyStorage::record_delta_bases),
+];
+
+/// Terminology families whose releases can be imported side by side
+/// A family's live release uses the plain content tables; every other imported release gets its
+/// own copy of the family's tables (named `<table>@v<version id>`) until it is promoted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFamily {
+    Snomed,
+    Amt,
+    ValueSets,
+}
+
+impl ContentFamily {
+    /// Family of a terminology type (SNOMED snapshot, full and delta releases share one)
+    pub fn of(terminology_type: &str) -> Option<Self> {
+        match terminology_type {
+            "snomed" | "snomed-full" | "snomed-delta" => Some(Self::Snomed),
+            "amt" => Some(Self::Amt),
+            "valuesets" => Some(Self::ValueSets),
+            _ => None,
+        }
+    }
+
+    /// Terminology types whose releases belong to this family
+    pub fn terminology_types(self) -> &'static [&'static str] {
+        match self {
+            Self::Snomed => &["snomed", "snomed-full", "snomed-delta"],
+            Self::Amt => &["amt"],
+            Self::ValueSets => &["valuesets"],
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::Snomed => "snomed",
+            Self::Amt => "amt",
+            Self::ValueSets => "valuesets",
+        }
+    }
+
+    fn table_names(self) -> &'static [&'static str] {
+        match self {
+            Self::Snomed => &[
+                "snomed_concepts",
+                "snomed_descriptions",
+                "snomed_concept_descriptions",
+                "snomed_relationships",
+                "snomed_isa_children",
+                "snomed_closure",
+                "snomed_concept_history",
+                "snomed_description_history",
+                "snomed_language_refset",
+                "snomed_preferred_terms",
+                "snomed_refset_members",
+                "snomed_refsets",
+                "snomed_map_entries",
+                "snomed_map_targets",
+            ],
+            Self::Amt => &["amt_codes"],
+            Self::ValueSets => &[
+                "valuesets",
+                "valueset_concepts",
+                "valueset_members",
+                "valueset_compose",
+                "concept_maps",
+                "concept_map_elements",
+                "concept_map_targets",
+                "codesystems",
+                "codesystem_concepts",
+            ],
+        }
+    }
+}
+
+/// Name of a content table for a side-by-side release
+/// redb table definitions borrow their name, so each name is leaked once (a few per imported release)
+fn release_table_name(table: &str, version_id: u64) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let name = format!("{}@v{}", table, version_id);
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    match names.get(name.as_str()) {
+        Some(existing) => existing,
+        None => {
+            let leaked: &'static str = Box::leak(name.into_boxed_str());
+            names.insert(leaked);
+            leaked
+        }
+    }
+}
+
+/// Name-only handle for renaming and deleting tables (redb only checks the name)
+fn table_handle(name: &str) -> TableDefinition<'_, &'static str, &'static [u8]> {
+    TableDefinition::new(name)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminologyVersion {
+    pub id: u64,
+    pub terminology_type: String,
+    pub version: String,
+    pub effective_date: Option<String>,
+    pub download_url: String,
+    pub file_path: Option<String>,
+    pub downloaded_at: Option<DateTime<Utc>>,
+    pub is_latest: bool,
+    pub created_at: DateTime<Utc>,
+    // NCTS extension fields
+    pub content_item_identifier: Option<String>,
+    pub content_item_version: Option<String>,
+    pub sha256_hash: Option<String>,
+    pub sct_base_version: Option<String>,
+    // Import tracking
+    pub imported: bool,
+    pub imported_at: Option<DateTime<Utc>>,
+}
+
+/// State of an import job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    /// Stopped with `cancel_job`; a SNOMED release resumes from its checkpoint when imported again
+    Cancelled,
+    /// Still running when the process stopped; resumes like a cancelled job
+    Interrupted,
+}
+
+/// An import run, kept so jobs can be listed after they finish or the app restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub id: u64,
+    pub terminology_type: String,
+    pub version_id: u64,
+    pub version: String,
+    pub status: JobStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Import summary, or the error the job failed with
+    pub message: Option<String>,
+    /// Whether the job picked up the checkpoint of an earlier, interrupted run
+    pub resumed: bool,
+}
+
+/// Import work committed for a staged release, so an interrupted import resumes where it stopped
+/// Phases in `completed` are skipped; the first `rows` rows of `phase` are already in the staging tables
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportCheckpoint {
+    pub completed: Vec<String>,
+    pub phase: Option<String>,
+    pub rows: u64,
+}
+
+/// A release moved into the live tables by `TerminologyStorage::promote_release`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Promotion {
+    pub family: ContentFamily,
+    /// The release that was live before, now in its own tables
+    pub demoted: Option<u64>,
+}
+
+/// Record counts and on-disk size of one imported release
+/// Deltas are counted with the live SNOMED release they were applied to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReleaseStats {
+    pub version_id: u64,
+    pub terminology_type: String,
+    pub version: String,
+    /// Whether the release is in the live tables that queries use by default
+    pub live: bool,
+    pub snomed_concepts: u64,
+    pub snomed_descriptions: u64,
+    pub snomed_relationships: u64,
+    pub refset_members: u64,
+    pub amt_codes: u64,
+    pub valuesets: u64,
+    /// Codes listed in ValueSet expansions
+    pub valueset_members: u64,
+    pub codesystems: u64,
+    pub codesystem_concepts: u64,
+    pub concept_maps: u64,
+    /// Rows across all of the release's tables, including derived indexes
+    pub database_records: u64,
+    /// Bytes the release's tables take in the database file
+    pub database_bytes: u64,
+    /// Bytes the release's search index takes on disk (filled in from `TerminologySearch::index_bytes`)
+    pub index_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedConcept {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub definition_status_id: String,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedDescription {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub concept_id: String,
+    pub language_code: String,
+    pub type_id: String,
+    pub term: String,
+    pub case_significance_id: String,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedRelationship {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub source_id: String,
+    pub destination_id: String,
+    pub relationship_group: i32,
+    pub type_id: String,
+    pub characteristic_type_id: String,
+    pub modifier_id: String,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageRefsetMember {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub refset_id: String,
+    pub description_id: String,
+    pub acceptability_id: String,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefsetMember {
+    pub id: String,
+    pub effective_time: String,
+    pub active: bool,
+    pub module_id: String,
+    pub refset_id: String,
+    pub referenced_component_id: String,
+    pub attributes: Vec<String>,
+    pub version_id: u64,
+}
+
+/// An imported reference set: its RF2 type (e.g. "Simple", "Ordered") and distinct active members
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedRefset {
+    pub refset_id: String,
+    pub kind: String,
+    pub member_count: u64,
+    pub version_id: u64,
+}
+
+/// A row of a complex or extended map refset (e.g. SNOMED CT-AU to ICD-10-AM)
+/// Empty RF2 columns are stored as None; map_category_id is only set by extended maps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnomedMapEntry {
+    pub member_id: String,
+    pub refset_id: String,
+    pub source_code: String,
+    pub map_group: u32,
+    pub map_priority: u32,
+    pub map_rule: Option<String>,
+    pub map_advice: Option<String>,
+    pub map_target: Option<String>,
+    pub correlation_id: Option<String>,
+    pub map_category_id: Option<String>,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmtCode {
+    pub id: String,
+    pub preferred_term: String,
+    pub code_type: String,
+    pub parent_code: Option<String>,
+    pub properties: Option<String>,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSet {
+    pub url: String,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    pub version_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSetConcept {
+    pub valueset_url: String,
+    pub system: String,
+    pub code: String,
+    pub display: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeSystem {
+    pub url: String,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    pub content: Option<String>,
+    pub concept_count: u64,
+    pub version_id: u64,
+}
+
+/// A FHIR CodeSystem concept with its direct parents and children
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeSystemConcept {
+    pub system: String,
+    pub code: String,
+    pub display: Option<String>,
+    pub definition: Option<String>,
+    pub parents: Vec<String>,
+    pub children: Vec<String>,
+    pub properties: Vec<ConceptProperty>,
+    pub designations: Vec<ConceptDesignation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMap {
+    pub url: String,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    pub source: Option<String>,
+    pub target: Option<String>,
+    pub groups: Vec<ConceptMapGroupInfo>,
+    pub version_id: u64,
+}
+
+/// Systems and unmapped rule of a ConceptMap group (its elements are stored as rows)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapGroupInfo {
+    pub source: Option<String>,
+    pub target: Option<String>,
+    pub unmapped: Option<ConceptMapUnmapped>,
+}
+
+/// One source code to target mapping of a ConceptMap group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptMapRow {
+    pub map_url: String,
+    pub group: u32,
+    pub source_system: Option<String>,
+    pub source_code: String,
+    pub source_display: Option<String>,
+    pub target_system: Option<String>,
+    pub target_code: Option<String>,
+    pub target_display: Option<String>,
+    pub equivalence: String,
+    pub comment: Option<String>,
+}
+
+pub struct TerminologyStorage {
+    db: Arc<Database>,
+    data_dir: PathBuf,
+    db_path: PathBuf,
+    /// Side-by-side release this view reads and writes, or None for the live tables
+    release: Option<(ContentFamily, u64)>,
+}
+
+impl TerminologyStorage {
+    /// Create a new storage instance with the given database path
+    pub fn new(db_path: PathBuf, data_dir: PathBuf) -> Result<Self, StorageError> {
+        // Ensure parent directory exists
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Ensure data directory exists
+        std::fs::create_dir_all(&data_dir)?;
+
+        // Open or create redb database
+        let db = Database::create(&db_path)?;
+
+        let storage = Self {
+            db: Arc::new(db),
+            data_dir,
+            db_path,
+            release: None,
+        };
+
+        // Initialize tables (redb creates tables lazily, but we can ensure they exist)
+        storage.initialize_tables()?;
+
+        Ok(storage)
+    }
+
+    /// Initialize database tables, upgrading a database written by an older version of the app
+    fn initialize_tables(&self) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        let found = Self::stored_schema_version(&write_txn)?;
+        if found > SCHEMA_VERSION {
+            return Err(StorageError::SchemaTooNew {
+                path: self.db_path.display().to_string(),
+                found,
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        {
+            let _ = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+            let _ = write_txn.open_table(TERMINOLOGY_VERSION_COUNTER)?;
+            let _ = write_txn.open_table(LIVE_RELEASES)?;
+            let _ = write_txn.open_table(IMPORT_JOBS)?;
+            let _ = write_txn.open_table(IMPORT_CHECKPOINTS)?;
+            let _ = write_txn.open_table(SNOMED_DELTA_BASES)?;
+        }
+        self.create_content_tables(&write_txn)?;
+
+        for &(from, step, migrate) in MIGRATIONS.iter().filter(|(from, ..)| *from >= found) {
+            migrate(self, &write_txn).map_err(|e| StorageError::Migration {
+                from,
+                step,
+                reason: e.to_string(),
+            })?;
+        }
+        write_txn.open_table(SCHEMA_META)?.insert("version", SCHEMA_VERSION)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Schema version recorded in the database; a new database is already current
+    fn stored_schema_version(write_txn: &redb::WriteTransaction) -> Result<u64, StorageError> {
+        let mut names = write_txn.list_tables()?.map(|table| table.name().to_string());
+        if !names.any(|name| name == SCHEMA_META.name() || name == TERMINOLOGY_VERSIONS.name()) {
+            return Ok(SCHEMA_VERSION);
+        }
+        let meta = write_txn.open_table(SCHEMA_META)?;
+        let version = meta.get("version")?.map(|v| v.value());
+        Ok(version.unwrap_or(UNVERSIONED_SCHEMA))
+    }
+
+    /// Storage schema version of the open database
+    pub fn schema_version(&self) -> Result<u64, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let meta = read_txn.open_table(SCHEMA_META)?;
+        let version = meta.get("version")?.map(|v| v.value());
+        Ok(version.unwrap_or(UNVERSIONED_SCHEMA))
+    }
+
+    /// Migration from schema v1: fill the secondary indexes of content imported before they existed,
+    /// for the live tables and every side-by-side release
+    /// An index is only rebuilt when it is empty and the table it indexes is not
+    fn backfill_indexes(&self, write_txn: &redb::WriteTransaction) -> Result<(), StorageError> {
+        let release_ids = |table: &str| -> Result<Vec<u64>, StorageError> {
+            let prefix = format!("{}@v", table);
+            Ok(write_txn
+                .list_tables()?
+                .filter_map(|handle| handle.name().strip_prefix(prefix.as_str())?.parse().ok())
+                .collect())
+        };
+
+        let snomed_views = std::iter::once(None)
+            .chain(release_ids(SNOMED_DESCRIPTIONS.name())?.into_iter().map(Some));
+        for release in snomed_views {
+            let view = self.view(release.map(|id| (ContentFamily::Snomed, id)));
+            let mut by_concept = write_txn.open_table(view.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+            let descriptions = write_txn.open_table(view.table(SNOMED_DESCRIPTIONS))?;
+            if by_concept.is_empty()? && !descriptions.is_empty()? {
+                for item in descriptions.iter()? {
+                    let (_, value) = item?;
+                    let desc: SnomedDescription = bincode::deserialize(value.value())?;
+                    by_concept.insert((desc.concept_id.as_str(), desc.id.as_str()), ())?;
+                }
+            }
+        }
+
+        let valueset_views = std::iter::once(None)
+            .chain(release_ids(VALUESET_CONCEPTS.name())?.into_iter().map(Some));
+        for release in valueset_views {
+            let view = self.view(release.map(|id| (ContentFamily::ValueSets, id)));
+            let mut members = write_txn.open_table(view.table(VALUESET_MEMBERS))?;
+            let concepts = write_txn.open_table(view.table(VALUESET_CONCEPTS))?;
+            if members.is_empty()? && !concepts.is_empty()? {
+                for item in concepts.iter()? {
+                    let (_, value) = item?;
+                    let concept: ValueSetConcept = bincode::deserialize(value.value())?;
+                    let key = (concept.valueset_url.as_str(), concept.system.as_str(), concept.code.as_str());
+                    members.insert(key, ())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Migration from schema v2: databases from before side-by-side imports kept no record of the live
+    /// release, which is the most recently imported release of each family
+    fn record_live_releases(&self, write_txn: &redb::WriteTransaction) -> Result<(), StorageError> {
+        let versions = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+        let mut live_releases = write_txn.open_table(LIVE_RELEASES)?;
+        for family in [ContentFamily::Snomed, ContentFamily::Amt, ContentFamily::ValueSets] {
+            if live_releases.get(family.key())?.is_some() {
+                continue;
+            }
+
+            let mut live: Option<TerminologyVersion> = None;
+            for item in versions.iter()? {
+                let (_, value) = item?;
+                let version: TerminologyVersion = bincode::deserialize(value.value())?;
+                let is_release = version.terminology_type != "snomed-delta"
+                    && family.terminology_types().contains(&version.terminology_type.as_str());
+                if is_release && version.imported && live.as_ref().is_none_or(|l| version.imported_at > l.imported_at)
+                {
+                    live = Some(version);
+                }
+            }
+            if let Some(live) = live {
+                live_releases.insert(family.key(), live.id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Migration from schema v3: deltas were applied to the live SNOMED release without recording it
+    /// Deltas imported after the current live release are taken to have been applied to it; older ones
+    /// stay unrecorded, as the release they were applied to can no longer be told apart
+    fn record_delta_bases(&self, write_txn: &redb::WriteTransaction) -> Result<(), StorageError> {
+        let Some(live) = write_txn.open_table(LIVE_RELEASES)?.get(ContentFamily::Snomed.key())?.map(|v| v.value())
+        else {
+            return Ok(());
+        };
+        let versions = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+        let Some(live_imported_at) = versions
+            .get(live)?
+            .map(|value| bincode::deserialize::<TerminologyVersion>(value.value()))
+            .transpose()?
+            .and_then(|version| version.imported_at)
+        else {
+            return Ok(());
+        };
+
+        let mut bases = write_txn.open_table(SNOMED_DELTA_BASES)?;
+        for item in versions.iter()? {
+            let (_, value) = item?;
+            let version: TerminologyVersion = bincode::deserialize(value.value())?;
+            let applied_to_live = version.terminology_type == "snomed-delta"
+                && version.imported
+                && version.imported_at.is_some_and(|at| at > live_imported_at);
+            if applied_to_live && bases.get(version.id)?.is_none() {
+                bases.insert(version.id, live)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the content tables this view reads and writes
+    fn create_content_tables(&self, write_txn: &redb::WriteTransaction) -> Result<(), StorageError> {
+        let _ = write_txn.open_table(self.table(SNOMED_CONCEPTS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_DESCRIPTIONS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_RELATIONSHIPS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_ISA_CHILDREN))?;
+        let _ = write_txn.open_table(self.table(SNOMED_CLOSURE))?;
+        let _ = write_txn.open_table(self.table(SNOMED_CONCEPT_HISTORY))?;
+        let _ = write_txn.open_table(self.table(SNOMED_DESCRIPTION_HISTORY))?;
+        let _ = write_txn.open_table(self.table(SNOMED_LANGUAGE_REFSET))?;
+        let _ = write_txn.open_table(self.table(SNOMED_PREFERRED_TERMS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_REFSET_MEMBERS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_REFSETS))?;
+        let _ = write_txn.open_table(self.table(SNOMED_MAP_ENTRIES))?;
+        let _ = write_txn.open_table(self.table(SNOMED_MAP_TARGETS))?;
+        let _ = write_txn.open_table(self.table(AMT_CODES))?;
+        let _ = write_txn.open_table(self.table(VALUESETS))?;
+        let _ = write_txn.open_table(self.table(VALUESET_CONCEPTS))?;
+        let _ = write_txn.open_table(self.table(VALUESET_MEMBERS))?;
+        let _ = write_txn.open_table(self.table(VALUESET_COMPOSE))?;
+        let _ = write_txn.open_table(self.table(CONCEPT_MAPS))?;
+        let _ = write_txn.open_table(self.table(CONCEPT_MAP_ELEMENTS))?;
+        let _ = write_txn.open_table(self.table(CONCEPT_MAP_TARGETS))?;
+        let _ = write_txn.open_table(self.table(CODESYSTEMS))?;
+        let _ = write_txn.open_table(self.table(CODESYSTEM_CONCEPTS))?;
+        Ok(())
+    }
+
+    /// Resolve a content table for this view: a side-by-side release's own copy of its family's
+    /// tables, or the plain (live) table otherwise
+    pub fn table<K: redb::Key + 'static, V: redb::Value + 'static>(
+        &self,
+        definition: TableDefinition<'static, K, V>,
+    ) -> TableDefinition<'static, K, V> {
+        match self.release {
+            Some((family, version_id)) if family.table_names().contains(&definition.name()) => {
+                TableDefinition::new(release_table_name(definition.name(), version_id))
+            }
+            _ => definition,
+        }
+    }
+
+    fn view(&self, release: Option<(ContentFamily, u64)>) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            data_dir: self.data_dir.clone(),
+            db_path: self.db_path.clone(),
+            release,
+        }
+    }
+
+    /// Version id of the side-by-side release this view is scoped to (None for the live tables)
+    pub fn release(&self) -> Option<u64> {
+        self.release.map(|(_, version_id)| version_id)
+    }
+
+    /// Get the version whose content is in a family's live tables
+    pub fn live_release(&self, family: ContentFamily) -> Result<Option<u64>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(LIVE_RELEASES)?;
+        let version_id = table.get(family.key())?.map(|v| v.value());
+        Ok(version_id.filter(|&id| id != 0))
+    }
+
+    /// Record which version's content is in a family's live tables
+    pub fn set_live_release(&self, family: ContentFamily, version_id: Option<u64>) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(LIVE_RELEASES)?;
+            table.insert(family.key(), version_id.unwrap_or(0))?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Whether a version's content is (or, before import, will be) kept in its own tables
+    /// beside the live release. Deltas always apply to the live release
+    pub fn is_side_by_side(&self, version: &TerminologyVersion) -> Result<bool, StorageError> {
+        let Some(family) = ContentFamily::of(&version.terminology_type) else {
+            return Ok(false);
+        };
+        if version.terminology_type == "snomed-delta" {
+            return Ok(false);
+        }
+
+        let live = self.live_release(family)?;
+        Ok(if version.imported {
+            live != Some(version.id)
+        } else {
+            live.is_some_and(|live| live != version.id)
+        })
+    }
+
+    /// Record counts and database size of every imported release, live ones first
+    /// Table lengths are kept by redb, so this reads table metadata rather than rows
+    pub fn release_stats(&self) -> Result<Vec<ReleaseStats>, StorageError> {
+        let mut stats = Vec::new();
+        for family in [ContentFamily::Snomed, ContentFamily::Amt, ContentFamily::ValueSets] {
+            for terminology_type in family.terminology_types() {
+                if *terminology_type == "snomed-delta" {
+                    continue;
+                }
+                for version in self.get_all_versions(terminology_type)? {
+                    if !version.imported {
+                        continue;
+                    }
+                    let view = self.release_view(&version)?;
+                    stats.push(view.table_stats(family, &version)?);
+                }
+            }
+        }
+
+        stats.sort_by_key(|s| (!s.live, s.version_id));
+        Ok(stats)
+    }
+
+    /// Counts and sizes of a family's tables in this view
+    fn table_stats(&self, family: ContentFamily, version: &TerminologyVersion) -> Result<ReleaseStats, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let mut stats = ReleaseStats {
+            version_id: version.id,
+            terminology_type: version.terminology_type.clone(),
+            version: version.version.clone(),
+            live: self.release.is_none(),
+            ..Default::default()
+        };
+
+        for &name in family.table_names() {
+            let table_name = match self.release {
+                Some((_, version_id)) => release_table_name(name, version_id),
+                None => name,
+            };
+            // A release imported before a table was added has no copy of it
+            let table = match read_txn.open_untyped_table(table_handle(table_name)) {
+                Ok(table) => table,
+                Err(redb::TableError::TableDoesNotExist(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let records = table.len()?;
+            let table_stats = table.stats()?;
+            stats.database_records += records;
+            stats.database_bytes += table_stats.stored_bytes() + table_stats.metadata_bytes();
+
+            let count = match name {
+                "snomed_concepts" => &mut stats.snomed_concepts,
+                "snomed_descriptions" => &mut stats.snomed_descriptions,
+                "snomed_relationships" => &mut stats.snomed_relationships,
+                "snomed_refset_members" => &mut stats.refset_members,
+                "amt_codes" => &mut stats.amt_codes,
+                "valuesets" => &mut stats.valuesets,
+                "valueset_members" => &mut stats.valueset_members,
+                "codesystems" => &mut stats.codesystems,
+                "codesystem_concepts" => &mut stats.codesystem_concepts,
+                "concept_maps" => &mut stats.concept_maps,
+                _ => continue,
+            };
+            *count = records;
+        }
+
+        Ok(stats)
+    }
+
+    /// Storage view for reading or importing a release: its own tables when it sits beside the
+    /// live release, the live tables otherwise
+    pub fn release_view(&self, version: &TerminologyVersion) -> Result<Self, StorageError> {
+        if !self.is_side_by_side(version)? {
+            return Ok(self.view(None));
+        }
+        let family = ContentFamily::of(&version.terminology_type)
+            .ok_or_else(|| StorageError::NotFound(format!("Unknown terminology type: {}", version.terminology_type)))?;
+
+        let view = self.view(Some((family, version.id)));
+        if !version.imported {
+            let write_txn = self.db.begin_write()?;
+            view.create_content_tables(&write_txn)?;
+            write_txn.commit()?;
+        }
+        Ok(view)
+    }
+
+    /// Staging tables to import a release into: the release's own copy of its family's tables. They are
+    /// kept when an interrupted import left a checkpoint to resume from, and cleared of anything a failed
+    /// attempt left behind otherwise. Nothing else is touched until the staged release is validated and,
+    /// if there is no live release, promoted into the live tables
+    /// A delta is staged against the live SNOMED release, which is recorded as its base: the importer
+    /// copies the live tables in (see `copy_live_tables`) and `promote_staged_delta` swaps them back
+    pub fn stage_release(&self, version: &TerminologyVersion) -> Result<Self, StorageError> {
+        let family = ContentFamily::of(&version.terminology_type)
+            .ok_or_else(|| StorageError::NotFound(format!("{} releases cannot be staged", version.terminology_type)))?;
+        if version.imported {
+            return Err(StorageError::NotFound(format!(
+                "{} version {} is already imported",
+                version.terminology_type, version.version
+            )));
+        }
+        let delta_base = if version.terminology_type == "snomed-delta" {
+            Some(self.live_release(ContentFamily::Snomed)?.ok_or_else(|| {
+                StorageError::NotFound("No SNOMED CT release imported yet to apply the delta to".to_string())
+            })?)
+        } else {
+            None
+        };
+
+        // A delta staged against a release that is no longer live starts again from the live one
+        let resuming = self.get_import_checkpoint(version.id)?.is_some()
+            && (delta_base.is_none() || self.delta_base(version.id)? == delta_base);
+        let view = self.view(Some((family, version.id)));
+        let write_txn = self.db.begin_write()?;
+        if !resuming {
+            view.delete_release_tables(&write_txn)?;
+            write_txn.open_table(IMPORT_CHECKPOINTS)?.remove(version.id)?;
+        }
+        view.create_content_tables(&write_txn)?;
+        if let Some(base) = delta_base {
+            write_txn.open_table(SNOMED_DELTA_BASES)?.insert(version.id, base)?;
+        }
+        write_txn.commit()?;
+        Ok(view)
+    }
+
+    /// Copy the live release's tables into this staging view, so a delta can be applied to the copy
+    /// Each table is copied in its own transaction; returns the number of rows copied (0 for the live view)
+    pub fn copy_live_tables(&self) -> Result<usize, StorageError> {
+        let Some((family, _)) = self.release else {
+            return Ok(0);
+        };
+
+        let copied = match family {
+            ContentFamily::Snomed => {
+                self.copy_live_table(SNOMED_CONCEPTS)?
+                    + self.copy_live_table(SNOMED_DESCRIPTIONS)?
+                    + self.copy_live_table(SNOMED_CONCEPT_DESCRIPTIONS)?
+                    + self.copy_live_table(SNOMED_RELATIONSHIPS)?
+                    + self.copy_live_table(SNOMED_ISA_CHILDREN)?
+                    + self.copy_live_table(SNOMED_CLOSURE)?
+                    + self.copy_live_table(SNOMED_CONCEPT_HISTORY)?
+                    + self.copy_live_table(SNOMED_DESCRIPTION_HISTORY)?
+                    + self.copy_live_table(SNOMED_LANGUAGE_REFSET)?
+                    + self.copy_live_table(SNOMED_PREFERRED_TERMS)?
+                    + self.copy_live_table(SNOMED_REFSET_MEMBERS)?
+                    + self.copy_live_table(SNOMED_REFSETS)?
+                    + self.copy_live_table(SNOMED_MAP_ENTRIES)?
+                    + self.copy_live_table(SNOMED_MAP_TARGETS)?
+            }
+            ContentFamily::Amt => self.copy_live_table(AMT_CODES)?,
+            ContentFamily::ValueSets => {
+                self.copy_live_table(VALUESETS)?
+                    + self.copy_live_table(VALUESET_CONCEPTS)?
+                    + self.copy_live_table(VALUESET_MEMBERS)?
+                    + self.copy_live_table(VALUESET_COMPOSE)?
+                    + self.copy_live_table(CONCEPT_MAPS)?
+                    + self.copy_live_table(CONCEPT_MAP_ELEMENTS)?
+                    + self.copy_live_table(CONCEPT_MAP_TARGETS)?
+                    + self.copy_live_table(CODESYSTEMS)?
+                    + self.copy_live_table(CODESYSTEM_CONCEPTS)?
+            }
+        };
+        Ok(copied)
+    }
+
+    /// Replace this view's copy of a table with the rows of the live table
+    fn copy_live_table<K: redb::Key + 'static, V: redb::Value + 'static>(
+        &self,
+        definition: TableDefinition<'static, K, V>,
+    ) -> Result<usize, StorageError> {
+        let write_txn = self.db.begin_write()?;
+        write_txn.delete_table(self.table(definition))?;
+        let mut copied = 0;
+        {
+            let live = write_txn.open_table(definition)?;
+            let mut table = write_txn.open_table(self.table(definition))?;
+            for item in live.iter()? {
+                let (key, value) = item?;
+                table.insert(key.value(), value.value())?;
+                copied += 1;
+            }
+        }
+        write_txn.commit()?;
+        Ok(copied)
+    }
+
+    /// Replace the live SNOMED tables with this staged delta's tables (see `stage_release`) and mark the
+    /// delta imported, in one transaction. Fails, leaving the live tables alone, if the release the delta
+    /// was staged against is no longer live
+    pub fn promote_staged_delta(&self) -> Result<(), StorageError> {
+        let Some((family, id)) = self.release else {
+            return Err(StorageError::NotFound("Only a staged delta can be promoted".to_string()));
+        };
+        let write_txn = self.db.begin_write()?;
+        let base = write_txn.open_table(SNOMED_DELTA_BASES)?.get(id)?.map(|v| v.value());
+        let live = write_txn.open_table(LIVE_RELEASES)?.get(family.key())?.map(|v| v.value());
+        if base.is_none() || live != base {
+            return Err(StorageError::NotFound(
+                "The live SNOMED CT release changed while the delta was staged; import the delta again".to_string(),
+            ));
+        }
+
+        for &name in family.table_names() {
+            write_txn.delete_table(table_handle(name))?;
+            write_txn.rename_table(table_handle(release_table_name(name, id)), table_handle(name))?;
+        }
+        Self::set_imported(&write_txn, id)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Drop the tables and checkpoint of a staged release whose import failed
+    pub fn discard_staged(&self) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        self.delete_release_tables(&write_txn)?;
+        if let Some(version_id) = self.release() {
+            write_txn.open_table(IMPORT_CHECKPOINTS)?.remove(version_id)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Discard the staged imports of a family's other releases that never finished, so abandoned
+    /// checkpoints do not keep their tables around once a different release is imported
+    /// Returns the version ids whose staging was dropped (their staging search indexes go too)
+    pub fn discard_stale_staging(&self, version: &TerminologyVersion) -> Result<Vec<u64>, StorageError> {
+        let Some(family) = ContentFamily::of(&version.terminology_type) else {
+            return Ok(Vec::new());
+        };
+
+        let mut stale = Vec::new();
+        for version_id in self.checkpointed_versions()? {
+            let Some(other) = self.get_version(version_id)? else {
+                continue;
+            };
+            if other.id != version.id && !other.imported && ContentFamily::of(&other.terminology_type) == Some(family) {
+                self.view(Some((family, other.id))).discard_staged()?;
+                stale.push(other.id);
+            }
+        }
+        Ok(stale)
+    }
+
+    fn checkpointed_versions(&self) -> Result<Vec<u64>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(IMPORT_CHECKPOINTS)?;
+        let mut version_ids = Vec::new();
+        for item in table.iter()? {
+            let (key, _) = item?;
+            version_ids.push(key.value());
+        }
+        Ok(version_ids)
+    }
+
+    /// Get the checkpoint an interrupted import of a version left behind
+    pub fn get_import_checkpoint(&self, version_id: u64) -> Result<Option<ImportCheckpoint>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(IMPORT_CHECKPOINTS)?;
+        match table.get(version_id)? {
+            Some(value) => Ok(Some(bincode::deserialize(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a new running import job for a version
+    pub fn create_job(&self, version: &TerminologyVersion) -> Result<ImportJob, StorageError> {
+        let resumed = self.get_import_checkpoint(version.id)?.is_some();
+        let write_txn = self.db.begin_write()?;
+        let job = {
+            let mut counter = write_txn.open_table(TERMINOLOGY_VERSION_COUNTER)?;
+            let id = counter.get("import_jobs")?.map(|v| v.value()).unwrap_or(0) + 1;
+            counter.insert("import_jobs", id)?;
+
+            let job = ImportJob {
+                id,
+                terminology_type: version.terminology_type.clone(),
+                version_id: version.id,
+                version: version.version.clone(),
+                status: JobStatus::Running,
+                started_at: Utc::now(),
+                finished_at: None,
+                message: None,
+                resumed,
+            };
+            let bytes = bincode::serialize(&job)?;
+            write_txn.open_table(IMPORT_JOBS)?.insert(id, bytes.as_slice())?;
+            job
+        };
+        write_txn.commit()?;
+        Ok(job)
+    }
+
+    /// Update a job's status and message, setting its finish time once it stops running
+    pub fn finish_job(&self, id: u64, status: JobStatus, message: Option<String>) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(IMPORT_JOBS)?;
+            let job = table.get(id)?.map(|value| bincode::deserialize::<ImportJob>(value.value())).transpose()?;
+            if let Some(mut job) = job {
+                job.status = status;
+                job.message = message;
+                job.finished_at = (status != JobStatus::Running).then(Utc::now);
+                let bytes = bincode::serialize(&job)?;
+                table.insert(id, bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get all import jobs, newest first
+    pub fn get_jobs(&self) -> Result<Vec<ImportJob>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(IMPORT_JOBS)?;
+        let mut jobs = Vec::new();
+        for item in table.iter()?.rev() {
+            let (_, value) = item?;
+            jobs.push(bincode::deserialize(value.value())?);
+        }
+        Ok(jobs)
+    }
+
+    /// Check that a staged release holds the content its family needs before it is marked imported
+    pub fn validate_staged(&self, version: &TerminologyVersion) -> Result<ReleaseStats, StorageError> {
+        let family = ContentFamily::of(&version.terminology_type)
+            .ok_or_else(|| StorageError::NotFound(format!("Unknown terminology type: {}", version.terminology_type)))?;
+        let stats = self.table_stats(family, version)?;
+
+        let unclosed_hierarchy = if family == ContentFamily::Snomed {
+            let read_txn = self.db.begin_read()?;
+            let isa_children = read_txn.open_table(self.table(SNOMED_ISA_CHILDREN))?.len()?;
+            isa_children > 0 && read_txn.open_table(self.table(SNOMED_CLOSURE))?.is_empty()?
+        } else {
+            false
+        };
+
+        let missing = match family {
+            ContentFamily::Snomed if stats.snomed_concepts == 0 => Some("concepts"),
+            ContentFamily::Snomed if stats.snomed_descriptions == 0 => Some("descriptions"),
+            ContentFamily::Snomed if unclosed_hierarchy => Some("is-a transitive closure"),
+            ContentFamily::Amt if stats.amt_codes == 0 => Some("AMT codes"),
+            ContentFamily::ValueSets if stats.valuesets + stats.codesystems + stats.concept_maps == 0 => {
+                Some("ValueSets, CodeSystems or ConceptMaps")
+            }
+            _ => None,
+        };
+        match missing {
+            Some(what) => Err(StorageError::IncompleteRelease(format!(
+                "{} version {} has no {}",
+                version.terminology_type, version.version, what
+            ))),
+            None => Ok(stats),
+        }
+    }
+
+    /// Storage view for a query's optional version selector: the local version id, the release
+    /// version or its NCTS content item version. No selector means the live release
+    pub fn select_release(&self, family: ContentFamily, selector: Option<&str>) -> Result<Self, StorageError> {
+        let Some(selector) = selector else {
+            return Ok(self.view(None));
+        };
+
+        for terminology_type in family.terminology_types() {
+            for version in self.get_all_versions(terminology_type)? {
+                let matches = version.id.to_string() == selector
+                    || version.version == selector
+                    || version.content_item_version.as_deref() == Some(selector);
+                if matches && version.imported {
+                    return self.release_view(&version);
+                }
+            }
+        }
+
+        Err(StorageError::NotFound(format!(
+            "No imported {} release matches version '{}'",
+            family.key(),
+            selector
+        )))
+    }
+
+    /// Get a version record by id
+    pub fn get_version(&self, id: u64) -> Result<Option<TerminologyVersion>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+        match table.get(id)? {
+            Some(value) => Ok(Some(bincode::deserialize(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get next version ID (auto-increment)
+    fn next_version_id(&self, write_txn: &redb::WriteTransaction) -> Result<u64, StorageError> {
+        let mut table = write_txn.open_table(TERMINOLOGY_VERSION_COUNTER)?;
+        let current = table.get("counter")?.map(|v| v.value()).unwrap_or(0);
+        let next = current + 1;
+        table.insert("counter", next)?;
+        Ok(next)
+    }
+
+    /// Record a new terminology version with NCTS metadata
+    pub fn record_version(
+        &self,
+        terminology_type: &str,
+        version: &str,
+        effective_date: Option<&str>,
+        download_url: &str,
+        content_item_identifier: Option<&str>,
+        content_item_version: Option<&str>,
+        sha256_hash: Option<&str>,
+        sct_base_version: Option<&str>,
+    ) -> Result<u64, StorageError> {
+        let write_txn = self.db.begin_write()?;
+
+        let version_id = {
+            let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+            // Check if version already exists (using the already-opened table)
+            let existing = Self::find_version_in_table(&table, terminology_type, version)?;
+
+            if let Some(existing_version) = existing {
+                // Update existing
+                let mut updated = existing_version;
+                updated.download_url = download_url.to_string();
+                updated.effective_date = effective_date.map(|s| s.to_string());
+                updated.content_item_identifier = content_item_identifier.map(|s| s.to_string());
+                updated.content_item_version = content_item_version.map(|s| s.to_string());
+                updated.sha256_hash = sha256_hash.map(|s| s.to_string());
+                updated.sct_base_version = sct_base_version.map(|s| s.to_string());
+
+                let bytes = bincode::serialize(&updated)?;
+                table.insert(updated.id, bytes.as_slice())?;
+                updated.id
+            } else {
+                // Create new - need to open counter table separately
+                drop(table); // Release the table lock temporarily
+                let id = self.next_version_id(&write_txn)?;
+                let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+                let new_version = TerminologyVersion {
+                    id,
+                    terminology_type: terminology_type.to_string(),
+                    version: version.to_string(),
+                    effective_date: effective_date.map(|s| s.to_string()),
+                    download_url: download_url.to_string(),
+                    file_path: None,
+                    downloaded_at: None,
+                    is_latest: false,
+                    created_at: Utc::now(),
+                    content_item_identifier: content_item_identifier.map(|s| s.to_string()),
+                    content_item_version: content_item_version.map(|s| s.to_string()),
+                    sha256_hash: sha256_hash.map(|s| s.to_string()),
+                    sct_base_version: sct_base_version.map(|s| s.to_string()),
+                    imported: false,
+                    imported_at: None,
+                };
+
+                let bytes = bincode::serialize(&new_version)?;
+                table.insert(id, bytes.as_slice())?;
+                id
+            }
+        };
+
+        write_txn.commit()?;
+        Ok(version_id)
+    }
+
+    /// Find version by terminology type and version string in an already-opened table
+    fn find_version_in_table(
+        table: &redb::Table<u64, &[u8]>,
+        terminology_type: &str,
+        version: &str,
+    ) -> Result<Option<TerminologyVersion>, StorageError> {
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let ver: TerminologyVersion = bincode::deserialize(value.value())?;
+            if ver.terminology_type == terminology_type && ver.version == version {
+                return Ok(Some(ver));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Mark a version as downloaded and set its file path
+    pub fn mark_downloaded(&self, id: u64, file_path: &str) -> Result<(), StorageError> {
+        if file_path.is_empty() {
+            return self.clear_downloaded(id);
+        }
+
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+            let version_bytes = match table.get(id)? {
+                Some(value) => {
+                    let bytes = value.value().to_vec();
+                    drop(value);
+                    Some(bytes)
+                }
+                None => None,
+            };
+
+            if let Some(bytes) = version_bytes {
+                let mut version: TerminologyVersion = bincode::deserialize(&bytes)?;
+                version.file_path = Some(file_path.to_string());
+                version.downloaded_at = Some(Utc::now());
+
+                let new_bytes = bincode::serialize(&version)?;
+                table.insert(id, new_bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Clear download metadata (file_path and downloaded_at) for a version
+    pub fn clear_downloaded(&self, id: u64) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+            let version_bytes = match table.get(id)? {
+                Some(value) => {
+                    let bytes = value.value().to_vec();
+                    drop(value);
+                    Some(bytes)
+                }
+                None => None,
+            };
+
+            if let Some(bytes) = version_bytes {
+                let mut version: TerminologyVersion = bincode::deserialize(&bytes)?;
+                version.file_path = None;
+                version.downloaded_at = None;
+
+                let new_bytes = bincode::serialize(&version)?;
+                table.insert(id, new_bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Mark a version as the latest for its terminology type
+    /// Only the flag changes; an imported release is made live with `promote_release`
+    pub fn mark_as_latest(&self, id: u64, terminology_type: &str) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        Self::set_latest(&write_txn, id, terminology_type)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Make an imported side-by-side release live and mark it the latest, in one transaction: its tables
+    /// become the live tables and the previous live release moves into its own tables
+    /// Returns None when nothing was promoted (the release is already live, not imported, or a delta),
+    /// in which case only the latest flag changes
+    pub fn promote_release(&self, id: u64) -> Result<Option<Promotion>, StorageError> {
+        let write_txn = self.db.begin_write()?;
+        let version: TerminologyVersion = match write_txn.open_table(TERMINOLOGY_VERSIONS)?.get(id)? {
+            Some(value) => bincode::deserialize(value.value())?,
+            None => return Err(StorageError::NotFound(format!("Unknown version id: {}", id))),
+        };
+
+        let family = ContentFamily::of(&version.terminology_type).filter(|_| version.terminology_type != "snomed-delta");
+        let promotion = match family {
+            Some(family) if version.imported => {
+                let live = write_txn.open_table(LIVE_RELEASES)?.get(family.key())?.map(|v| v.value());
+                let demoted = live.filter(|&live| live != 0);
+                (demoted != Some(id)).then_some(Promotion { family, demoted })
+            }
+            _ => None,
+        };
+
+        if let Some(Promotion { family, demoted }) = promotion {
+            // A release imported before a table was added has no copy of it yet
+            self.view(Some((family, id))).create_content_tables(&write_txn)?;
+            for &name in family.table_names() {
+                match demoted {
+                    Some(demoted) => {
+                        write_txn.rename_table(table_handle(name), table_handle(release_table_name(name, demoted)))?
+                    }
+                    None => {
+                        write_txn.delete_table(table_handle(name))?;
+                    }
+                }
+                write_txn.rename_table(table_handle(release_table_name(name, id)), table_handle(name))?;
+            }
+            write_txn.open_table(LIVE_RELEASES)?.insert(family.key(), id)?;
+        }
+        Self::set_latest(&write_txn, id, &version.terminology_type)?;
+        write_txn.commit()?;
+        Ok(promotion)
+    }
+
+    /// Flag one version as the latest of its terminology type and unflag the others
+    fn set_latest(write_txn: &redb::WriteTransaction, id: u64, terminology_type: &str) -> Result<(), StorageError> {
+        let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+        let mut updates = Vec::new();
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let mut version: TerminologyVersion = bincode::deserialize(value.value())?;
+            if version.terminology_type == terminology_type {
+                version.is_latest = version.id == id;
+                updates.push((key.value(), version));
+            }
+        }
+
+        for (key, version) in updates {
+            let bytes = bincode::serialize(&version)?;
+            table.insert(key, bytes.as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Get the latest version for a terminology type
+    pub fn get_latest(&self, terminology_type: &str) -> Result<Option<TerminologyVersion>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let version: TerminologyVersion = bincode::deserialize(value.value())?;
+
+            if version.terminology_type == terminology_type && version.is_latest {
+                return Ok(Some(version));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get all versions for a terminology type
+    pub fn get_all_versions(&self, terminology_type: &str) -> Result<Vec<TerminologyVersion>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+        let mut versions = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let version: TerminologyVersion = bincode::deserialize(value.value())?;
+
+            if version.terminology_type == terminology_type {
+                versions.push(version);
+            }
+        }
+
+        // Sort by created_at descending
+        versions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        Ok(versions)
+    }
+
+    /// Get all latest versions across all terminology types
+    pub fn get_all_latest(&self) -> Result<Vec<TerminologyVersion>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+        let mut versions = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let version: TerminologyVersion = bincode::deserialize(value.value())?;
+
+            if version.is_latest {
+                versions.push(version);
+            }
+        }
+
+        // Sort by terminology_type
+        versions.sort_by(|a, b| a.terminology_type.cmp(&b.terminology_type));
+
+        Ok(versions)
+    }
+
+    /// Get the SNOMED CT release in the live tables, or the most recent delta applied to it
+    /// This is the release the live SNOMED content currently reflects
+    pub fn get_imported_snomed_release(&self) -> Result<Option<TerminologyVersion>, StorageError> {
+        let Some(live) = self.live_release(ContentFamily::Snomed)? else {
+            return Ok(None);
+        };
+
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TERMINOLOGY_VERSIONS)?;
+        let bases = read_txn.open_table(SNOMED_DELTA_BASES)?;
+
+        let mut current: Option<TerminologyVersion> = None;
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let version: TerminologyVersion = bincode::deserialize(value.value())?;
+
+            // A delta applied to a release that has since been demoted moved out of the live tables with it
+            let is_live = version.id == live
+                || (version.terminology_type == "snomed-delta"
+                    && bases.get(version.id)?.map(|base| base.value()) == Some(live));
+            if is_live && version.imported && current.as_ref().is_none_or(|c| version.imported_at > c.imported_at) {
+                current = Some(version);
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// Whether this view's SNOMED release is an RF2 Full import, i.e. its history is complete enough for point-in-time queries
+    /// A staged delta has the history of the release it is applied to
+    pub fn has_snomed_full_release(&self) -> Result<bool, StorageError> {
+        let release = match self.release {
+            Some((ContentFamily::Snomed, version_id)) => Some(version_id),
+            _ => self.live_release(ContentFamily::Snomed)?,
+        };
+        let Some(mut version) = release.map(|id| self.get_version(id)).transpose()?.flatten() else {
+            return Ok(false);
+        };
+        if version.terminology_type == "snomed-delta" {
+            match self.delta_base(version.id)?.map(|id| self.get_version(id)).transpose()?.flatten() {
+                Some(base) => version = base,
+                None => return Ok(false),
+            }
+        }
+
+        Ok(version.terminology_type == "snomed-full")
+    }
+
+    /// Get the id of the SNOMED release a delta was applied to
+    pub fn delta_base(&self, delta_id: u64) -> Result<Option<u64>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SNOMED_DELTA_BASES)?;
+        Ok(table.get(delta_id)?.map(|v| v.value()))
+    }
+
+    /// Mark a version as imported
+    pub fn mark_imported(&self, id: u64) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        Self::set_imported(&write_txn, id)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Mark a version as imported and drop its import checkpoint in a write transaction
+    fn set_imported(write_txn: &redb::WriteTransaction, id: u64) -> Result<(), StorageError> {
+        {
+            let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+            let version_bytes = match table.get(id)? {
+                Some(value) => {
+                    let bytes = value.value().to_vec();
+                    drop(value);
+                    Some(bytes)
+                }
+                None => None,
+            };
+
+            if let Some(bytes) = version_bytes {
+                let mut version: TerminologyVersion = bincode::deserialize(&bytes)?;
+                version.imported = true;
+                version.imported_at = Some(Utc::now());
+
+                let new_bytes = bincode::serialize(&version)?;
+                table.insert(id, new_bytes.as_slice())?;
+            }
+        }
+        write_txn.open_table(IMPORT_CHECKPOINTS)?.remove(id)?;
+        Ok(())
+    }
+
+    /// Insert a SNOMED concept
+    pub fn insert_snomed_concept(&self, concept: &SnomedConcept) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.table(SNOMED_CONCEPTS))?;
+            let bytes = bincode::serialize(concept)?;
+            table.insert(concept.id.as_str(), bytes.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Insert a SNOMED description
+    pub fn insert_snomed_description(&self, description: &SnomedDescription) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.table(SNOMED_DESCRIPTIONS))?;
+            let bytes = bincode::serialize(description)?;
+            table.insert(description.id.as_str(), bytes.as_slice())?;
+
+            let mut by_concept = write_txn.open_table(self.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+            by_concept.insert((description.concept_id.as_str(), description.id.as_str()), ())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get a SNOMED concept by ID
+    pub fn get_snomed_concept(&self, id: &str) -> Result<Option<SnomedConcept>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_CONCEPTS))?;
+
+        if let Some(value) = table.get(id)? {
+            let concept: SnomedConcept = bincode::deserialize(value.value())?;
+            Ok(Some(concept))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get all descriptions for a SNOMED concept
+    pub fn get_snomed_descriptions(&self, concept_id: &str) -> Result<Vec<SnomedDescription>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let by_concept = read_txn.open_table(self.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+        let table = read_txn.open_table(self.table(SNOMED_DESCRIPTIONS))?;
+
+        let mut descriptions = Vec::new();
+        Self::collect_descriptions(&by_concept, &table, concept_id, &mut descriptions)?;
+        Ok(descriptions)
+    }
+
+    /// Append a concept's descriptions, found through the (concept_id, description_id) index
+    fn collect_descriptions(
+        by_concept: &impl ReadableTable<(&'static str, &'static str), ()>,
+        descriptions: &impl ReadableTable<&'static str, &'static [u8]>,
+        concept_id: &str,
+        out: &mut Vec<SnomedDescription>,
+    ) -> Result<(), StorageError> {
+        for item in by_concept.range((concept_id, "")..)? {
+            let (key, _) = item?;
+            let (concept, description_id) = key.value();
+            if concept != concept_id {
+                break;
+            }
+            if let Some(value) = descriptions.get(description_id)? {
+                out.push(bincode::deserialize(value.value())?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get a SNOMED concept's state on a date (YYYYMMDD): its latest row with effectiveTime on or before it
+    pub fn get_snomed_concept_as_of(&self, id: &str, as_of: &str) -> Result<Option<SnomedConcept>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_CONCEPT_HISTORY))?;
+
+        match table.range((id, "")..=(id, as_of))?.next_back() {
+            Some(item) => {
+                let (_, value) = item?;
+                Ok(Some(bincode::deserialize(value.value())?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get the descriptions of a SNOMED concept as they stood on a date (YYYYMMDD)
+    /// Descriptions created after the date are omitted; inactive ones are returned with active = false
+    pub fn get_snomed_descriptions_as_of(
+        &self,
+        concept_id: &str,
+        as_of: &str,
+    ) -> Result<Vec<SnomedDescription>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_DESCRIPTION_HISTORY))?;
+
+        // Rows are ordered by description id then effectiveTime, so the last qualifying row per id wins
+        let mut descriptions: Vec<SnomedDescription> = Vec::new();
+        for item in table.range((concept_id, "", "")..)? {
+            let (key, value) = item?;
+            let (concept, description_id, effective_time) = key.value();
+            if concept != concept_id {
+                break;
+            }
+            if effective_time > as_of {
+                continue;
+            }
+
+            let desc: SnomedDescription = bincode::deserialize(value.value())?;
+            match descriptions.last_mut() {
+                Some(last) if last.id == description_id => *last = desc,
+                _ => descriptions.push(desc),
+            }
+        }
+
+        Ok(descriptions)
+    }
+
+    /// Get the acceptability ids of descriptions in a language refset (description id -> acceptability id)
+    /// Descriptions without an active member are left out
+    pub fn get_snomed_acceptability(
+        &self,
+        descriptions: &[SnomedDescription],
+        refset_id: &str,
+    ) -> Result<HashMap<String, String>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_LANGUAGE_REFSET))?;
+
+        let mut acceptability = HashMap::new();
+        for desc in descriptions {
+            if let Some(value) = table.get((desc.id.as_str(), refset_id))? {
+                let member: LanguageRefsetMember = bincode::deserialize(value.value())?;
+                if member.active {
+                    acceptability.insert(desc.id.clone(), member.acceptability_id);
+                }
+            }
+        }
+
+        Ok(acceptability)
+    }
+
+    /// Get the en-AU preferred terms of a set of SNOMED concepts (concept id -> term)
+    /// Concepts without a preferred synonym in the language refset are left out
+    pub fn get_snomed_preferred_terms<'c>(
+        &self,
+        concept_ids: impl IntoIterator<Item = &'c str>,
+    ) -> Result<HashMap<String, String>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_PREFERRED_TERMS))?;
+
+        let mut terms = HashMap::new();
+        for concept_id in concept_ids {
+            if let Some(value) = table.get(concept_id)? {
+                let desc: SnomedDescription = bincode::deserialize(value.value())?;
+                terms.insert(concept_id.to_string(), desc.term);
+            }
+        }
+
+        Ok(terms)
+    }
+
+    /// Get every imported reference set
+    pub fn get_snomed_refsets(&self) -> Result<Vec<SnomedRefset>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_REFSETS))?;
+
+        let mut refsets = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            refsets.push(bincode::deserialize(value.value())?);
+        }
+
+        Ok(refsets)
+    }
+
+    /// Get an imported reference set by id
+    pub fn get_snomed_refset(&self, refset_id: &str) -> Result<Option<SnomedRefset>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_REFSETS))?;
+
+        match table.get(refset_id)? {
+            Some(value) => Ok(Some(bincode::deserialize(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the components with an active row in a reference set
+    pub fn get_refset_member_ids(&self, refset_id: &str) -> Result<HashSet<String>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_REFSET_MEMBERS))?;
+
+        let mut members = HashSet::new();
+        for item in table.range((refset_id, "", "")..)? {
+            let (key, value) = item?;
+            let (refset, component_id, _) = key.value();
+            if refset != refset_id {
+                break;
+            }
+            if members.contains(component_id) {
+                continue;
+            }
+
+            let member: RefsetMember = bincode::deserialize(value.value())?;
+            if member.active {
+                members.insert(member.referenced_component_id);
+            }
+        }
+
+        Ok(members)
+    }
+
+    /// Get a component's active rows in a reference set (several for map or association targets)
+    pub fn get_refset_rows(&self, refset_id: &str, component_id: &str) -> Result<Vec<RefsetMember>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_REFSET_MEMBERS))?;
+
+        let mut rows = Vec::new();
+        for item in table.range((refset_id, component_id, "")..)? {
+            let (key, value) = item?;
+            let (refset, component, _) = key.value();
+            if refset != refset_id || component != component_id {
+                break;
+            }
+            let member: RefsetMember = bincode::deserialize(value.value())?;
+            if member.active {
+                rows.push(member);
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Whether a component has an active row in a reference set
+    pub fn is_refset_member(&self, refset_id: &str, component_id: &str) -> Result<bool, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_REFSET_MEMBERS))?;
+
+        for item in table.range((refset_id, component_id, "")..)? {
+            let (key, value) = item?;
+            let (refset, component, _) = key.value();
+            if refset != refset_id || component != component_id {
+                break;
+            }
+            let member: RefsetMember = bincode::deserialize(value.value())?;
+            if member.active {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Get the map rows of a source concept across every map refset, in refset, group and priority order
+    pub fn get_snomed_map_entries(&self, source_code: &str) -> Result<Vec<SnomedMapEntry>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_MAP_ENTRIES))?;
+
+        let mut entries: Vec<SnomedMapEntry> = Vec::new();
+        for item in table.range((source_code, "", "")..)? {
+            let (key, value) = item?;
+            if key.value().0 != source_code {
+                break;
+            }
+            entries.push(bincode::deserialize(value.value())?);
+        }
+        entries.sort_by_key(|e| (e.refset_id.clone(), e.map_group, e.map_priority));
+
+        Ok(entries)
+    }
+
+    /// Get the map rows that map to a target code (e.g. an ICD-10-AM code)
+    pub fn get_snomed_map_entries_by_target(&self, map_target: &str) -> Result<Vec<SnomedMapEntry>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let targets = read_txn.open_table(self.table(SNOMED_MAP_TARGETS))?;
+        let entries = read_txn.open_table(self.table(SNOMED_MAP_ENTRIES))?;
+
+        let mut found: Vec<SnomedMapEntry> = Vec::new();
+        for item in targets.range((map_target, "", "")..)? {
+            let (key, source_code) = item?;
+            let (target, refset_id, member_id) = key.value();
+            if target != map_target {
+                break;
+            }
+            if let Some(value) = entries.get((source_code.value(), refset_id, member_id))? {
+                found.push(bincode::deserialize(value.value())?);
+            }
+        }
+        found.sort_by_key(|e| (e.refset_id.clone(), e.source_code.clone(), e.map_group, e.map_priority));
+
+        Ok(found)
+    }
+
+    /// Get all relationships where the given concept is the source
+    pub fn get_snomed_relationships(&self, source_id: &str) -> Result<Vec<SnomedRelationship>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_RELATIONSHIPS))?;
+
+        let mut relationships = Vec::new();
+        for item in table.range((source_id, "")..)? {
+            let (key, value) = item?;
+            let (source, _relationship_id) = key.value();
+            if source != source_id {
+                break;
+            }
+
+            let relationship: SnomedRelationship = bincode::deserialize(value.value())?;
+            relationships.push(relationship);
+        }
+
+        Ok(relationships)
+    }
+
+    /// Get the direct supertypes (active is-a targets) of a SNOMED concept
+    pub fn get_snomed_parents(&self, concept_id: &str) -> Result<Vec<String>, StorageError> {
+        let parents = self
+            .get_snomed_relationships(concept_id)?
+            .into_iter()
+            .filter(|r| r.active && r.type_id == IS_A_TYPE_ID)
+            .map(|r| r.destination_id)
+            .collect();
+
+        Ok(parents)
+    }
+
+    /// Get the direct subtypes (active is-a sources) of a SNOMED concept
+    pub fn get_snomed_children(&self, concept_id: &str) -> Result<Vec<String>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_ISA_CHILDREN))?;
+
+        let mut children = Vec::new();
+        for item in table.range((concept_id, "")..)? {
+            let (key, _) = item?;
+            let (parent, child) = key.value();
+            if parent != concept_id {
+                break;
+            }
+            children.push(child.to_string());
+        }
+
+        Ok(children)
+    }
+
+    /// Check whether `ancestor_id` is a proper transitive supertype of `descendant_id`
+    pub fn snomed_is_ancestor(&self, ancestor_id: &str, descendant_id: &str) -> Result<bool, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_CLOSURE))?;
+
+        Ok(table.get((ancestor_id, descendant_id))?.is_some())
+    }
+
+    /// Get every proper transitive subtype of a SNOMED concept from the closure table
+    pub fn get_snomed_descendants(&self, concept_id: &str) -> Result<Vec<String>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_CLOSURE))?;
+
+        let mut descendants = Vec::new();
+        for item in table.range((concept_id, "")..)? {
+            let (key, _) = item?;
+            let (ancestor, descendant) = key.value();
+            if ancestor != concept_id {
+                break;
+            }
+            descendants.push(descendant.to_string());
+        }
+
+        Ok(descendants)
+    }
+
+    /// Get every proper transitive supertype of a SNOMED concept
+    /// The closure is keyed by ancestor, so this walks the is-a parents instead
+    pub fn get_snomed_ancestors(&self, concept_id: &str) -> Result<Vec<String>, StorageError> {
+        let mut ancestors = HashSet::new();
+        let mut queue: VecDeque<String> = self.get_snomed_parents(concept_id)?.into();
+
+        while let Some(parent) = queue.pop_front() {
+            if ancestors.insert(parent.clone()) {
+                queue.extend(self.get_snomed_parents(&parent)?);
+            }
+        }
+
+        Ok(ancestors.into_iter().collect())
+    }
+
+    /// Get the ids of all active SNOMED concepts
+    pub fn get_all_snomed_concept_ids(&self) -> Result<Vec<String>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_CONCEPTS))?;
+
+        let mut ids = Vec::new();
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let concept: SnomedConcept = bincode::deserialize(value.value())?;
+            if concept.active {
+                ids.push(key.value().to_string());
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Get the concepts that have at least one subtype and those that have at least one supertype
+    pub fn get_snomed_isa_participants(&self) -> Result<(HashSet<String>, HashSet<String>), StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(SNOMED_ISA_CHILDREN))?;
+
+        let mut parents = HashSet::new();
+        let mut children = HashSet::new();
+        for item in table.iter()? {
+            let (key, _) = item?;
+            let (parent, child) = key.value();
+            parents.insert(parent.to_string());
+            children.insert(child.to_string());
+        }
+
+        Ok((parents, children))
+    }
+
+    /// Get the descriptions for a set of SNOMED concepts in one read transaction
+    pub fn get_snomed_descriptions_for(
+        &self,
+        concept_ids: &HashSet<String>,
+    ) -> Result<Vec<SnomedDescription>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let by_concept = read_txn.open_table(self.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+        let table = read_txn.open_table(self.table(SNOMED_DESCRIPTIONS))?;
+
+        let mut descriptions = Vec::new();
+        for concept_id in concept_ids {
+            Self::collect_descriptions(&by_concept, &table, concept_id, &mut descriptions)?;
+        }
+        Ok(descriptions)
+    }
+
+    /// Insert an AMT code
+    pub fn insert_amt_code(&self, code: &AmtCode) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.table(AMT_CODES))?;
+            let bytes = bincode::serialize(code)?;
+            // Use composite key (SCTID, code_type) to allow same SCTID across multiple product types
+            table.insert((code.id.as_str(), code.code_type.as_str()), bytes.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get an AMT code by ID (returns first match across all product types)
+    pub fn get_amt_code(&self, id: &str) -> Result<Option<AmtCode>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(AMT_CODES))?;
+
+        // Since we use composite key (SCTID, code_type), the SCTID's entries are a range starting at (id, "")
+        match table.range((id, "")..)?.next() {
+            Some(item) => {
+                let (key, value) = item?;
+                let (sctid, _code_type) = key.value();
+                if sctid != id {
+                    return Ok(None);
+                }
+                let code: AmtCode = bincode::deserialize(value.value())?;
+                Ok(Some(code))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get all AMT codes (used for statistics/diagnostics)
+    pub fn get_all_amt_codes(&self) -> Result<Vec<AmtCode>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(AMT_CODES))?;
+
+        let mut codes = Vec::new();
+        for entry in table.iter()? {
+            let (_key, value) = entry?;
+            let code: AmtCode = bincode::deserialize(value.value())?;
+            codes.push(code);
+        }
+
+        Ok(codes)
+    }
+
+    /// Insert a ValueSet
+    pub fn insert_valueset(&self, valueset: &ValueSet) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.table(VALUESETS))?;
+            let bytes = bincode::serialize(valueset)?;
+            table.insert(valueset.url.as_str(), bytes.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get a ValueSet by URL
+    pub fn get_valueset(&self, url: &str) -> Result<Option<ValueSet>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(VALUESETS))?;
+
+        if let Some(value) = table.get(url)? {
+            let valueset: ValueSet = bincode::deserialize(value.value())?;
+            Ok(Some(valueset))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get the intensional compose rules for a ValueSet, if it has any
+    pub fn get_valueset_compose(&self, url: &str) -> Result<Option<ValueSetCompose>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(VALUESET_COMPOSE))?;
+
+        if let Some(value) = table.get(url)? {
+            let compose: ValueSetCompose = bincode::deserialize(value.value())?;
+            Ok(Some(compose))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get all ValueSets
+    pub fn get_all_valuesets(&self) -> Result<Vec<ValueSet>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(VALUESETS))?;
+
+        let mut valuesets = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let valueset: ValueSet = bincode::deserialize(value.value())?;
+            valuesets.push(valueset);
+        }
+
+        Ok(valuesets)
+    }
+
+    /// Get a CodeSystem by URL
+    pub fn get_codesystem(&self, url: &str) -> Result<Option<CodeSystem>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CODESYSTEMS))?;
+
+        match table.get(url)? {
+            Some(value) => Ok(Some(bincode::deserialize(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all CodeSystems
+    pub fn get_all_codesystems(&self) -> Result<Vec<CodeSystem>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CODESYSTEMS))?;
+
+        let mut codesystems = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            codesystems.push(bincode::deserialize(value.value())?);
+        }
+
+        Ok(codesystems)
+    }
+
+    /// Get a concept of an imported CodeSystem
+    pub fn get_codesystem_concept(
+        &self,
+        system: &str,
+        code: &str,
+    ) -> Result<Option<CodeSystemConcept>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CODESYSTEM_CONCEPTS))?;
+
+        match table.get((system, code))? {
+            Some(value) => Ok(Some(bincode::deserialize(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get every concept of an imported CodeSystem, in code order
+    pub fn get_codesystem_concepts(&self, system: &str) -> Result<Vec<CodeSystemConcept>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CODESYSTEM_CONCEPTS))?;
+
+        let mut concepts = Vec::new();
+        for item in table.range((system, "")..)? {
+            let (key, value) = item?;
+            if key.value().0 != system {
+                break;
+            }
+            concepts.push(bincode::deserialize(value.value())?);
+        }
+
+        Ok(concepts)
+    }
+
+    /// Get a ConceptMap by URL
+    pub fn get_concept_map(&self, url: &str) -> Result<Option<ConceptMap>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CONCEPT_MAPS))?;
+
+        match table.get(url)? {
+            Some(value) => Ok(Some(bincode::deserialize(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all ConceptMaps
+    pub fn get_all_concept_maps(&self) -> Result<Vec<ConceptMap>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CONCEPT_MAPS))?;
+
+        let mut maps = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            maps.push(bincode::deserialize(value.value())?);
+        }
+
+        Ok(maps)
+    }
+
+    /// Get the ConceptMap rows mapping from a source code (an empty system matches groups without one)
+    pub fn get_concept_map_rows(&self, system: &str, code: &str) -> Result<Vec<ConceptMapRow>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CONCEPT_MAP_ELEMENTS))?;
+        Self::concept_map_rows_for(&table, system, code)
+    }
+
+    /// Get the ConceptMap rows mapping to a target code
+    pub fn get_concept_map_rows_by_target(
+        &self,
+        system: &str,
+        code: &str,
+    ) -> Result<Vec<ConceptMapRow>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(CONCEPT_MAP_TARGETS))?;
+        Self::concept_map_rows_for(&table, system, code)
+    }
+
+    fn concept_map_rows_for(
+        table: &redb::ReadOnlyTable<(&str, &str, &str, u64), &[u8]>,
+        system: &str,
+        code: &str,
+    ) -> Result<Vec<ConceptMapRow>, StorageError> {
+        let mut rows = Vec::new();
+        for item in table.range((system, code, "", 0)..)? {
+            let (key, value) = item?;
+            let (row_system, row_code, _, _) = key.value();
+            if row_system != system || row_code != code {
+                break;
+            }
+            rows.push(bincode::deserialize(value.value())?);
+        }
+
+        Ok(rows)
+    }
+
+    /// Insert a ValueSet concept
+    pub fn insert_valueset_concept(&self, concept: &ValueSetConcept) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.table(VALUESET_CONCEPTS))?;
+            let bytes = bincode::serialize(concept)?;
+            table.insert(
+                (concept.valueset_url.as_str(), concept.code.as_str()),
+                bytes.as_slice(),
+            )?;
+
+            let mut members = write_txn.open_table(self.table(VALUESET_MEMBERS))?;
+            members.insert((concept.valueset_url.as_str(), concept.system.as_str(), concept.code.as_str()), ())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get all concepts in a ValueSet
+    pub fn get_valueset_concepts(&self, valueset_url: &str) -> Result<Vec<ValueSetConcept>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(self.table(VALUESET_CONCEPTS))?;
+
+        let mut concepts = Vec::new();
+        for item in table.range((valueset_url, "")..)? {
+            let (key, value) = item?;
+            let (url, _code) = key.value();
+            if url != valueset_url {
+                break;
+            }
+            concepts.push(bincode::deserialize(value.value())?);
+        }
+
+        Ok(concepts)
+    }
+
+    /// Check if a code exists in a ValueSet
+    pub fn valueset_contains_code(
+        &self,
+        valueset_url: &str,
+        system: &str,
+        code: &str,
+    ) -> Result<bool, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let members = read_txn.open_table(self.table(VALUESET_MEMBERS))?;
+
+        Ok(members.get((valueset_url, system, code))?.is_some())
+    }
+
+    /// Get the database file path
+    pub fn db_path(&self) -> &PathBuf {
+        &self.db_path
+    }
+
+    /// Sanitize a version string to make it safe for use in filenames
+    fn sanitize_version_for_filename(version: &str) -> String {
+        version
+            .replace(' ', "_")
+            .replace('/', "-")
+            .replace('\\', "-")
+            .replace('(', "")
+            .replace(')', "")
+            .replace('[', "")
+            .replace(']', "")
+            .replace('{', "")
+            .replace('}', "")
+            .replace(':', "-")
+            .replace('*', "")
+            .replace('?', "")
+            .replace('"', "")
+            .replace('<', "")
+            .replace('>', "")
+            .replace('|', "-")
+    }
+
+    /// Generate a file path for a terminology download
+    pub fn generate_file_path(&self, terminology_type: &str, version: &str) -> PathBuf {
+        let safe_version = Self::sanitize_version_for_filename(version);
+
+        let extension = match terminology_type {
+            "snomed" => "zip",
+            "snomed-delta" => "zip",
+            "snomed-full" => "zip",
+            "amt" => "csv",
+            "valuesets" => "json",
+            "loinc" => "zip",
+            _ => "zip",
+        };
+        let filename = format!("{}_{}.{}", terminology_type, safe_version, extension);
+        self.data_dir.join(filename)
+    }
+
+    /// Get reference to the database for batch operations
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// Drop a side-by-side release's own tables, returning the number of rows they held
+    /// Returns None when the version's content is in the live tables
+    fn drop_release_tables(&self, version_id: u64) -> Result<Option<i64>, StorageError> {
+        let Some(version) = self.get_version(version_id)? else {
+            return Ok(None);
+        };
+        let Some(family) = ContentFamily::of(&version.terminology_type) else {
+            return Ok(None);
+        };
+        if !version.imported || !self.is_side_by_side(&version)? {
+            return Ok(None);
+        }
+
+        let mut deleted_count = 0i64;
+        {
+            let read_txn = self.db.begin_read()?;
+            for &name in family.table_names() {
+                if let Ok(table) = read_txn.open_untyped_table(table_handle(release_table_name(name, version_id))) {
+                    deleted_count += table.len()? as i64;
+                }
+            }
+        }
+
+        let write_txn = self.db.begin_write()?;
+        self.view(Some((family, version_id))).delete_release_tables(&write_txn)?;
+        write_txn.commit()?;
+
+        Ok(Some(deleted_count))
+    }
+
+    /// Delete the side-by-side release tables this view is scoped to (nothing for the live view)
+    fn delete_release_tables(&self, write_txn: &redb::WriteTransaction) -> Result<(), StorageError> {
+        if let Some((family, version_id)) = self.release {
+            for &name in family.table_names() {
+                write_txn.delete_table(table_handle(release_table_name(name, version_id)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete all SNOMED data for a specific version
+    pub fn delete_snomed_by_version(&self, version_id: u64) -> Result<i64, StorageError> {
+        if let Some(deleted) = self.drop_release_tables(version_id)? {
+            return Ok(deleted);
+        }
+
+        let mut deleted_count = 0i64;
+
+        let write_txn = self.db.begin_write()?;
+        {
+            // Delete concepts
+            let mut concepts_table = write_txn.open_table(self.table(SNOMED_CONCEPTS))?;
+            let mut to_delete = Vec::new();
+
+            for item in concepts_table.iter()? {
+                let (key, value) = item?;
+                let concept: SnomedConcept = bincode::deserialize(value.value())?;
+                if concept.version_id == version_id {
+                    to_delete.push(key.value().to_string());
+                }
+            }
+
+            for key in &to_delete {
+                concepts_table.remove(key.as_str())?;
+                deleted_count += 1;
+            }
+
+            // Delete descriptions and their concept index entries
+            let mut descriptions_table = write_txn.open_table(self.table(SNOMED_DESCRIPTIONS))?;
+            let mut by_concept = write_txn.open_table(self.table(SNOMED_CONCEPT_DESCRIPTIONS))?;
+            let mut descriptions_to_delete = Vec::new();
+
+            for item in descriptions_table.iter()? {
+                let (_, value) = item?;
+                let desc: SnomedDescription = bincode::deserialize(value.value())?;
+                if desc.version_id == version_id {
+                    descriptions_to_delete.push((desc.concept_id, desc.id));
+                }
+            }
+
+            for (concept_id, description_id) in &descriptions_to_delete {
+                descriptions_table.remove(description_id.as_str())?;
+                by_concept.remove((concept_id.as_str(), description_id.as_str()))?;
+                deleted_count += 1;
+            }
+
+            // Delete relationships
+            let mut relationships_table = write_txn.open_table(self.table(SNOMED_RELATIONSHIPS))?;
+            let mut composite_to_delete = Vec::new();
+
+            for item in relationships_table.iter()? {
+                let (key, value) = item?;
+                let relationship: SnomedRelationship = bincode::deserialize(value.value())?;
+                if relationship.version_id == version_id {
+                    let (source, relationship_id) = key.value();
+                    composite_to_delete.push((source.to_string(), relationship_id.to_string()));
+                }
+            }
+
+            for (source, relationship_id) in &composite_to_delete {
+                relationships_table.remove((source.as_str(), relationship_id.as_str()))?;
+                deleted_count += 1;
+            }
+
+            // Delete is-a hierarchy index entries
+            let mut isa_table = write_txn.open_table(self.table(SNOMED_ISA_CHILDREN))?;
+            composite_to_delete.clear();
+
+            for item in isa_table.iter()? {
+                let (key, value) = item?;
+                if value.value() == version_id {
+                    let (parent, child) = key.value();
+                    composite_to_delete.push((parent.to_string(), child.to_string()));
+                }
+            }
+
+            for (parent, child) in &composite_to_delete {
+                isa_table.remove((parent.as_str(), child.as_str()))?;
+            }
+
+            // Delete point-in-time history rows
+            let mut concept_history = write_txn.open_table(self.table(SNOMED_CONCEPT_HISTORY))?;
+            composite_to_delete.clear();
+
+            for item in concept_history.iter()? {
+                let (key, value) = item?;
+                let concept: SnomedConcept = bincode::deserialize(value.value())?;
+                if concept.version_id == version_id {
+                    let (id, effective_time) = key.value();
+                    composite_to_delete.push((id.to_string(), effective_time.to_string()));
+                }
+            }
+
+            for (id, effective_time) in &composite_to_delete {
+                concept_history.remove((id.as_str(), effective_time.as_str()))?;
+            }
+
+            let mut description_history = write_txn.open_table(self.table(SNOMED_DESCRIPTION_HISTORY))?;
+            let mut history_to_delete = Vec::new();
+
+            for item in description_history.iter()? {
+                let (key, value) = item?;
+                let desc: SnomedDescription = bincode::deserialize(value.value())?;
+                if desc.version_id == version_id {
+                    let (concept_id, id, effective_time) = key.value();
+                    history_to_delete.push((concept_id.to_string(), id.to_string(), effective_time.to_string()));
+                }
+            }
+
+            for (concept_id, id, effective_time) in &history_to_delete {
+                description_history.remove((concept_id.as_str(), id.as_str(), effective_time.as_str()))?;
+            }
+
+            // Delete language refset members and the preferred terms derived from them
+            let mut language_table = write_txn.open_table(self.table(SNOMED_LANGUAGE_REFSET))?;
+            composite_to_delete.clear();
+
+            for item in language_table.iter()? {
+                let (key, value) = item?;
+                let member: LanguageRefsetMember = bincode::deserialize(value.value())?;
+                if member.version_id == version_id {
+                    let (description_id, refset_id) = key.value();
+                    composite_to_delete.push((description_id.to_string(), refset_id.to_string()));
+                }
+            }
+
+            for (description_id, refset_id) in &composite_to_delete {
+                language_table.remove((description_id.as_str(), refset_id.as_str()))?;
+                deleted_count += 1;
+            }
+
+            let mut preferred_terms = write_txn.open_table(self.table(SNOMED_PREFERRED_TERMS))?;
+            to_delete.clear();
+
+            for item in preferred_terms.iter()? {
+                let (key, value) = item?;
+                let desc: SnomedDescription = bincode::deserialize(value.value())?;
+                if desc.version_id == version_id {
+                    to_delete.push(key.value().to_string());
+                }
+            }
+
+            for key in &to_delete {
+                preferred_terms.remove(key.as_str())?;
+            }
+
+            // Delete reference set members and summaries
+            let mut refset_members = write_txn.open_table(self.table(SNOMED_REFSET_MEMBERS))?;
+            let mut members_to_delete = Vec::new();
+
+            for item in refset_members.iter()? {
+                let (key, value) = item?;
+                let member: RefsetMember = bincode::deserialize(value.value())?;
+                if member.version_id == version_id {
+                    let (refset_id, component_id, id) = key.value();
+                    members_to_delete.push((refset_id.to_string(), component_id.to_string(), id.to_string()));
+                }
+            }
+
+            for (refset_id, component_id, id) in &members_to_delete {
+                refset_members.remove((refset_id.as_str(), component_id.as_str(), id.as_str()))?;
+                deleted_count += 1;
+            }
+
+            let mut refsets = write_txn.open_table(self.table(SNOMED_REFSETS))?;
+            to_delete.clear();
+
+            for item in refsets.iter()? {
+                let (key, value) = item?;
+                let refset: SnomedRefset = bincode::deserialize(value.value())?;
+                if refset.version_id == version_id {
+                    to_delete.push(key.value().to_string());
+                }
+            }
+
+            for key in &to_delete {
+                refsets.remove(key.as_str())?;
+            }
+
+            // Delete map rows and their reverse index entries
+            let mut map_entries = write_txn.open_table(self.table(SNOMED_MAP_ENTRIES))?;
+            let mut map_targets = write_txn.open_table(self.table(SNOMED_MAP_TARGETS))?;
+            let mut entries_to_delete = Vec::new();
+
+            for item in map_entries.iter()? {
+                let (_, value) = item?;
+                let entry: SnomedMapEntry = bincode::deserialize(value.value())?;
+                if entry.version_id == version_id {
+                    entries_to_delete.push(entry);
+                }
+            }
+
+            for entry in &entries_to_delete {
+                map_entries.remove((entry.source_code.as_str(), entry.refset_id.as_str(), entry.member_id.as_str()))?;
+                if let Some(target) = &entry.map_target {
+                    map_targets.remove((target.as_str(), entry.refset_id.as_str(), entry.member_id.as_str()))?;
+                }
+            }
+
+            // Delete transitive closure entries
+            let mut closure_table = write_txn.open_table(self.table(SNOMED_CLOSURE))?;
+            composite_to_delete.clear();
+
+            for item in closure_table.iter()? {
+                let (key, value) = item?;
+                if value.value() == version_id {
+                    let (ancestor, descendant) = key.value();
+                    composite_to_delete.push((ancestor.to_string(), descendant.to_string()));
+                }
+            }
+
+            for (ancestor, descendant) in &composite_to_delete {
+                closure_table.remove((ancestor.as_str(), descendant.as_str()))?;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(deleted_count)
+    }
+
+    /// Delete all AMT data for a specific version
+    pub fn delete_amt_by_version(&self, version_id: u64) -> Result<i64, StorageError> {
+        if let Some(deleted) = self.drop_release_tables(version_id)? {
+            return Ok(deleted);
+        }
+
+        let mut deleted_count = 0i64;
+
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(self.table(AMT_CODES))?;
+            let mut to_delete = Vec::new();
+
+            for item in table.iter()? {
+                let (key, value) = item?;
+                let code: AmtCode = bincode::deserialize(value.value())?;
+                if code.version_id == version_id {
+                    // Store composite key (SCTID, code_type)
+                    let (sctid, code_type) = key.value();
+                    to_delete.push((sctid.to_string(), code_type.to_string()));
+                }
+            }
+
+            for (sctid, code_type) in &to_delete {
+                table.remove((sctid.as_str(), code_type.as_str()))?;
+                deleted_count += 1;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(deleted_count)
+    }
+
+    /// Delete all ValueSet data for a specific version
+    pub fn delete_valuesets_by_version(&self, version_id: u64) -> Result<i64, StorageError> {
+        if let Some(deleted) = self.drop_release_tables(version_id)? {
+            return Ok(deleted);
+        }
+
+        let mut deleted_count = 0i64;
+
+        let write_txn = self.db.begin_write()?;
+        {
+            // First, collect ValueSet URLs to delete
+            let mut valueset_urls = Vec::new();
+            {
+                let valuesets_table = write_txn.open_table(self.table(VALUESETS))?;
+                for item in valuesets_table.iter()? {
+                    let (key, value) = item?;
+                    let valueset: ValueSet = bincode::deserialize(value.value())?;
+                    if valueset.version_id == version_id {
+                        valueset_urls.push(key.value().to_string());
+                    }
+                }
+            }
+
+            // Delete ValueSet concepts for these URLs
+            let mut concepts_table = write_txn.open_table(self.table(VALUESET_CONCEPTS))?;
+            let mut concepts_to_delete = Vec::new();
+
+            for item in concepts_table.iter()? {
+                let (key, _) = item?;
+                let (url, code) = key.value();
+                if valueset_urls.contains(&url.to_string()) {
+                    concepts_to_delete.push((url.to_string(), code.to_string()));
+                }
+            }
+
+            for (url, code) in &concepts_to_delete {
+                concepts_table.remove((url.as_str(), code.as_str()))?;
+                deleted_count += 1;
+            }
+
+            // Delete their membership index entries
+            let mut members_table = write_txn.open_table(self.table(VALUESET_MEMBERS))?;
+            for url in &valueset_urls {
+                let mut members = Vec::new();
+                for item in members_table.range((url.as_str(), "", "")..)? {
+                    let (key, _) = item?;
+                    let (member_url, system, code) = key.value();
+                    if member_url != url {
+                        break;
+                    }
+                    members.push((system.to_string(), code.to_string()));
+                }
+                for (system, code) in &members {
+                    members_table.remove((url.as_str(), system.as_str(), code.as_str()))?;
+                }
+            }
+
+            // Delete compose rules
+            let mut compose_table = write_txn.open_table(self.table(VALUESET_COMPOSE))?;
+            for url in &valueset_urls {
+                compose_table.remove(url.as_str())?;
+            }
+
+            // Delete CodeSystems and their concepts
+            let mut codesystems_table = write_txn.open_table(self.table(CODESYSTEMS))?;
+            let mut codesystem_urls = Vec::new();
+            for item in codesystems_table.iter()? {
+                let (key, value) = item?;
+                let codesystem: CodeSystem = bincode::deserialize(value.value())?;
+                if codesystem.version_id == version_id {
+                    codesystem_urls.push(key.value().to_string());
+                }
+            }
+
+            let mut codesystem_concepts = write_txn.open_table(self.table(CODESYSTEM_CONCEPTS))?;
+            for url in &codesystem_urls {
+                let mut codes = Vec::new();
+                for item in codesystem_concepts.range((url.as_str(), "")..)? {
+                    let (key, _) = item?;
+                    let (system, code) = key.value();
+                    if system != url {
+                        break;
+                    }
+                    codes.push(code.to_string());
+                }
+                for code in &codes {
+                    codesystem_concepts.remove((url.as_str(), code.as_str()))?;
+                    deleted_count += 1;
+                }
+                codesystems_table.remove(url.as_str())?;
+                deleted_count += 1;
+            }
+
+            // Delete ConceptMaps and their rows
+            let mut maps_table = write_txn.open_table(self.table(CONCEPT_MAPS))?;
+            let mut map_urls = HashSet::new();
+            for item in maps_table.iter()? {
+                let (key, value) = item?;
+                let map: ConceptMap = bincode::deserialize(value.value())?;
+                if map.version_id == version_id {
+                    map_urls.insert(key.value().to_string());
+                }
+            }
+
+            for definition in [CONCEPT_MAP_ELEMENTS, CONCEPT_MAP_TARGETS] {
+                let mut rows_table = write_txn.open_table(self.table(definition))?;
+                let mut rows_to_delete = Vec::new();
+                for item in rows_table.iter()? {
+                    let (key, _) = item?;
+                    let (system, code, url, row) = key.value();
+                    if map_urls.contains(url) {
+                        rows_to_delete.push((system.to_string(), code.to_string(), url.to_string(), row));
+                    }
+                }
+                for (system, code, url, row) in &rows_to_delete {
+                    rows_table.remove((system.as_str(), code.as_str(), url.as_str(), *row))?;
+                    deleted_count += 1;
+                }
+            }
+
+            for url in &map_urls {
+                maps_table.remove(url.as_str())?;
+                deleted_count += 1;
+            }
+
+            // Delete ValueSets
+            let mut valuesets_table = write_txn.open_table(self.table(VALUESETS))?;
+            for url in &valueset_urls {
+                valuesets_table.remove(url.as_str())?;
+                deleted_count += 1;
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(deleted_count)
+    }
+
+    /// Clear imported status for a version
+    pub fn clear_imported_status(&self, version_id: u64) -> Result<(), StorageError> {
+        let live_family = match self.get_version(version_id)? {
+            Some(version) => ContentFamily::of(&version.terminology_type)
+                .filter(|&family| self.live_release(family).ok().flatten() == Some(version_id)),
+            None => None,
+        };
+
+        let write_txn = self.db.begin_write()?;
+        if let Some(family) = live_family {
+            // The live tables no longer hold a release; the next import goes there
+            write_txn.open_table(LIVE_RELEASES)?.insert(family.key(), 0)?;
+        }
+        {
+            let mut table = write_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+            let version_bytes = match table.get(version_id)? {
+                Some(value) => {
+                    let bytes = value.value().to_vec();
+                    drop(value);
+                    Some(bytes)
+                }
+                None => None,
+            };
+
+            if let Some(bytes) = version_bytes {
+                let mut version: TerminologyVersion = bincode::deserialize(&bytes)?;
+                version.imported = false;
+                version.imported_at = None;
+
+                let new_bytes = bincode::serialize(&version)?;
+                table.insert(version_id, new_bytes.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get all versions that are "ghost" records (have downloaded_at but no file_path)
+    pub fn get_ghost_versions(&self) -> Result<Vec<u64>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TERMINOLOGY_VERSIONS)?;
+
+        let mut ghost_ids = Vec::new();
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let version: TerminologyVersion = bincode::deserialize(value.value())?;
+
+            if version.downloaded_at.is_some() &&
+               (version.file_path.is_none() || version.file_path.as_ref().map_or(true, |p| p.is_empty())) {
+                ghost_ids.push(version.id);
+            }
+        }
+
+        Ok(ghost_ids)
+    }
+}
+
+/// A new storage in a temporary directory, removed when the returned `TempDir` is dropped
+#[cfg(test)]
+pub(crate) fn test_storage() -> (tempfile::TempDir, TerminologyStorage) {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let storage = TerminologyStorage::new(dir.path().join("test.redb"), dir.path().join("data"))
+        .expect("Failed to create storage");
+    (dir, storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn concept(id: &str, version_id: u64) -> SnomedConcept {
+        SnomedConcept {
+            id: id.to_string(),
+            effective_time: "20250131".to_string(),
+            active: true,
+            module_id: "32506021000036107".to_string(),
+            definition_status_id: "900000000000074008".to_string(),
+            version_id,
+        }
+    }
+
+    #[test]
+    fn test_side_by_side_release_promotion() {
+        let (_dir, storage) = test_storage();
+
+        let v1 = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        let v2 = storage.record_version("snomed", "20250228", None, "", None, None, None, None).unwrap();
+        let release = |id| storage.get_version(id).unwrap().unwrap();
+
+        // The first release goes into the live tables
+        let live = storage.release_view(&release(v1)).unwrap();
+        assert_eq!(live.release(), None);
+        live.insert_snomed_concept(&concept("1001", v1)).unwrap();
+        storage.mark_imported(v1).unwrap();
+        storage.set_live_release(ContentFamily::Snomed, Some(v1)).unwrap();
+
+        // The next one gets its own tables
+        let side = storage.release_view(&release(v2)).unwrap();
+        assert_eq!(side.release(), Some(v2));
+        side.insert_snomed_concept(&concept("2002", v2)).unwrap();
+        storage.mark_imported(v2).unwrap();
+        // As if the release had been imported before the map tables were added
+        let write_txn = storage.database().begin_write().unwrap();
+        write_txn.delete_table(side.table(SNOMED_MAP_TARGETS)).unwrap();
+        write_txn.commit().unwrap();
+
+        assert!(storage.get_snomed_concept("2002").unwrap().is_none(), "Live release is untouched");
+        let selected = storage.select_release(ContentFamily::Snomed, Some("20250228")).unwrap();
+        assert!(selected.get_snomed_concept("2002").unwrap().is_some());
+        assert!(selected.get_snomed_concept("1001").unwrap().is_none());
+        assert!(storage.select_release(ContentFamily::Snomed, Some("19990101")).is_err());
+        assert!(storage.select_release(ContentFamily::Amt, Some("20250228")).is_err());
+
+        storage.mark_as_latest(v2, "snomed").unwrap();
+        assert_eq!(storage.live_release(ContentFamily::Snomed).unwrap(), Some(v1), "Marking latest moves no content");
+        assert!(storage.get_version(v2).unwrap().unwrap().is_latest);
+
+        let promotion = storage.promote_release(v2).unwrap();
+        assert_eq!(promotion, Some(Promotion { family: ContentFamily::Snomed, demoted: Some(v1) }));
+        assert!(storage.promote_release(v2).unwrap().is_none(), "Already live");
+        assert!(storage.get_snomed_map_entries_by_target("X").unwrap().is_empty(), "The missing table was created");
+        assert_eq!(storage.live_release(ContentFamily::Snomed).unwrap(), Some(v2));
+        assert!(storage.get_snomed_concept("2002").unwrap().is_some());
+        let previous = storage.select_release(ContentFamily::Snomed, Some(&v1.to_string())).unwrap();
+        assert_eq!(previous.release(), Some(v1));
+        assert!(previous.get_snomed_concept("1001").unwrap().is_some());
+
+        // Deleting the demoted release drops its tables without touching the live ones
+        assert_eq!(storage.delete_snomed_by_version(v1).unwrap(), 1);
+        assert!(storage.get_snomed_concept("2002").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_staged_delta_promotion() {
+        let (_dir, storage) = test_storage();
+
+        let delta = storage.record_version("snomed-delta", "20250228", None, "", None, None, None, None).unwrap();
+        let release = |id| storage.get_version(id).unwrap().unwrap();
+        assert!(storage.stage_release(&release(delta)).is_err(), "No live release to apply the delta to");
+
+        let v1 = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        storage.insert_snomed_concept(&concept("1001", v1)).unwrap();
+        storage.insert_snomed_description(&description("11", "1001", v1)).unwrap();
+        storage.mark_imported(v1).unwrap();
+        storage.set_live_release(ContentFamily::Snomed, Some(v1)).unwrap();
+
+        let staging = storage.stage_release(&release(delta)).unwrap();
+        assert_eq!(staging.release(), Some(delta));
+        assert_eq!(storage.delta_base(delta).unwrap(), Some(v1));
+        assert_eq!(staging.copy_live_tables().unwrap(), 3, "The concept, its description and their index row");
+        staging.insert_snomed_concept(&concept("2002", delta)).unwrap();
+        assert!(storage.get_snomed_concept("2002").unwrap().is_none(), "Live release is untouched");
+        assert!(staging.validate_staged(&release(delta)).is_ok());
+
+        staging.promote_staged_delta().unwrap();
+        assert!(release(delta).imported);
+        assert_eq!(storage.live_release(ContentFamily::Snomed).unwrap(), Some(v1));
+        assert!(storage.get_snomed_concept("1001").unwrap().is_some());
+        assert!(storage.get_snomed_concept("2002").unwrap().is_some());
+
+        // A delta staged against a release that is demoted before it is promoted is refused
+        let delta2 = storage.record_version("snomed-delta", "20250331", None, "", None, None, None, None).unwrap();
+        let staging = storage.stage_release(&release(delta2)).unwrap();
+        staging.copy_live_tables().unwrap();
+        let v2 = storage.record_version("snomed", "20250331", None, "", None, None, None, None).unwrap();
+        storage.mark_imported(v2).unwrap();
+        storage.set_live_release(ContentFamily::Snomed, Some(v2)).unwrap();
+        assert!(staging.promote_staged_delta().is_err());
+        assert!(!release(delta2).imported);
+        assert!(storage.get_snomed_concept("1001").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_release_stats() {
+        let (_dir, storage) = test_storage();
+        assert!(storage.release_stats().unwrap().is_empty());
+
+        let v1 = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        let live = storage.release_view(&storage.get_version(v1).unwrap().unwrap()).unwrap();
+        live.insert_snomed_concept(&concept("1001", v1)).unwrap();
+        live.insert_snomed_description(&description("11", "1001", v1)).unwrap();
+        storage.mark_imported(v1).unwrap();
+        storage.set_live_release(ContentFamily::Snomed, Some(v1)).unwrap();
+
+        let v2 = storage.record_version("snomed", "20250228", None, "", None, None, None, None).unwrap();
+        let side = storage.release_view(&storage.get_version(v2).unwrap().unwrap()).unwrap();
+        side.insert_snomed_concept(&concept("1001", v2)).unwrap();
+        side.insert_snomed_concept(&concept("2002", v2)).unwrap();
+        storage.mark_imported(v2).unwrap();
+
+        let stats = storage.release_stats().unwrap();
+        assert_eq!(stats.len(), 2);
+        assert!(stats[0].live);
+        assert_eq!((stats[0].version_id, stats[0].snomed_concepts, stats[0].snomed_descriptions), (v1, 1, 1));
+        assert_eq!(stats[0].database_records, 3, "The concept index row is counted too");
+        assert!(stats[0].database_bytes > 0);
+        assert!(!stats[1].live);
+        assert_eq!((stats[1].version_id, stats[1].snomed_concepts, stats[1].snomed_descriptions), (v2, 2, 0));
+    }
+
+    fn description(id: &str, concept_id: &str, version_id: u64) -> SnomedDescription {
+        SnomedDescription {
+            id: id.to_string(),
+            effective_time: "20250131".to_string(),
+            active: true,
+            module_id: "32506021000036107".to_string(),
+            concept_id: concept_id.to_string(),
+            language_code: "en".to_string(),
+            type_id: "900000000000013009".to_string(),
+            term: format!("Term {}", id),
+            case_significance_id: "900000000000448009".to_string(),
+            version_id,
+        }
+    }
+
+    #[test]
+    fn test_secondary_indexes() {
+        let (dir, storage) = test_storage();
+
+        storage.insert_snomed_description(&description("11", "1001", 1)).unwrap();
+        storage.insert_snomed_description(&description("12", "1001", 1)).unwrap();
+        storage.insert_snomed_description(&description("21", "10011", 2)).unwrap();
+        let ids = |concept_id| -> Vec<String> {
+            storage.get_snomed_descriptions(concept_id).unwrap().into_iter().map(|d| d.id).collect()
+        };
+        assert_eq!(ids("1001"), ["11", "12"], "A concept id that prefixes another does not pick up its descriptions");
+        assert_eq!(ids("10011"), ["21"]);
+
+        let concept = |code: &str, system: &str| ValueSetConcept {
+            valueset_url: "http://example.org/vs".to_string(),
+            system: system.to_string(),
+            code: code.to_string(),
+            display: None,
+        };
+        storage.insert_valueset_concept(&concept("a", "http://example.org/cs")).unwrap();
+        assert!(storage.valueset_contains_code("http://example.org/vs", "http://example.org/cs", "a").unwrap());
+        assert!(!storage.valueset_contains_code("http://example.org/vs", "http://other.org/cs", "a").unwrap());
+        assert_eq!(storage.get_valueset_concepts("http://example.org/vs").unwrap().len(), 1);
+        assert!(storage.get_valueset_concepts("http://example.org/v").unwrap().is_empty());
+
+        assert_eq!(storage.delete_snomed_by_version(2).unwrap(), 1);
+        assert!(ids("10011").is_empty());
+
+        // Drop the indexes and the schema version, as in a database from before they existed;
+        // reopening migrates it and fills them again
+        {
+            let write_txn = storage.database().begin_write().unwrap();
+            write_txn.delete_table(SNOMED_CONCEPT_DESCRIPTIONS).unwrap();
+            write_txn.delete_table(VALUESET_MEMBERS).unwrap();
+            write_txn.delete_table(SCHEMA_META).unwrap();
+            write_txn.commit().unwrap();
+        }
+        drop(storage);
+        let storage = TerminologyStorage::new(dir.path().join("test.redb"), dir.path().join("data"))
+            .expect("Failed to reopen storage");
+        assert_eq!(storage.get_snomed_descriptions("1001").unwrap().len(), 2);
+        assert!(storage.valueset_contains_code("http://example.org/vs", "http://example.org/cs", "a").unwrap());
+    }
+
+    #[test]
+    fn test_schema_migrations() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let db_path = dir.path().join("test.redb");
+        let open = || TerminologyStorage::new(db_path.clone(), dir.path().join("data"));
+
+        let storage = open().expect("Failed to create storage");
+        assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION, "A new database starts current");
+        let v1 = storage.record_version("amt", "20250131", None, "", None, None, None, None).unwrap();
+        let v2 = storage.record_version("snomed", "20250131", None, "", None, None, None, None).unwrap();
+        storage.mark_imported(v1).unwrap();
+        storage.mark_imported(v2).unwrap();
+        let delta = storage.record_version("snomed-delta", "20250228", None, "", None, None, None, None).unwrap();
+        storage.mark_imported(delta).unwrap();
+        let side = storage.view(Some((ContentFamily::Snomed, 7)));
+        {
+            let write_txn = storage.database().begin_write().unwrap();
+            side.create_content_tables(&write_txn).unwrap();
+            write_txn.commit().unwrap();
+        }
+        side.insert_snomed_description(&description("71", "7001", 7)).unwrap();
+
+        // A database from before schema versions: no live release or delta records and no description index
+        {
+            let write_txn = storage.database().begin_write().unwrap();
+            write_txn.delete_table(SCHEMA_META).unwrap();
+            write_txn.delete_table(LIVE_RELEASES).unwrap();
+            write_txn.delete_table(SNOMED_DELTA_BASES).unwrap();
+            write_txn.delete_table(side.table(SNOMED_CONCEPT_DESCRIPTIONS)).unwrap();
+            write_txn.commit().unwrap();
+        }
+        drop((storage, side));
+
+        let storage = open().expect("Failed to migrate storage");
+        assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION);
+        assert_eq!(storage.live_release(ContentFamily::Amt).unwrap(), Some(v1));
+        assert_eq!(storage.live_release(ContentFamily::Snomed).unwrap(), Some(v2));
+        assert_eq!(storage.live_release(ContentFamily::ValueSets).unwrap(), None);
+        assert_eq!(storage.delta_base(delta).unwrap(), Some(v2), "A delta imported after the live release");
+        let side = storage.view(Some((ContentFamily::Snomed, 7)));
+        assert_eq!(side.get_snomed_descriptions("7001").unwrap().len(), 1, "Side-by-side indexes are filled");
+
+        // A database written by a newer app is refused and left as it was
+        {
+            let write_txn = storage.database().begin_write().unwrap();
+            write_txn.open_table(SCHEMA_META).unwrap().insert("version", SCHEMA_VERSION + 1).unwrap();
+            write_txn.commit().unwrap();
+        }
+        drop((storage, side));
+        let err = open().err().expect("Newer schema is refused");
+        assert!(matches!(err, StorageError::SchemaTooNew { found, .. } if found == SCHEMA_VERSION + 1));
+        assert!(err.to_string().contains("Update the app"));
+        let db = Database::open(&db_path).unwrap();
+        let read_txn = db.begin_read().unwrap();
+        let version = read_txn.open_table(SCHEMA_META).unwrap().get("version").unwrap().map(|v| v.value());
+        assert_eq!(version, Some(SCHEMA_VERSION + 1));
+    }
+}
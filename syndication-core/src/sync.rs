@@ -0,0 +1,808 @@
+use crate::import::TerminologyImporter;
+use crate::jobs::{Cancelled, JobControl, JobRegistry};
+use crate::local::identify_release;
+use crate::ncts::{NctsClient, TerminologyType};
+use crate::progress::{ProgressReporter, SyncProgress};
+use crate::search::TerminologySearch;
+use crate::storage::{ContentFamily, ReleaseStats, TerminologyStorage, TerminologyVersion};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Terminology types available via syndication (LOINC excluded - proprietary binary only)
+pub const SYNDICATED_TYPES: [&str; 3] = ["snomed", "valuesets", "amt"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncResult {
+    pub terminology_type: String,
+    pub success: bool,
+    pub latest_version: Option<String>,
+    pub error: Option<String>,
+}
+
+impl SyncResult {
+    fn failed(terminology_type: &str, latest_version: Option<&str>, error: String) -> Self {
+        Self {
+            terminology_type: terminology_type.to_string(),
+            success: false,
+            latest_version: latest_version.map(|v| v.to_string()),
+            error: Some(error),
+        }
+    }
+}
+
+/// Download the latest NCTS version of a terminology type into storage
+/// Feed and download failures are reported in the SyncResult; storage failures are errors
+pub async fn sync_latest(
+    ncts_client: &NctsClient,
+    storage: &TerminologyStorage,
+    terminology_type: &str,
+    progress: Option<&dyn ProgressReporter>,
+) -> Result<SyncResult> {
+    let term_type: TerminologyType = terminology_type.parse()?;
+
+    // Fetch the latest version from NCTS
+    let latest_entry = match ncts_client.fetch_latest(term_type).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            return Ok(SyncResult::failed(terminology_type, None, "No versions found".to_string()));
+        }
+        Err(e) => {
+            return Ok(SyncResult::failed(terminology_type, None, format!("Failed to fetch: {}", e)));
+        }
+    };
+
+    // Use content_item_version if available, otherwise fall back to title
+    let version = latest_entry.content_item_version.as_ref()
+        .or(latest_entry.version.as_ref())
+        .unwrap_or(&latest_entry.title)
+        .clone();
+
+    // Check if we already have this version
+    let existing = storage.get_latest(terminology_type).context("Storage error")?;
+
+    if let Some(existing) = existing {
+        // Compare using content_item_version if available, otherwise use version
+        let existing_version = existing.content_item_version.as_ref()
+            .unwrap_or(&existing.version);
+
+        // Only skip download if version matches AND file actually exists on disk
+        if existing_version == &version {
+            if let Some(ref file_path) = existing.file_path {
+                if Path::new(file_path).exists() {
+                    return Ok(SyncResult {
+                        terminology_type: terminology_type.to_string(),
+                        success: true,
+                        latest_version: Some(version),
+                        error: Some("Already up to date".to_string()),
+                    });
+                } else {
+                    println!("⚠ Version exists in database but file is missing, re-downloading...");
+                }
+            } else {
+                println!("⚠ Version exists in database but no file_path recorded, downloading...");
+            }
+        }
+    }
+
+    // Record the new version with NCTS metadata
+    let version_id = storage
+        .record_version(
+            terminology_type,
+            &version,
+            latest_entry.effective_date.as_deref(),
+            latest_entry.download_url.as_deref().unwrap_or(""),
+            latest_entry.content_item_identifier.as_deref(),
+            latest_entry.content_item_version.as_deref(),
+            latest_entry.sha256_hash.as_deref(),
+            latest_entry.sct_base_version.as_deref(),
+        )
+        .context("Failed to record version")?;
+
+    // Download the file if a download URL is available
+    if let Some(download_url) = &latest_entry.download_url {
+        let file_path = storage.generate_file_path(terminology_type, &version);
+
+        if let Some(reporter) = progress {
+            reporter.sync_progress(&SyncProgress {
+                phase: "Downloading".to_string(),
+                message: format!("Downloading {} from NCTS...", terminology_type),
+                percentage: 0.0,
+            });
+        }
+
+        if let Err(e) = ncts_client.download_terminology(download_url, &file_path, progress).await {
+            return Ok(SyncResult::failed(terminology_type, Some(&version), format!("Download failed: {}", e)));
+        }
+
+        // Validate SHA-256 hash if provided (CP 94)
+        if let Some(expected_hash) = &latest_entry.sha256_hash {
+            println!("Validating downloaded file hash...");
+            if let Err(e) = NctsClient::validate_file_hash(&file_path, expected_hash).await {
+                // Hash validation failed - delete the file and report the failure
+                let _ = tokio::fs::remove_file(&file_path).await;
+                return Ok(SyncResult::failed(
+                    terminology_type,
+                    Some(&version),
+                    format!("Hash validation failed: {}", e),
+                ));
+            }
+            println!("✓ File integrity validated");
+        } else {
+            println!("⚠ Warning: No SHA-256 hash provided in feed, skipping validation");
+        }
+
+        // Mark as downloaded
+        let file_path_str = file_path.to_string_lossy().to_string();
+        storage
+            .mark_downloaded(version_id, &file_path_str)
+            .context("Failed to mark downloaded")?;
+    }
+
+    // Mark as latest
+    storage
+        .mark_as_latest(version_id, terminology_type)
+        .context("Failed to mark as latest")?;
+
+    Ok(SyncResult {
+        terminology_type: terminology_type.to_string(),
+        success: true,
+        latest_version: Some(version),
+        error: None,
+    })
+}
+
+/// Import the latest downloaded version of a terminology type into storage and the search indexes
+/// A release is imported into staging tables and a staging index of its own (see `ContentFamily`) and
+/// validated before it is marked imported. The first release of a family is then promoted into the live
+/// tables; later ones stay beside the live release until promoted with `promote_release`. A failed import
+/// discards what was staged, so the live release is never left half-written
+/// A delta is applied to a staged copy of the live SNOMED release and its index, validated the same
+/// way, then swapped in over the live release
+/// With a job, the import stops when it is cancelled; a cancelled or interrupted SNOMED release keeps
+/// its staging tables and checkpoint and resumes from the last committed batch when imported again
+/// Returns a human-readable summary (including when the version was already imported)
+pub async fn import_latest(
+    storage: &TerminologyStorage,
+    searcher: &TerminologySearch,
+    terminology_type: &str,
+    progress: Option<&dyn ProgressReporter>,
+    job: Option<&JobControl>,
+) -> Result<String> {
+    let version = storage
+        .get_latest(terminology_type)
+        .context("Failed to get latest version")?
+        .with_context(|| format!("No downloaded version found for {}. Please sync first.", terminology_type))?;
+
+    if version.imported {
+        return Ok(format!(
+            "{} version {} already imported",
+            terminology_type, version.version
+        ));
+    }
+
+    let file_path = version
+        .file_path
+        .clone()
+        .with_context(|| format!("No file path found for {}", terminology_type))?;
+    let file_path = Path::new(&file_path);
+
+    let family = ContentFamily::of(terminology_type)
+        .with_context(|| format!("Unknown terminology type: {}", terminology_type))?;
+    for stale in storage.discard_stale_staging(&version).context("Failed to discard abandoned imports")? {
+        searcher.remove_release(stale).context("Failed to remove abandoned search index")?;
+    }
+    let staging = storage.stage_release(&version).context("Failed to create staging tables")?;
+    if terminology_type == "snomed-delta" {
+        searcher
+            .stage_from_live(family, version.id)
+            .context("Failed to copy live search index")?;
+    } else {
+        searcher.remove_release(version.id).context("Failed to clear staging search index")?;
+    }
+
+    let staged = stage_release(&staging, searcher, &version, file_path, progress, job).await;
+    if let Err(e) = staged {
+        let resumable = e.is::<Cancelled>() && storage.get_import_checkpoint(version.id)?.is_some();
+        if resumable {
+            return Err(e);
+        }
+
+        // The live release was never touched; a retry clears anything that could not be discarded here
+        if let Err(cleanup) = staging.discard_staged() {
+            eprintln!("Warning: Failed to discard staged tables of version {}: {}", version.id, cleanup);
+        }
+        if let Err(cleanup) = searcher.remove_release(version.id) {
+            eprintln!("Warning: Failed to discard staging search index of version {}: {}", version.id, cleanup);
+        }
+        return Err(e);
+    }
+
+    if terminology_type == "snomed-delta" {
+        if let Err(e) = staging.promote_staged_delta() {
+            if let Err(cleanup) = staging.discard_staged() {
+                eprintln!("Warning: Failed to discard staged tables of version {}: {}", version.id, cleanup);
+            }
+            if let Err(cleanup) = searcher.remove_release(version.id) {
+                eprintln!("Warning: Failed to discard staging search index of version {}: {}", version.id, cleanup);
+            }
+            return Err(e).context("Failed to apply delta to the live release");
+        }
+
+        // The live tables already hold the delta, so the live index is rebuilt from them if the swap fails
+        let moved = match searcher.promote_release(family, version.id, None) {
+            Ok(moved) => moved,
+            Err(e) => {
+                eprintln!("Warning: Failed to swap search indexes of version {}, rebuilding: {}", version.id, e);
+                if let Err(cleanup) = searcher.remove_release(version.id) {
+                    eprintln!("Warning: Failed to discard staging search index of version {}: {}", version.id, cleanup);
+                }
+                false
+            }
+        };
+        if !moved {
+            TerminologyImporter::new(storage, version.id)
+                .build_search_index(family, searcher)
+                .context("Failed to rebuild live search index")?;
+        }
+        return Ok(format!(
+            "Successfully imported {} version {}",
+            terminology_type, version.version
+        ));
+    }
+
+    storage
+        .mark_imported(version.id)
+        .context("Failed to mark as imported")?;
+
+    if storage.live_release(family).context("Storage error")?.is_some() {
+        return Ok(format!(
+            "Successfully imported {} version {} beside the live release (promote it to make it the default)",
+            terminology_type, version.version
+        ));
+    }
+    promote_release(storage, searcher, version.id)?;
+
+    Ok(format!(
+        "Successfully imported {} version {}",
+        terminology_type, version.version
+    ))
+}
+
+/// Run `import_latest` as a job that `JobRegistry::cancel` can stop and `JobRegistry::list` reports
+/// `on_start` receives the job id before the import begins
+pub async fn import_latest_job(
+    storage: &TerminologyStorage,
+    searcher: &TerminologySearch,
+    jobs: &JobRegistry,
+    terminology_type: &str,
+    progress: Option<&dyn ProgressReporter>,
+    on_start: impl FnOnce(u64),
+) -> Result<String> {
+    let version = storage
+        .get_latest(terminology_type)
+        .context("Failed to get latest version")?
+        .with_context(|| format!("No downloaded version found for {}. Please sync first.", terminology_type))?;
+    if version.imported {
+        return import_latest(storage, searcher, terminology_type, progress, None).await;
+    }
+
+    let job = jobs.start(storage, &version).context("Failed to record import job")?;
+    on_start(job.id());
+    let result = import_latest(storage, searcher, terminology_type, progress, Some(&job)).await;
+    jobs.finish(storage, &job, &result).context("Failed to record import job")?;
+    result
+}
+
+/// Import a release file supplied locally instead of downloaded from NCTS (see `local::identify_release`)
+/// The file is copied into the data directory and recorded as the latest version of its type, as a sync
+/// would leave it, then imported as a job like `import_latest_job`
+pub async fn import_local_file(
+    storage: &TerminologyStorage,
+    searcher: &TerminologySearch,
+    jobs: &JobRegistry,
+    path: &Path,
+    progress: Option<&dyn ProgressReporter>,
+    on_start: impl FnOnce(u64),
+) -> Result<String> {
+    let release = identify_release(path)?;
+    let terminology_type = release.terminology_type.as_str();
+
+    let recorded = storage
+        .get_all_versions(terminology_type)
+        .context("Storage error")?
+        .into_iter()
+        .find(|v| v.version == release.version);
+    let recorded_hash = recorded.as_ref().and_then(|v| v.sha256_hash.as_deref());
+    if recorded.as_ref().is_some_and(|v| v.imported) && recorded_hash.is_some_and(|h| h != release.sha256_hash) {
+        anyhow::bail!(
+            "{} version {} is already imported from a different file",
+            terminology_type,
+            release.version
+        );
+    }
+
+    // The media may be removed once imported, so the file is kept where a download would be
+    let source = std::fs::canonicalize(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let file_path = storage.generate_file_path(terminology_type, &release.version);
+    let stored = file_path.exists() && recorded_hash == Some(release.sha256_hash.as_str());
+    if !stored && std::fs::canonicalize(&file_path).ok().as_ref() != Some(&source) {
+        if let Some(parent) = file_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if let Some(reporter) = progress {
+            reporter.sync_progress(&SyncProgress {
+                phase: "Copying".to_string(),
+                message: format!("Copying {} release from {:?}...", terminology_type, path),
+                percentage: 0.0,
+            });
+        }
+        tokio::fs::copy(&source, &file_path)
+            .await
+            .with_context(|| format!("Failed to copy {:?} to {:?}", path, file_path))?;
+    }
+
+    let version_id = storage
+        .record_version(
+            terminology_type,
+            &release.version,
+            release.effective_date.as_deref(),
+            url::Url::from_file_path(&source).map(String::from).unwrap_or_default().as_str(),
+            release.content_item_identifier.as_deref(),
+            release.content_item_version.as_deref(),
+            Some(&release.sha256_hash),
+            None,
+        )
+        .context("Failed to record version")?;
+    storage
+        .mark_downloaded(version_id, &file_path.to_string_lossy())
+        .context("Failed to mark downloaded")?;
+    storage
+        .mark_as_latest(version_id, terminology_type)
+        .context("Failed to mark as latest")?;
+
+    import_latest_job(storage, searcher, jobs, terminology_type, progress, on_start).await
+}
+
+/// Import a release into its staging tables and index, then check both are complete
+async fn stage_release(
+    staging: &TerminologyStorage,
+    searcher: &TerminologySearch,
+    version: &TerminologyVersion,
+    file_path: &Path,
+    progress: Option<&dyn ProgressReporter>,
+    job: Option<&JobControl>,
+) -> Result<()> {
+    let searcher = searcher.for_view(staging).context("Failed to open staging search index")?;
+    let mut importer = TerminologyImporter::new(staging, version.id);
+    if let Some(reporter) = progress {
+        importer = importer.with_progress(reporter);
+    }
+    if let Some(job) = job {
+        importer = importer.with_job(job);
+    }
+
+    match version.terminology_type.as_str() {
+        "snomed" => importer
+            .import_snomed(file_path, &searcher)
+            .await
+            .context("SNOMED import failed")?,
+        "snomed-full" => importer
+            .import_snomed_full(file_path, &searcher)
+            .await
+            .context("SNOMED full import failed")?,
+        "snomed-delta" => importer
+            .import_snomed_delta(file_path, version.sct_base_version.as_deref(), &searcher)
+            .await
+            .context("SNOMED delta import failed")?,
+        "amt" => importer
+            .import_amt(file_path, &searcher)
+            .await
+            .context("AMT import failed")?,
+        "valuesets" => importer
+            .import_valuesets(file_path, &searcher)
+            .await
+            .context("ValueSets import failed")?,
+        other => anyhow::bail!("Unknown terminology type: {}", other),
+    }
+
+    let stats = staging.validate_staged(version).context("Imported release failed validation")?;
+    let index_empty = match version.terminology_type.as_str() {
+        "amt" => searcher.is_amt_empty()?,
+        "valuesets" => stats.valuesets > 0 && searcher.is_valueset_empty()?,
+        _ => searcher.is_snomed_empty()?,
+    };
+    if index_empty {
+        anyhow::bail!(
+            "Imported release failed validation: the {} search index is empty",
+            version.terminology_type
+        );
+    }
+    Ok(())
+}
+
+/// Make an imported release the default for queries (see `TerminologyStorage::promote_release`)
+/// and swap the search indexes of the releases that changed places (see `TerminologySearch::promote_release`)
+pub fn promote_release(
+    storage: &TerminologyStorage,
+    searcher: &TerminologySearch,
+    version_id: u64,
+) -> Result<String> {
+    let version = storage
+        .get_version(version_id)
+        .context("Storage error")?
+        .with_context(|| format!("Unknown version id: {}", version_id))?;
+    if !version.imported {
+        anyhow::bail!("{} version {} has not been imported", version.terminology_type, version.version);
+    }
+    let family = ContentFamily::of(&version.terminology_type)
+        .with_context(|| format!("Unknown terminology type: {}", version.terminology_type))?;
+
+    let Some(promotion) = storage.promote_release(version.id).context("Failed to promote release")? else {
+        return Ok(format!(
+            "{} version {} is already the live release",
+            version.terminology_type, version.version
+        ));
+    };
+
+    let moved = searcher
+        .promote_release(family, version.id, promotion.demoted)
+        .context("Failed to swap search indexes")?;
+    if !moved {
+        // A release without a search index of its own is indexed from storage instead
+        TerminologyImporter::new(storage, version.id)
+            .build_search_index(family, searcher)
+            .context("Failed to rebuild live search index")?;
+    }
+
+    Ok(format!(
+        "Promoted {} version {} to the live release",
+        version.terminology_type, version.version
+    ))
+}
+
+/// Record counts, database size and search index size of every imported release
+pub fn storage_stats(storage: &TerminologyStorage, searcher: &TerminologySearch) -> Result<Vec<ReleaseStats>> {
+    let mut stats = storage.release_stats().context("Failed to read storage statistics")?;
+    for release in &mut stats {
+        let family = ContentFamily::of(&release.terminology_type)
+            .with_context(|| format!("Unknown terminology type: {}", release.terminology_type))?;
+        let side_by_side = (!release.live).then_some(release.version_id);
+        release.index_bytes = searcher
+            .index_bytes(family, side_by_side)
+            .context("Failed to measure search index")?;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_storage;
+    use redb::TableHandle;
+    use std::sync::Arc;
+
+    fn bundle(url: &str, title: &str) -> String {
+        format!(
+            r#"{{ "resourceType": "Bundle", "entry": [ {{ "resource": {{
+                "resourceType": "ValueSet", "url": "{}", "title": "{}", "status": "active",
+                "expansion": {{ "contains": [ {{ "system": "http://snomed.info/sct", "code": "195967001" }} ] }}
+            }} }} ] }}"#,
+            url, title
+        )
+    }
+
+    /// Write an RF2 archive holding concept, description and relationship files of one release type
+    fn rf2(path: &Path, release: &str, concepts: &[&str], descriptions: &[&str], relationships: &[&str]) {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        let files = [
+            ("sct2_Concept", "id\teffectiveTime\tactive\tmoduleId\tdefinitionStatusId", concepts),
+            (
+                "sct2_Description",
+                "id\teffectiveTime\tactive\tmoduleId\tconceptId\tlanguageCode\ttypeId\tterm\tcaseSignificanceId",
+                descriptions,
+            ),
+            (
+                "sct2_Relationship",
+                "id\teffectiveTime\tactive\tmoduleId\tsourceId\tdestinationId\trelationshipGroup\ttypeId\tcharacteristicTypeId\tmodifierId",
+                relationships,
+            ),
+        ];
+        for (name, header, rows) in files {
+            let language = if name == "sct2_Description" { "-en" } else { "" };
+            zip.start_file(format!("{}/Terminology/{}_{}{}_AU1000036_20250228.txt", release, name, release, language), options)
+                .unwrap();
+            writeln!(zip, "{}", header).unwrap();
+            for row in rows {
+                writeln!(zip, "{}", row).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Cancels a job once the import reaches a phase
+    struct CancelAt(Arc<JobControl>, &'static str);
+
+    impl ProgressReporter for CancelAt {
+        fn import_progress(&self, progress: &crate::progress::ImportProgress) {
+            if progress.phase == self.1 {
+                self.0.cancel();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_staged_delta_resumes() {
+        let (dir, storage) = test_storage();
+        let searcher = TerminologySearch::new(&dir.path().join("index")).expect("Failed to create indexes");
+        let jobs = JobRegistry::new(&storage).unwrap();
+        let base = "http://snomed.info/sct/32506021000036107/version/20250131";
+
+        let snapshot = dir.path().join("snapshot.zip");
+        rf2(
+            &snapshot,
+            "Snapshot",
+            &["1001\t20250131\t1\t32506021000036107\t900000000000074008"],
+            &["11\t20250131\t1\t32506021000036107\t1001\ten\t900000000000013009\tClinical finding\t900000000000448009"],
+            &[],
+        );
+        let v1 = storage.record_version("snomed", "20250131", None, "", None, Some(base), None, None).unwrap();
+        storage.mark_downloaded(v1, &snapshot.to_string_lossy()).unwrap();
+        storage.mark_as_latest(v1, "snomed").unwrap();
+        import_latest(&storage, &searcher, "snomed", None, None).await.unwrap();
+
+        let delta_path = dir.path().join("delta.zip");
+        rf2(
+            &delta_path,
+            "Delta",
+            &["2002\t20250228\t1\t32506021000036107\t900000000000074008"],
+            &["22\t20250228\t1\t32506021000036107\t2002\ten\t900000000000013009\tAsthma disorder\t900000000000448009"],
+            &["33\t20250228\t1\t32506021000036107\t2002\t1001\t0\t116680003\t900000000000011006\t900000000000451002"],
+        );
+        let delta_version = "http://snomed.info/sct/32506021000036107/version/20250228";
+        let delta = storage
+            .record_version("snomed-delta", "20250228", None, "", None, Some(delta_version), None, Some(base))
+            .unwrap();
+        storage.mark_downloaded(delta, &delta_path.to_string_lossy()).unwrap();
+        storage.mark_as_latest(delta, "snomed-delta").unwrap();
+
+        // Cancelled part way, the delta leaves the live release alone and keeps its staged copy
+        let job = jobs.start(&storage, &storage.get_version(delta).unwrap().unwrap()).unwrap();
+        let cancel = CancelAt(Arc::clone(&job), "Importing Descriptions");
+        let err = import_latest(&storage, &searcher, "snomed-delta", Some(&cancel), Some(&job)).await.unwrap_err();
+        assert!(err.is::<Cancelled>(), "{:#}", err);
+        assert!(storage.get_snomed_concept("2002").unwrap().is_none());
+        assert!(searcher.search_snomed("disorder", 10).unwrap().is_empty());
+        let checkpoint = storage.get_import_checkpoint(delta).unwrap().unwrap();
+        assert_eq!(checkpoint.completed, ["live release", "concepts"]);
+
+        // The resumed delta is validated and swapped in, with its closure and index rebuilt
+        let job = jobs.start(&storage, &storage.get_version(delta).unwrap().unwrap()).unwrap();
+        import_latest(&storage, &searcher, "snomed-delta", None, Some(&job)).await.unwrap();
+        assert!(storage.get_version(delta).unwrap().unwrap().imported);
+        assert_eq!(storage.live_release(ContentFamily::Snomed).unwrap(), Some(v1));
+        assert_eq!(storage.get_imported_snomed_release().unwrap().unwrap().id, delta);
+        assert!(storage.get_snomed_concept("1001").unwrap().is_some());
+        assert!(storage.get_snomed_concept("2002").unwrap().is_some());
+        assert!(storage.get_import_checkpoint(delta).unwrap().is_none());
+        assert_eq!(searcher.search_snomed("disorder", 10).unwrap().len(), 1);
+        assert_eq!(searcher.search_snomed("finding", 10).unwrap().len(), 1);
+        assert_eq!(searcher.index_bytes(ContentFamily::Snomed, Some(delta)).unwrap(), 0, "Staging index moved");
+        let read_txn = storage.database().begin_read().unwrap();
+        let staged = read_txn.list_tables().unwrap().filter(|t| t.name().ends_with(&format!("@v{}", delta))).count();
+        assert_eq!(staged, 0, "Staging tables were swapped in");
+    }
+
+    /// Replaces the live SNOMED release once the import reaches a phase
+    struct DemoteAt<'a>(&'a TerminologyStorage, &'static str, u64);
+
+    impl ProgressReporter for DemoteAt<'_> {
+        fn import_progress(&self, progress: &crate::progress::ImportProgress) {
+            if progress.phase == self.1 {
+                self.0.set_live_release(ContentFamily::Snomed, Some(self.2)).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refused_delta_is_discarded() {
+        let (dir, storage) = test_storage();
+        let searcher = TerminologySearch::new(&dir.path().join("index")).expect("Failed to create indexes");
+        let base = "http://snomed.info/sct/32506021000036107/version/20250131";
+
+        let snapshot = dir.path().join("snapshot.zip");
+        rf2(
+            &snapshot,
+            "Snapshot",
+            &["1001\t20250131\t1\t32506021000036107\t900000000000074008"],
+            &["11\t20250131\t1\t32506021000036107\t1001\ten\t900000000000013009\tClinical finding\t900000000000448009"],
+            &[],
+        );
+        let v1 = storage.record_version("snomed", "20250131", None, "", None, Some(base), None, None).unwrap();
+        storage.mark_downloaded(v1, &snapshot.to_string_lossy()).unwrap();
+        storage.mark_as_latest(v1, "snomed").unwrap();
+        import_latest(&storage, &searcher, "snomed", None, None).await.unwrap();
+
+        let delta_path = dir.path().join("delta.zip");
+        rf2(
+            &delta_path,
+            "Delta",
+            &["2002\t20250228\t1\t32506021000036107\t900000000000074008"],
+            &["22\t20250228\t1\t32506021000036107\t2002\ten\t900000000000013009\tAsthma disorder\t900000000000448009"],
+            &[],
+        );
+        let delta_version = "http://snomed.info/sct/32506021000036107/version/20250228";
+        let delta = storage
+            .record_version("snomed-delta", "20250228", None, "", None, Some(delta_version), None, Some(base))
+            .unwrap();
+        storage.mark_downloaded(delta, &delta_path.to_string_lossy()).unwrap();
+        storage.mark_as_latest(delta, "snomed-delta").unwrap();
+
+        // The live release changes while the delta is staged, so the delta is refused and its staging dropped
+        let v2 = storage.record_version("snomed", "20250331", None, "", None, None, None, None).unwrap();
+        let demote = DemoteAt(&storage, "Importing Descriptions", v2);
+        let err = import_latest(&storage, &searcher, "snomed-delta", Some(&demote), None).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("Failed to apply delta"), "{:#}", err);
+        assert!(!storage.get_version(delta).unwrap().unwrap().imported);
+        assert!(storage.get_snomed_concept("1001").unwrap().is_some());
+        assert!(storage.get_snomed_concept("2002").unwrap().is_none());
+        assert!(storage.get_import_checkpoint(delta).unwrap().is_none());
+        assert!(searcher.search_snomed("disorder", 10).unwrap().is_empty());
+        assert_eq!(searcher.index_bytes(ContentFamily::Snomed, Some(delta)).unwrap(), 0);
+        let read_txn = storage.database().begin_read().unwrap();
+        let staged = read_txn.list_tables().unwrap().filter(|t| t.name().ends_with(&format!("@v{}", delta))).count();
+        assert_eq!(staged, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_import_keeps_live_release() {
+        let (dir, storage) = test_storage();
+        let searcher = TerminologySearch::new(&dir.path().join("index")).expect("Failed to create indexes");
+
+        let download = |version: &str, contents: &str| {
+            let path = dir.path().join(format!("valuesets_{}.json", version));
+            std::fs::write(&path, contents).unwrap();
+            let id = storage.record_version("valuesets", version, None, "", None, None, None, None).unwrap();
+            storage.mark_downloaded(id, &path.to_string_lossy()).unwrap();
+            storage.mark_as_latest(id, "valuesets").unwrap();
+            id
+        };
+
+        let v1 = download("v1", &bundle("http://example.org/vs/asthma", "Asthma disorders"));
+        import_latest(&storage, &searcher, "valuesets", None, None).await.unwrap();
+        assert_eq!(storage.live_release(ContentFamily::ValueSets).unwrap(), Some(v1));
+        assert!(storage.get_valueset("http://example.org/vs/asthma").unwrap().is_some());
+        assert!(!searcher.search_valuesets("disorders", 10).unwrap().is_empty());
+        assert_eq!(searcher.index_bytes(ContentFamily::ValueSets, Some(v1)).unwrap(), 0, "Staging index removed");
+
+        let unchanged = |storage: &TerminologyStorage| {
+            assert_eq!(storage.live_release(ContentFamily::ValueSets).unwrap(), Some(v1));
+            assert!(storage.get_valueset("http://example.org/vs/asthma").unwrap().is_some());
+            assert!(!searcher.search_valuesets("disorders", 10).unwrap().is_empty());
+        };
+
+        // A bundle that parses but holds nothing fails validation
+        let empty = download("v2", r#"{ "resourceType": "Bundle", "entry": [] }"#);
+        let err = import_latest(&storage, &searcher, "valuesets", None, None).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("has no ValueSets"), "{:#}", err);
+        unchanged(&storage);
+        assert!(!storage.get_version(empty).unwrap().unwrap().imported);
+        let read_txn = storage.database().begin_read().unwrap();
+        let staged = read_txn.list_tables().unwrap().filter(|t| t.name().ends_with(&format!("@v{}", empty))).count();
+        assert_eq!(staged, 0, "Staging tables are discarded");
+        drop(read_txn);
+
+        // A file that fails to parse leaves nothing behind either
+        let broken = download("v3", r#"{ "resourceType": "Bundle", "entry": [ {"#);
+        assert!(import_latest(&storage, &searcher, "valuesets", None, None).await.is_err());
+        unchanged(&storage);
+        assert!(!storage.get_version(broken).unwrap().unwrap().imported);
+        assert_eq!(searcher.index_bytes(ContentFamily::ValueSets, Some(broken)).unwrap(), 0);
+
+        // A complete release is kept beside the live one
+        let v4 = download("v4", &bundle("http://example.org/vs/diabetes", "Diabetes disorders"));
+        let message = import_latest(&storage, &searcher, "valuesets", None, None).await.unwrap();
+        assert!(message.contains("beside the live release"), "{}", message);
+        unchanged(&storage);
+        assert!(storage.get_valueset("http://example.org/vs/diabetes").unwrap().is_none());
+        let release = storage.select_release(ContentFamily::ValueSets, Some("v4")).unwrap();
+        assert_eq!(release.release(), Some(v4));
+        assert!(release.get_valueset("http://example.org/vs/diabetes").unwrap().is_some());
+
+        // Promotion swaps the releases' own search indexes instead of rebuilding them
+        let v4_index = searcher.index_bytes(ContentFamily::ValueSets, Some(v4)).unwrap();
+        assert!(v4_index > 0);
+        promote_release(&storage, &searcher, v4).unwrap();
+        assert_eq!(searcher.index_bytes(ContentFamily::ValueSets, None).unwrap(), v4_index);
+        assert_eq!(searcher.index_bytes(ContentFamily::ValueSets, Some(v4)).unwrap(), 0);
+        assert!(searcher.search_valuesets("asthma", 10).unwrap().is_empty());
+        assert_eq!(searcher.search_valuesets("diabetes", 10).unwrap().len(), 1);
+        let demoted = searcher.for_release(v1).unwrap();
+        assert_eq!(demoted.search_valuesets("asthma", 10).unwrap().len(), 1);
+
+        // Promoting back swaps them again, closing the demoted release's open index first
+        promote_release(&storage, &searcher, v1).unwrap();
+        assert_eq!(searcher.search_valuesets("asthma", 10).unwrap().len(), 1);
+        assert!(demoted.search_valuesets("asthma", 10).is_err(), "The moved index was closed");
+        assert_eq!(searcher.for_release(v4).unwrap().search_valuesets("diabetes", 10).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_local_file() {
+        let (dir, storage) = test_storage();
+        let searcher = TerminologySearch::new(&dir.path().join("index")).expect("Failed to create indexes");
+        let jobs = JobRegistry::new(&storage).unwrap();
+
+        let usb = dir.path().join("usb");
+        std::fs::create_dir_all(&usb).unwrap();
+        let path = usb.join("ncts_valuesets.json");
+        let contents = bundle("http://example.org/vs/asthma", "Asthma disorders")
+            .replacen(r#""resourceType": "Bundle","#, r#""resourceType": "Bundle", "timestamp": "2025-03-01T10:00:00Z","#, 1);
+        std::fs::write(&path, contents).unwrap();
+
+        let mut started = None;
+        let message = import_local_file(&storage, &searcher, &jobs, &path, None, |id| started = Some(id))
+            .await
+            .unwrap();
+        assert!(message.starts_with("Successfully imported valuesets"), "{}", message);
+        assert!(started.is_some());
+
+        let version = storage.get_latest("valuesets").unwrap().unwrap();
+        assert_eq!(version.version, "2025-03-01T10:00:00Z");
+        assert_eq!(version.effective_date.as_deref(), Some("2025-03-01"));
+        assert!(version.imported && version.sha256_hash.is_some());
+        assert!(version.download_url.starts_with("file://"), "{}", version.download_url);
+        let stored = version.file_path.unwrap();
+        assert!(stored.starts_with(&*dir.path().join("data").to_string_lossy()), "{}", stored);
+        assert!(storage.get_valueset("http://example.org/vs/asthma").unwrap().is_some());
+
+        // The media can be removed; importing the same file again is a no-op
+        std::fs::copy(&path, usb.join("copy.json")).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(std::path::Path::new(&stored).exists());
+        let message = import_local_file(&storage, &searcher, &jobs, &usb.join("copy.json"), None, |_| {})
+            .await
+            .unwrap();
+        assert!(message.contains("already imported"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_reimport_side_by_side_file() {
+        let (dir, storage) = test_storage();
+        let searcher = TerminologySearch::new(&dir.path().join("index")).expect("Failed to create indexes");
+        let jobs = JobRegistry::new(&storage).unwrap();
+
+        let release = |name: &str, url: &str, title: &str, timestamp: &str| {
+            let path = dir.path().join(name);
+            let contents = bundle(url, title).replacen(
+                r#""resourceType": "Bundle","#,
+                &format!(r#""resourceType": "Bundle", "timestamp": "{}","#, timestamp),
+                1,
+            );
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let v1 = release("v1.json", "http://example.org/vs/asthma", "Asthma", "2025-03-01T10:00:00Z");
+        let v2 = release("v2.json", "http://example.org/vs/diabetes", "Diabetes", "2025-04-01T10:00:00Z");
+
+        import_local_file(&storage, &searcher, &jobs, &v1, None, |_| {}).await.unwrap();
+        let message = import_local_file(&storage, &searcher, &jobs, &v2, None, |_| {}).await.unwrap();
+        assert!(message.contains("beside the live release"), "{}", message);
+        let live = storage.live_release(ContentFamily::ValueSets).unwrap();
+
+        // Importing the side-by-side file again makes it the latest without promoting it
+        let message = import_local_file(&storage, &searcher, &jobs, &v2, None, |_| {}).await.unwrap();
+        assert!(message.contains("already imported"), "{}", message);
+        assert_eq!(storage.get_latest("valuesets").unwrap().unwrap().version, "2025-04-01T10:00:00Z");
+        assert_eq!(storage.live_release(ContentFamily::ValueSets).unwrap(), live);
+        assert!(storage.get_valueset("http://example.org/vs/asthma").unwrap().is_some());
+        assert!(storage.get_valueset("http://example.org/vs/diabetes").unwrap().is_none());
+
+        let results = searcher.search_valuesets("Asthma", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].code, "http://example.org/vs/asthma");
+        assert!(searcher.search_valuesets("Diabetes", 10).unwrap().is_empty());
+    }
+}